@@ -109,7 +109,7 @@ mod tests {
 
         // calculate johns chance to win against paul
         let chance = expected_score(john, paul);
-        assert!(chance >= 0.0 && chance <= 1.0);
+        assert!((0.0..=1.0).contains(&chance));
         println!("johns chance to win against paul: {}", chance)
     }
 
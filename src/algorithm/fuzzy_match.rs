@@ -0,0 +1,106 @@
+//! Typo tolerant matching of names by their trigrams, as in the `pg_trgm` of PostgreSQL.
+//!
+//! Each word of a name is padded with two spaces in front and one behind, and cut
+//! into the runs of three characters, e.g. `"bo"` into `"  b"`, `" bo"` and `"bo "`.
+//! A typo only spoils the few trigrams around it, so the misspelled names still
+//! share most of their trigrams with the right one.
+
+/// Lowercases the text and splits it into words by anything but letters and digits.
+pub fn normalize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Gets the distinct trigrams of the text, sorted.
+pub fn trigrams(text: &str) -> Vec<String> {
+    let mut trigrams = Vec::new();
+    for word in normalize(text) {
+        let padded: Vec<char> = format!("  {} ", word).chars().collect();
+        for window in padded.windows(3) {
+            trigrams.push(window.iter().collect::<String>());
+        }
+    }
+    trigrams.sort();
+    trigrams.dedup();
+    trigrams
+}
+
+/// Gets the share of the query's trigrams found in the text, from 0.0 to 1.0.
+///
+/// A query matching a word of the text is as similar as one matching the whole.
+pub fn similarity(query: &str, text: &str) -> f64 {
+    let query_trigrams = trigrams(query);
+    if query_trigrams.is_empty() {
+        return 0.0;
+    }
+
+    let text_trigrams = trigrams(text);
+    let shared_cnt = query_trigrams
+        .iter()
+        .filter(|trigram| text_trigrams.binary_search(trigram).is_ok())
+        .count();
+    shared_cnt as f64 / query_trigrams.len() as f64
+}
+
+/// Gets the normalized text from each of its words on, e.g. `"Scarlett Johansson"` into
+/// `"scarlett johansson"` and `"johansson"`. The prefixes of the text and of its words
+/// are the prefixes of these, so they are indexed for the prefix search.
+pub fn word_suffixes(text: &str) -> Vec<String> {
+    let words = normalize(text);
+    let mut suffixes: Vec<String> = (0..words.len()).map(|idx| words[idx..].join(" ")).collect();
+    suffixes.sort();
+    suffixes.dedup();
+    suffixes
+}
+
+/// Gets the query normalized as the `word_suffixes` it's a prefix of.
+pub fn normalize_prefix(query: &str) -> String {
+    normalize(query).join(" ")
+}
+
+/// Whether the text, or one of its words, starts with the query, ignoring the case.
+pub fn is_prefix(query: &str, text: &str) -> bool {
+    let query = normalize_prefix(query);
+    if query.is_empty() {
+        return false;
+    }
+
+    word_suffixes(text)
+        .iter()
+        .any(|suffix| suffix.starts_with(&query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert_eq!(
+            normalize(" Scarlett  JOHANSSON-2 "),
+            vec!["scarlett", "johansson", "2"]
+        );
+        assert_eq!(trigrams("Bo bo"), vec!["  b", " bo", "bo "]);
+        assert!(trigrams(" - ").is_empty());
+        assert_eq!(
+            word_suffixes("Scarlett JOHANSSON"),
+            vec!["johansson", "scarlett johansson"]
+        );
+        assert_eq!(normalize_prefix(" Scarlett-Jo"), "scarlett jo");
+
+        assert!(is_prefix("scar", "Scarlett Johansson"));
+        assert!(is_prefix("JOHAN", "Scarlett Johansson"));
+        assert!(is_prefix("scarlett jo", "Scarlett Johansson"));
+        assert!(!is_prefix("carlett", "Scarlett Johansson"));
+        assert!(!is_prefix("", "Scarlett Johansson"));
+
+        assert_eq!(similarity("Scarlett", "Scarlett Johansson"), 1.0);
+        // A typo keeps most of the trigrams
+        assert!(similarity("scralett", "Scarlett Johansson") >= 0.5);
+        assert!(similarity("scarlet johanson", "Scarlett Johansson") >= 0.5);
+        assert!(similarity("scralett", "Scott") < 0.5);
+        assert_eq!(similarity("", "Scott"), 0.0);
+    }
+}
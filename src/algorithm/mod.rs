@@ -1,2 +1,3 @@
 pub mod elo_rating;
+pub mod fuzzy_match;
 mod k_factor;
@@ -0,0 +1,94 @@
+use std::io;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::config::settings::SettingsArgs;
+use crate::repository::Repositories;
+use crate::service::import_service::ImportOptions;
+use crate::service::{archive_service, import_service};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(flatten)]
+    pub settings: SettingsArgs,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Runs the http server, the default command
+    Serve,
+    /// Imports face_infos from a csv/json manifest of star names and image paths
+    Import {
+        /// The directory the image paths are relative to
+        #[arg(long)]
+        dir: PathBuf,
+        /// The manifest file, either .csv with a `star_name,image_path` header or .json
+        #[arg(long)]
+        manifest: PathBuf,
+        /// Approves the imported face_infos instead of queueing them for moderation
+        #[arg(long)]
+        approve: bool,
+        /// The creator recorded on the imported documents
+        #[arg(long, default_value = "import")]
+        creator: String,
+    },
+    /// Exports face_infos, file_resources, rating_logs and local files into a tar archive
+    ExportArchive {
+        /// The archive file to write
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Imports a tar archive written by `export-archive` into an empty deployment
+    ImportArchive {
+        /// The archive file to read
+        #[arg(long)]
+        input: PathBuf,
+    },
+}
+
+/// Imports the face_infos and prints the report as json,
+/// fails if the manifest is invalid or any row failed.
+pub async fn import(
+    repos: &Repositories,
+    dir: PathBuf,
+    manifest: PathBuf,
+    options: ImportOptions,
+) -> io::Result<()> {
+    let report = import_service::import_face_infos(repos, &dir, &manifest, &options)
+        .await
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err.to_string()))?;
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if report.failed > 0 {
+        return Err(io::Error::other(format!(
+            "{} of {} rows failed to import",
+            report.failed, report.total
+        )));
+    }
+    Ok(())
+}
+
+/// Exports the dataset archive and prints the summary as json.
+pub async fn export_archive(repos: &Repositories, output: PathBuf) -> io::Result<()> {
+    let summary = archive_service::export_archive(repos, &output)
+        .await
+        .map_err(|err| io::Error::other(err.to_string()))?;
+
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    Ok(())
+}
+
+/// Imports the dataset archive and prints the summary as json.
+pub async fn import_archive(repos: &Repositories, input: PathBuf) -> io::Result<()> {
+    let summary = archive_service::import_archive(repos, &input)
+        .await
+        .map_err(|err| io::Error::other(err.to_string()))?;
+
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    Ok(())
+}
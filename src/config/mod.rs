@@ -1,9 +1,170 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, RwLock};
+
+use lazy_static::lazy_static;
+
+use crate::config::settings::{Settings, SettingsArgs, SettingsError, SettingsReload};
+
+pub mod settings;
+
+/// Config file, `facemash.toml` is read if present
+pub static CONFIG_FILE: &str = "FACEMASH_CONFIG";
+pub const DEFAULT_CONFIG_FILE: &str = "facemash.toml";
+
+/// Server config
+pub static SERVER_HOST: &str = "SERVER_HOST";
+pub const DEFAULT_SERVER_HOST: &str = "0.0.0.0";
+pub static SERVER_PORT: &str = "SERVER_PORT";
+pub const DEFAULT_SERVER_PORT: u16 = 8080;
+/// How long the in-flight votes and uploads are drained on shutdown
+pub static SHUTDOWN_TIMEOUT_SECS: &str = "SHUTDOWN_TIMEOUT_SECS";
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
 /// Mongo config
 pub static MONGODB_URI: &str = "MONGODB_URI";
 
-/// Logger level
+/// Logger level, or `RUST_LOG` style directives
 pub static LOG_LEVEL: &str = "LOG_LEVEL";
+/// Read if `LOG_LEVEL` is not set
+pub static RUST_LOG: &str = "RUST_LOG";
+pub const DEFAULT_LOG_LEVEL: &str = "INFO";
+/// Logger format, `text` or `json`
+pub static LOG_FORMAT: &str = "LOG_FORMAT";
+/// The comma separated fields whose values are masked in the logs
+pub static LOG_REDACT_FIELDS: &str = "LOG_REDACT_FIELDS";
+pub const DEFAULT_LOG_REDACT_FIELDS: [&str; 8] = [
+    "voter",
+    "reporter",
+    "comment",
+    "password",
+    "token",
+    "secret",
+    "authorization",
+    "cookie",
+];
+/// Whether the logs are printed to stdout, `true` or `false`
+pub static LOG_STDOUT: &str = "LOG_STDOUT";
+/// The log file, the logs are only printed to stdout if empty
+pub static LOG_FILE: &str = "LOG_FILE";
+/// The size in bytes the log file is rotated at, 0 never rotates by size
+pub static LOG_FILE_MAX_SIZE: &str = "LOG_FILE_MAX_SIZE";
+pub const DEFAULT_LOG_FILE_MAX_SIZE: u64 = 100 * 1024 * 1024;
+/// How often the log file is rotated, `never`, `hourly` or `daily`
+pub static LOG_FILE_ROTATION: &str = "LOG_FILE_ROTATION";
+/// How many rotated log files are kept
+pub static LOG_FILE_RETENTION: &str = "LOG_FILE_RETENTION";
+pub const DEFAULT_LOG_FILE_RETENTION: usize = 7;
+/// Whether the rotated log files are gzipped, `true` or `false`
+pub static LOG_FILE_COMPRESS: &str = "LOG_FILE_COMPRESS";
 
 /// SnowFlake config
 pub static SNOWFLAKE_MACHINE_ID: &str = "SNOWFLAKE_MACHINE_ID";
 pub static SNOWFLAKE_NODE_ID: &str = "SNOWFLAKE_NODE_ID";
+
+/// Rating config
+pub static RATING_K_FACTOR: &str = "RATING_K_FACTOR";
+pub static RATING_FIXED_K: &str = "RATING_FIXED_K";
+pub const DEFAULT_RATING_FIXED_K: u64 = 32;
+pub static RATING_INITIAL_SCORE: &str = "RATING_INITIAL_SCORE";
+
+/// Limits config
+pub static SAMPLE_SIZE: &str = "SAMPLE_SIZE";
+pub const DEFAULT_SAMPLE_SIZE: i64 = 2;
+pub static MAX_SAMPLE_SIZE: &str = "MAX_SAMPLE_SIZE";
+pub const DEFAULT_MAX_SAMPLE_SIZE: i64 = 20;
+pub static MAX_UPLOAD_BYTES: &str = "MAX_UPLOAD_BYTES";
+pub const DEFAULT_MAX_UPLOAD_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Purge config
+pub static PURGE_RETENTION_DAYS: &str = "PURGE_RETENTION_DAYS";
+pub const DEFAULT_PURGE_RETENTION_DAYS: i64 = 30;
+pub const MAX_PURGE_RETENTION_DAYS: i64 = 100 * 365;
+
+/// Report config
+pub static REPORT_HIDE_THRESHOLD: &str = "REPORT_HIDE_THRESHOLD";
+pub const DEFAULT_REPORT_HIDE_THRESHOLD: u64 = 3;
+
+/// Tracing config, the spans are only exported if the OTLP endpoint is set
+pub static OTEL_EXPORTER_OTLP_ENDPOINT: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+pub static OTEL_SERVICE_NAME: &str = "OTEL_SERVICE_NAME";
+pub const DEFAULT_OTEL_SERVICE_NAME: &str = "facemash-backend";
+/// The ratio of the traces sampled, 0.0 to 1.0
+pub static TRACING_SAMPLE_RATIO: &str = "TRACING_SAMPLE_RATIO";
+
+/// Admin config, the bearer token of the `/admin`, `/moderation` and delete/restore/update routes, which are refused if it's empty
+pub static ADMIN_TOKEN: &str = "ADMIN_TOKEN";
+/// Import config, the directory the manifests and the images imported over http must be in,
+/// the import over http is refused if it's empty
+pub static IMPORT_ROOT: &str = "IMPORT_ROOT";
+
+/// Startup config, the dependencies unavailable at startup are retried with backoff
+/// up to the attempts, 0 retries forever
+pub static STARTUP_MAX_ATTEMPTS: &str = "STARTUP_MAX_ATTEMPTS";
+pub const DEFAULT_STARTUP_MAX_ATTEMPTS: u32 = 10;
+pub static STARTUP_INITIAL_BACKOFF_MS: &str = "STARTUP_INITIAL_BACKOFF_MS";
+pub const DEFAULT_STARTUP_INITIAL_BACKOFF_MS: u64 = 500;
+pub static STARTUP_MAX_BACKOFF_MS: &str = "STARTUP_MAX_BACKOFF_MS";
+pub const DEFAULT_STARTUP_MAX_BACKOFF_MS: u64 = 30_000;
+
+/// Storage config, `mongo` or `sql`
+pub static STORAGE_BACKEND: &str = "STORAGE_BACKEND";
+/// The sqlx url of the `sql` storage backend, e.g. `sqlite://facemash.db?mode=rwc`
+pub static DATABASE_URL: &str = "DATABASE_URL";
+/// The directory of the uploaded files
+pub static SAVE_DIR: &str = "SAVE_DIR";
+pub const DEFAULT_SAVE_DIR: &str = "./tmp";
+
+lazy_static! {
+    /// The settings in use, read from the env until `init` is called.
+    static ref SETTINGS: RwLock<Arc<Settings>> =
+        RwLock::new(Arc::new(Settings::from_env().unwrap_or_default()));
+    /// The flags the settings were loaded with, applied again on reload.
+    static ref SETTINGS_ARGS: RwLock<SettingsArgs> = RwLock::new(SettingsArgs::default());
+    /// The vars of the `.env` file at startup, which were copied into the env.
+    static ref DOTENV_VARS: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+}
+
+/// Replaces the settings in use, the settings are expected to be validated.
+pub fn init(settings: Settings, args: SettingsArgs) {
+    *SETTINGS.write().unwrap() = Arc::new(settings);
+    *SETTINGS_ARGS.write().unwrap() = args;
+    *DOTENV_VARS.write().unwrap() = read_dotenv();
+}
+
+/// Reads the vars of the `.env` file, empty if there is no such file.
+fn read_dotenv() -> HashMap<String, String> {
+    // `dotenv::dotenv` never overrides the vars already set, so iterate the file instead
+    #[allow(deprecated)]
+    dotenv::dotenv_iter()
+        .map(|vars| vars.filter_map(Result::ok).collect())
+        .unwrap_or_default()
+}
+
+/// Gets the settings in use.
+///
+/// The settings are a snapshot, a request keeps the one it started with
+/// even if the settings are reloaded meanwhile.
+pub fn get() -> Arc<Settings> {
+    SETTINGS.read().unwrap().clone()
+}
+
+/// Loads the settings again like at startup, with the `.env` file read again,
+/// and replaces the reloadable sections of the settings in use.
+///
+/// The vars set outside the `.env` file still take precedence over it.
+pub fn reload() -> Result<SettingsReload, SettingsError> {
+    let startup_dotenv_vars = DOTENV_VARS.read().unwrap().clone();
+    let dotenv_vars = read_dotenv();
+    let reloaded = Settings::load_with(&SETTINGS_ARGS.read().unwrap(), |name| {
+        match env::var(name) {
+            Ok(value) if startup_dotenv_vars.get(name) != Some(&value) => Some(value),
+            _ => dotenv_vars.get(name).cloned(),
+        }
+    })?;
+
+    let mut settings = SETTINGS.write().unwrap();
+    let reload = settings.merge_reloadable(&reloaded);
+    *settings = Arc::new(reload.settings.clone());
+    Ok(reload)
+}
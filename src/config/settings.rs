@@ -0,0 +1,868 @@
+//! The typed settings, loaded from the config file, then overridden by the
+//! env vars and the command line flags, and validated before anything starts.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{env, fmt, fs, io};
+
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::*;
+use crate::entity::face_info::DEFAULT_SCORE;
+use crate::logger::filter::LogFilter;
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(PathBuf, io::Error),
+    Toml(PathBuf, toml::de::Error),
+    /// An env var which can't be parsed
+    Env {
+        name: String,
+        value: String,
+    },
+    /// The problems found by the validation, one per line
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(path, err) => write!(f, "failed to read {:?}: {}", path, err),
+            SettingsError::Toml(path, err) => write!(f, "failed to parse {:?}: {}", path, err),
+            SettingsError::Env { name, value } => {
+                write!(f, "invalid value of env var {}: {:?}", name, value)
+            }
+            SettingsError::Invalid(problems) => {
+                write!(f, "invalid settings:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Mongo,
+    Sql,
+}
+
+/// How the log lines are printed.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Colored lines for the terminal
+    #[default]
+    Text,
+    /// A json object per line for the log pipeline
+    Json,
+}
+
+/// How often the log file is rotated regardless of its size, in UTC.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Never,
+    Hourly,
+    #[default]
+    Daily,
+}
+
+/// How the K factor of a vote is chosen, see `algorithm::k_factor`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum KFactor {
+    #[default]
+    Uscf,
+    Fide,
+    Icc,
+    /// `rating.fixed_k` for every vote
+    Fixed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    pub host: String,
+    pub port: u16,
+    /// How long the in-flight votes and uploads are drained on SIGTERM or SIGINT
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        ServerSettings {
+            host: DEFAULT_SERVER_HOST.to_string(),
+            port: DEFAULT_SERVER_PORT,
+            shutdown_timeout_secs: DEFAULT_SHUTDOWN_TIMEOUT_SECS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MongoSettings {
+    pub uri: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSettings {
+    pub backend: StorageBackend,
+    /// The sqlx url of the `sql` backend
+    pub database_url: String,
+    /// The directory of the uploaded files
+    pub save_dir: String,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        StorageSettings {
+            backend: StorageBackend::default(),
+            database_url: String::new(),
+            save_dir: DEFAULT_SAVE_DIR.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct RatingSettings {
+    pub k_factor: KFactor,
+    /// The K factor of `k_factor = "fixed"`
+    pub fixed_k: u64,
+    /// The score of a new face_info
+    pub initial_score: f64,
+}
+
+impl Default for RatingSettings {
+    fn default() -> Self {
+        RatingSettings {
+            k_factor: KFactor::default(),
+            fixed_k: DEFAULT_RATING_FIXED_K,
+            initial_score: DEFAULT_SCORE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsSettings {
+    /// The number of face_infos sampled when the request doesn't say
+    pub sample_size: i64,
+    pub max_sample_size: i64,
+    /// The max size of an uploaded file
+    pub max_upload_bytes: u64,
+}
+
+impl Default for LimitsSettings {
+    fn default() -> Self {
+        LimitsSettings {
+            sample_size: DEFAULT_SAMPLE_SIZE,
+            max_sample_size: DEFAULT_MAX_SAMPLE_SIZE,
+            max_upload_bytes: DEFAULT_MAX_UPLOAD_BYTES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingSettings {
+    /// ERROR, WARN, INFO, DEBUG, TRACE or OFF, or `RUST_LOG` style directives
+    /// like `info,facemash_backend::controller=debug,mongodb=warn`, see `logger::filter`
+    pub level: String,
+    pub format: LogFormat,
+    /// The fields whose values are masked in the logs, case insensitive
+    pub redact_fields: Vec<String>,
+    pub stdout: bool,
+    pub file: LogFileSettings,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        LoggingSettings {
+            level: DEFAULT_LOG_LEVEL.to_string(),
+            format: LogFormat::default(),
+            redact_fields: DEFAULT_LOG_REDACT_FIELDS
+                .iter()
+                .map(|field| field.to_string())
+                .collect(),
+            stdout: true,
+            file: LogFileSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogFileSettings {
+    /// The log file, no file is written if empty
+    pub path: String,
+    /// The size in bytes the file is rotated at, 0 never rotates by size
+    pub max_size: u64,
+    pub rotation: LogRotation,
+    /// How many rotated files are kept, the oldest are removed first
+    pub retention: usize,
+    /// Gzips the rotated files
+    pub compress: bool,
+}
+
+impl Default for LogFileSettings {
+    fn default() -> Self {
+        LogFileSettings {
+            path: "".to_string(),
+            max_size: DEFAULT_LOG_FILE_MAX_SIZE,
+            rotation: LogRotation::default(),
+            retention: DEFAULT_LOG_FILE_RETENTION,
+            compress: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SnowflakeSettings {
+    /// Required, 0 to 31
+    pub machine_id: Option<i32>,
+    /// Required, 0 to 31
+    pub node_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModerationSettings {
+    /// The number of distinct reporters which hides a face_info, 0 never hides
+    pub report_hide_threshold: u64,
+}
+
+impl Default for ModerationSettings {
+    fn default() -> Self {
+        ModerationSettings {
+            report_hide_threshold: DEFAULT_REPORT_HIDE_THRESHOLD,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PurgeSettings {
+    /// Documents soft deleted within this many days are kept
+    pub retention_days: i64,
+}
+
+impl Default for PurgeSettings {
+    fn default() -> Self {
+        PurgeSettings {
+            retention_days: DEFAULT_PURGE_RETENTION_DAYS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminSettings {
+    /// The bearer token of the `/admin`, `/moderation` and delete/restore/update routes, they are refused if empty
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImportSettings {
+    /// The directory the manifests and the images imported over http must be in,
+    /// the import over http is refused if empty
+    pub root: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TracingSettings {
+    /// The OTLP/HTTP collector, e.g. `http://localhost:4318`, no span is exported if empty
+    pub otlp_endpoint: String,
+    pub service_name: String,
+    /// The ratio of the traces sampled, the parent's decision is followed if propagated
+    pub sample_ratio: f64,
+}
+
+impl Default for TracingSettings {
+    fn default() -> Self {
+        TracingSettings {
+            otlp_endpoint: "".to_string(),
+            service_name: DEFAULT_OTEL_SERVICE_NAME.to_string(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StartupSettings {
+    /// The attempts to reach a dependency before giving up, 0 retries forever
+    pub max_attempts: u32,
+    /// The delay before the first retry, doubled by every retry
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for StartupSettings {
+    fn default() -> Self {
+        StartupSettings {
+            max_attempts: DEFAULT_STARTUP_MAX_ATTEMPTS,
+            initial_backoff_ms: DEFAULT_STARTUP_INITIAL_BACKOFF_MS,
+            max_backoff_ms: DEFAULT_STARTUP_MAX_BACKOFF_MS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Increased by every reload, 0 for the settings loaded at startup
+    #[serde(skip)]
+    pub version: u64,
+    /// When the settings were loaded, in seconds
+    #[serde(skip)]
+    pub loaded_on: i64,
+    pub server: ServerSettings,
+    pub mongo: MongoSettings,
+    pub storage: StorageSettings,
+    pub rating: RatingSettings,
+    pub limits: LimitsSettings,
+    pub logging: LoggingSettings,
+    pub snowflake: SnowflakeSettings,
+    pub moderation: ModerationSettings,
+    pub purge: PurgeSettings,
+    pub admin: AdminSettings,
+    pub import: ImportSettings,
+    pub tracing: TracingSettings,
+    pub startup: StartupSettings,
+}
+
+/// The result of merging the reloaded settings into the settings in use.
+#[derive(Debug, Clone)]
+pub struct SettingsReload {
+    pub settings: Settings,
+    pub changed: Vec<&'static str>,
+    /// The sections which changed but are only read at startup
+    pub ignored: Vec<&'static str>,
+}
+
+/// The command line flags overriding the settings.
+#[derive(Debug, Clone, Default, Args)]
+pub struct SettingsArgs {
+    /// The TOML config file, defaults to the `FACEMASH_CONFIG` env var or `facemash.toml`
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// The address the http server binds
+    #[arg(long, global = true)]
+    pub host: Option<String>,
+    /// The port the http server binds
+    #[arg(long, global = true)]
+    pub port: Option<u16>,
+    /// ERROR, WARN, INFO, DEBUG, TRACE or OFF, or directives like `info,mongodb=warn`
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+    #[arg(long, global = true, value_enum)]
+    pub log_format: Option<LogFormat>,
+    #[arg(long, global = true, value_enum)]
+    pub storage_backend: Option<StorageBackend>,
+}
+
+impl Settings {
+    /// Loads the config file, applies the env vars and the flags, then validates.
+    pub fn load(args: &SettingsArgs) -> Result<Settings, SettingsError> {
+        Settings::load_with(args, |name| env::var(name).ok())
+    }
+
+    /// Like `load`, with the env vars looked up by `var`.
+    pub fn load_with<F>(args: &SettingsArgs, var: F) -> Result<Settings, SettingsError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut settings = match &args.config {
+            Some(path) => Settings::from_file(path)?,
+            None => match var(CONFIG_FILE) {
+                Some(path) => Settings::from_file(Path::new(&path))?,
+                None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                    Settings::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+                }
+                None => Settings::default(),
+            },
+        };
+        settings.apply_env(var)?;
+        settings.apply_args(args);
+        settings.validate()?;
+        settings.loaded_on = chrono::Utc::now().timestamp();
+        Ok(settings)
+    }
+
+    /// Takes the reloadable sections from the reloaded settings, which are the
+    /// rating, limits, logging and moderation ones.
+    ///
+    /// Returns the merged settings with the version increased, the reloadable sections
+    /// which changed, and the other sections which changed but need a restart.
+    pub fn merge_reloadable(&self, reloaded: &Settings) -> SettingsReload {
+        let mut settings = self.clone();
+        settings.version = self.version + 1;
+        settings.loaded_on = reloaded.loaded_on;
+
+        let mut changed = vec![];
+        if self.rating != reloaded.rating {
+            settings.rating = reloaded.rating.clone();
+            changed.push("rating");
+        }
+        if self.limits != reloaded.limits {
+            settings.limits = reloaded.limits.clone();
+            changed.push("limits");
+        }
+        if self.logging != reloaded.logging {
+            settings.logging = reloaded.logging.clone();
+            changed.push("logging");
+        }
+        if self.moderation != reloaded.moderation {
+            settings.moderation = reloaded.moderation.clone();
+            changed.push("moderation");
+        }
+
+        let mut ignored = vec![];
+        for (section, is_changed) in [
+            ("server", self.server != reloaded.server),
+            ("mongo", self.mongo != reloaded.mongo),
+            ("storage", self.storage != reloaded.storage),
+            ("snowflake", self.snowflake != reloaded.snowflake),
+            ("purge", self.purge != reloaded.purge),
+            ("admin", self.admin != reloaded.admin),
+            ("import", self.import != reloaded.import),
+            ("tracing", self.tracing != reloaded.tracing),
+            ("startup", self.startup != reloaded.startup),
+        ] {
+            if is_changed {
+                ignored.push(section);
+            }
+        }
+
+        SettingsReload {
+            settings,
+            changed,
+            ignored,
+        }
+    }
+
+    /// The defaults overridden by the env vars, not validated.
+    pub fn from_env() -> Result<Settings, SettingsError> {
+        let mut settings = Settings::default();
+        settings.apply_env(|name| env::var(name).ok())?;
+        Ok(settings)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Settings, SettingsError> {
+        let content =
+            fs::read_to_string(path).map_err(|err| SettingsError::Io(path.to_path_buf(), err))?;
+        toml::from_str(&content).map_err(|err| SettingsError::Toml(path.to_path_buf(), err))
+    }
+
+    /// Overrides the settings by the env vars looked up by `var`.
+    pub fn apply_env<F>(&mut self, var: F) -> Result<(), SettingsError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        fn parse<T: FromStr>(name: &str, value: String) -> Result<T, SettingsError> {
+            value.trim().parse::<T>().map_err(|_| SettingsError::Env {
+                name: name.to_string(),
+                value,
+            })
+        }
+
+        fn parse_enum<T: for<'de> Deserialize<'de>>(
+            name: &str,
+            value: String,
+        ) -> Result<T, SettingsError> {
+            serde_json::from_value(serde_json::Value::String(value.trim().to_lowercase())).map_err(
+                |_| SettingsError::Env {
+                    name: name.to_string(),
+                    value,
+                },
+            )
+        }
+
+        if let Some(value) = var(SERVER_HOST) {
+            self.server.host = value;
+        }
+        if let Some(value) = var(SERVER_PORT) {
+            self.server.port = parse(SERVER_PORT, value)?;
+        }
+        if let Some(value) = var(SHUTDOWN_TIMEOUT_SECS) {
+            self.server.shutdown_timeout_secs = parse(SHUTDOWN_TIMEOUT_SECS, value)?;
+        }
+        if let Some(value) = var(MONGODB_URI) {
+            self.mongo.uri = value;
+        }
+        if let Some(value) = var(STORAGE_BACKEND) {
+            self.storage.backend = parse_enum(STORAGE_BACKEND, value)?;
+        }
+        if let Some(value) = var(DATABASE_URL) {
+            self.storage.database_url = value;
+        }
+        if let Some(value) = var(SAVE_DIR) {
+            self.storage.save_dir = value;
+        }
+        if let Some(value) = var(RATING_K_FACTOR) {
+            self.rating.k_factor = parse_enum(RATING_K_FACTOR, value)?;
+        }
+        if let Some(value) = var(RATING_FIXED_K) {
+            self.rating.fixed_k = parse(RATING_FIXED_K, value)?;
+        }
+        if let Some(value) = var(RATING_INITIAL_SCORE) {
+            self.rating.initial_score = parse(RATING_INITIAL_SCORE, value)?;
+        }
+        if let Some(value) = var(SAMPLE_SIZE) {
+            self.limits.sample_size = parse(SAMPLE_SIZE, value)?;
+        }
+        if let Some(value) = var(MAX_SAMPLE_SIZE) {
+            self.limits.max_sample_size = parse(MAX_SAMPLE_SIZE, value)?;
+        }
+        if let Some(value) = var(MAX_UPLOAD_BYTES) {
+            self.limits.max_upload_bytes = parse(MAX_UPLOAD_BYTES, value)?;
+        }
+        if let Some(value) = var(LOG_LEVEL).or_else(|| var(RUST_LOG)) {
+            self.logging.level = value;
+        }
+        if let Some(value) = var(LOG_FORMAT) {
+            self.logging.format = parse_enum(LOG_FORMAT, value)?;
+        }
+        if let Some(value) = var(LOG_REDACT_FIELDS) {
+            self.logging.redact_fields = value
+                .split(',')
+                .map(|field| field.trim().to_string())
+                .filter(|field| !field.is_empty())
+                .collect();
+        }
+        if let Some(value) = var(LOG_STDOUT) {
+            self.logging.stdout = parse(LOG_STDOUT, value)?;
+        }
+        if let Some(value) = var(LOG_FILE) {
+            self.logging.file.path = value;
+        }
+        if let Some(value) = var(LOG_FILE_MAX_SIZE) {
+            self.logging.file.max_size = parse(LOG_FILE_MAX_SIZE, value)?;
+        }
+        if let Some(value) = var(LOG_FILE_ROTATION) {
+            self.logging.file.rotation = parse_enum(LOG_FILE_ROTATION, value)?;
+        }
+        if let Some(value) = var(LOG_FILE_RETENTION) {
+            self.logging.file.retention = parse(LOG_FILE_RETENTION, value)?;
+        }
+        if let Some(value) = var(LOG_FILE_COMPRESS) {
+            self.logging.file.compress = parse(LOG_FILE_COMPRESS, value)?;
+        }
+        if let Some(value) = var(SNOWFLAKE_MACHINE_ID) {
+            self.snowflake.machine_id = Some(parse(SNOWFLAKE_MACHINE_ID, value)?);
+        }
+        if let Some(value) = var(SNOWFLAKE_NODE_ID) {
+            self.snowflake.node_id = Some(parse(SNOWFLAKE_NODE_ID, value)?);
+        }
+        if let Some(value) = var(REPORT_HIDE_THRESHOLD) {
+            self.moderation.report_hide_threshold = parse(REPORT_HIDE_THRESHOLD, value)?;
+        }
+        if let Some(value) = var(PURGE_RETENTION_DAYS) {
+            self.purge.retention_days = parse(PURGE_RETENTION_DAYS, value)?;
+        }
+        if let Some(value) = var(ADMIN_TOKEN) {
+            self.admin.token = value;
+        }
+        if let Some(value) = var(IMPORT_ROOT) {
+            self.import.root = value;
+        }
+        if let Some(value) = var(OTEL_EXPORTER_OTLP_ENDPOINT) {
+            self.tracing.otlp_endpoint = value;
+        }
+        if let Some(value) = var(OTEL_SERVICE_NAME) {
+            self.tracing.service_name = value;
+        }
+        if let Some(value) = var(TRACING_SAMPLE_RATIO) {
+            self.tracing.sample_ratio = parse(TRACING_SAMPLE_RATIO, value)?;
+        }
+        if let Some(value) = var(STARTUP_MAX_ATTEMPTS) {
+            self.startup.max_attempts = parse(STARTUP_MAX_ATTEMPTS, value)?;
+        }
+        if let Some(value) = var(STARTUP_INITIAL_BACKOFF_MS) {
+            self.startup.initial_backoff_ms = parse(STARTUP_INITIAL_BACKOFF_MS, value)?;
+        }
+        if let Some(value) = var(STARTUP_MAX_BACKOFF_MS) {
+            self.startup.max_backoff_ms = parse(STARTUP_MAX_BACKOFF_MS, value)?;
+        }
+        Ok(())
+    }
+
+    pub fn apply_args(&mut self, args: &SettingsArgs) {
+        if let Some(host) = &args.host {
+            self.server.host = host.clone();
+        }
+        if let Some(port) = args.port {
+            self.server.port = port;
+        }
+        if let Some(log_level) = &args.log_level {
+            self.logging.level = log_level.clone();
+        }
+        if let Some(log_format) = args.log_format {
+            self.logging.format = log_format;
+        }
+        if let Some(storage_backend) = args.storage_backend {
+            self.storage.backend = storage_backend;
+        }
+    }
+
+    /// Checks the settings, all the problems are reported at once.
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        let mut problems = vec![];
+
+        if self.server.host.is_empty() {
+            problems.push("server.host must not be empty".to_string());
+        }
+        if self.server.port == 0 {
+            problems.push("server.port must not be 0".to_string());
+        }
+
+        match self.storage.backend {
+            StorageBackend::Mongo if self.mongo.uri.is_empty() => problems.push(format!(
+                "mongo.uri ({}) is required by the mongo storage backend",
+                MONGODB_URI
+            )),
+            StorageBackend::Sql if self.storage.database_url.is_empty() => problems.push(format!(
+                "storage.database_url ({}) is required by the sql storage backend",
+                DATABASE_URL
+            )),
+            _ => {}
+        }
+        if self.storage.save_dir.is_empty() {
+            problems.push("storage.save_dir must not be empty".to_string());
+        }
+
+        if self.rating.k_factor == KFactor::Fixed && self.rating.fixed_k == 0 {
+            problems.push("rating.fixed_k must be positive".to_string());
+        }
+        if !(self.rating.initial_score.is_finite() && self.rating.initial_score > 0.0) {
+            problems.push("rating.initial_score must be positive".to_string());
+        }
+
+        if self.limits.sample_size <= 0 || self.limits.sample_size > self.limits.max_sample_size {
+            problems.push(format!(
+                "limits.sample_size must be within 1 to limits.max_sample_size ({}), got {}",
+                self.limits.max_sample_size, self.limits.sample_size
+            ));
+        }
+        if self.limits.max_upload_bytes == 0 {
+            problems.push("limits.max_upload_bytes must be positive".to_string());
+        }
+
+        if let Err(err) = LogFilter::parse(&self.logging.level) {
+            problems.push(format!(
+                "logging.level must be a level or directives like `info,mongodb=warn`, {}",
+                err
+            ));
+        }
+        if !self.logging.stdout && self.logging.file.path.is_empty() {
+            problems.push(format!(
+                "logging.file.path ({}) is required if logging.stdout is false",
+                LOG_FILE
+            ));
+        }
+        for field in &self.logging.redact_fields {
+            if field.is_empty()
+                || !field
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                problems.push(format!(
+                    "logging.redact_fields must be field names, got {:?}",
+                    field
+                ));
+            }
+        }
+
+        for (field, env_name, id) in [
+            (
+                "snowflake.machine_id",
+                SNOWFLAKE_MACHINE_ID,
+                self.snowflake.machine_id,
+            ),
+            (
+                "snowflake.node_id",
+                SNOWFLAKE_NODE_ID,
+                self.snowflake.node_id,
+            ),
+        ] {
+            match id {
+                None => problems.push(format!("{} ({}) is required", field, env_name)),
+                Some(id) if !(0..=31).contains(&id) => {
+                    problems.push(format!("{} must be within 0 to 31, got {}", field, id))
+                }
+                Some(_) => {}
+            }
+        }
+
+        if !(0..=MAX_PURGE_RETENTION_DAYS).contains(&self.purge.retention_days) {
+            problems.push(format!(
+                "purge.retention_days must be within 0 to {}, got {}",
+                MAX_PURGE_RETENTION_DAYS, self.purge.retention_days
+            ));
+        }
+
+        if !self.tracing.otlp_endpoint.is_empty()
+            && !self.tracing.otlp_endpoint.starts_with("http://")
+            && !self.tracing.otlp_endpoint.starts_with("https://")
+        {
+            problems.push(format!(
+                "tracing.otlp_endpoint must be an http(s) url, got {:?}",
+                self.tracing.otlp_endpoint
+            ));
+        }
+        if self.tracing.service_name.is_empty() {
+            problems.push("tracing.service_name must not be empty".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.tracing.sample_ratio) {
+            problems.push(format!(
+                "tracing.sample_ratio must be within 0.0 to 1.0, got {}",
+                self.tracing.sample_ratio
+            ));
+        }
+
+        if self.startup.initial_backoff_ms > self.startup.max_backoff_ms {
+            problems.push(format!(
+                "startup.initial_backoff_ms ({}) must not exceed startup.max_backoff_ms ({})",
+                self.startup.initial_backoff_ms, self.startup.max_backoff_ms
+            ));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(SettingsError::Invalid(problems))
+        }
+    }
+}
+
+/// Parses the log level, case insensitive.
+pub fn parse_log_level(level: &str) -> Option<log::LevelFilter> {
+    match level.to_uppercase().as_str() {
+        "ERROR" => Some(log::LevelFilter::Error),
+        "WARN" => Some(log::LevelFilter::Warn),
+        "INFO" => Some(log::LevelFilter::Info),
+        "DEBUG" => Some(log::LevelFilter::Debug),
+        "TRACE" => Some(log::LevelFilter::Trace),
+        "OFF" => Some(log::LevelFilter::Off),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_load_settings() {
+        let mut settings: Settings = toml::from_str(
+            r#"
+            [server]
+            port = 9090
+
+            [mongo]
+            uri = "mongodb://localhost:27017"
+
+            [rating]
+            k_factor = "fixed"
+            fixed_k = 16
+
+            [snowflake]
+            machine_id = 1
+            "#,
+        )
+        .unwrap();
+        assert_eq!(settings.server.host, DEFAULT_SERVER_HOST);
+        assert_eq!(settings.rating.k_factor, KFactor::Fixed);
+
+        // The env vars override the file, and the flags override the env vars
+        let env = HashMap::from([
+            (SERVER_PORT, "9091"),
+            (SNOWFLAKE_NODE_ID, "2"),
+            (LOG_LEVEL, "debug"),
+        ]);
+        settings
+            .apply_env(|name| env.get(name).map(|value| value.to_string()))
+            .unwrap();
+        settings.apply_args(&SettingsArgs {
+            port: Some(9092),
+            ..SettingsArgs::default()
+        });
+        assert_eq!(settings.server.port, 9092);
+        assert_eq!(settings.snowflake.node_id, Some(2));
+        assert!(settings.validate().is_ok());
+
+        let env = HashMap::from([(SERVER_PORT, "http")]);
+        assert!(matches!(
+            settings.apply_env(|name| env.get(name).map(|value| value.to_string())),
+            Err(SettingsError::Env { .. })
+        ));
+
+        assert!(toml::from_str::<Settings>("[server]\nprot = 1").is_err());
+    }
+
+    #[test]
+    fn test_example_settings() {
+        let settings = Settings::from_file(Path::new("facemash.example.toml")).unwrap();
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        let settings = Settings {
+            storage: StorageSettings {
+                backend: StorageBackend::Sql,
+                ..StorageSettings::default()
+            },
+            logging: LoggingSettings {
+                level: "info,mongodb=LOUD".to_string(),
+                redact_fields: vec!["voter".to_string(), "".to_string()],
+                ..LoggingSettings::default()
+            },
+            snowflake: SnowflakeSettings {
+                machine_id: Some(32),
+                node_id: None,
+            },
+            ..Settings::default()
+        };
+        match settings.validate() {
+            Err(SettingsError::Invalid(problems)) => {
+                assert_eq!(problems.len(), 5, "{:?}", problems)
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_merge_reloadable_settings() {
+        let settings = Settings::default();
+        let mut reloaded = Settings::default();
+        reloaded.rating.fixed_k = 16;
+        reloaded.logging.level = "DEBUG".to_string();
+        reloaded.server.port = 9090;
+
+        let reload = settings.merge_reloadable(&reloaded);
+        assert_eq!(reload.settings.version, settings.version + 1);
+        assert_eq!(reload.changed, vec!["rating", "logging"]);
+        assert_eq!(reload.ignored, vec!["server"]);
+        assert_eq!(reload.settings.rating.fixed_k, 16);
+        assert_eq!(reload.settings.logging.level, "DEBUG");
+        // The sections only read at startup keep their values
+        assert_eq!(reload.settings.server.port, settings.server.port);
+    }
+}
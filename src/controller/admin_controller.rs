@@ -0,0 +1,341 @@
+use std::path::{Path, PathBuf};
+
+use actix_web::dev::Payload;
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::{get, post, web, FromRequest, HttpRequest, HttpResponse, Responder};
+use futures_util::future::{ready, Ready};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::config;
+use crate::config::settings::{
+    LimitsSettings, LoggingSettings, ModerationSettings, RatingSettings,
+};
+use crate::config::MAX_PURGE_RETENTION_DAYS;
+use crate::dto::{MAX_STAR_NAME_LEN, MAX_USER_NAME_LEN};
+use crate::error::{AppError, AppResult};
+use crate::repository::Repositories;
+use crate::service::admin_service::PurgeResult;
+use crate::service::import_service::{ImportOptions, ImportReport};
+use crate::service::season_service::SeasonStart;
+use crate::service::{admin_service, import_service, season_service};
+
+/// The credential of the `/admin`, `/moderation` and delete/restore/update routes,
+/// an `Authorization: Bearer <admin.token>` header.
+///
+/// Every privileged handler takes it, the routes are refused if `admin.token` is not set.
+pub struct AdminAuth;
+
+impl FromRequest for AdminAuth {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(check_admin_token(req.headers().get(header::AUTHORIZATION)).map(|_| AdminAuth))
+    }
+}
+
+fn check_admin_token(authorization: Option<&HeaderValue>) -> AppResult<()> {
+    let settings = config::get();
+    if settings.admin.token.is_empty() {
+        return Err(AppError::Forbidden(
+            "The admin routes are disabled, admin.token is not set!".to_string(),
+        ));
+    }
+
+    let token = authorization
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match token {
+        Some(token) if constant_time_eq(token.as_bytes(), settings.admin.token.as_bytes()) => {
+            Ok(())
+        }
+        _ => Err(AppError::Unauthorized(
+            "The admin token is missing or wrong!".to_string(),
+        )),
+    }
+}
+
+/// Compares without returning early, so the time taken doesn't tell how much matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct PurgeDeletedReq {
+    /// Documents soft deleted within this many days are kept,
+    /// defaults to `purge.retention_days` of the settings.
+    #[validate(range(min = 0, max = MAX_PURGE_RETENTION_DAYS))]
+    retention_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PurgeDeletedResp {
+    purge_result: PurgeResult,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ImportFaceInfosReq {
+    /// The directory the image paths are relative to, inside `import.root` on the server
+    #[schema(value_type = String)]
+    dir: PathBuf,
+    /// The manifest file, either .csv or .json, inside `import.root` on the server
+    #[schema(value_type = String)]
+    manifest: PathBuf,
+    #[serde(default)]
+    approve: bool,
+    #[validate(length(min = 1, max = MAX_USER_NAME_LEN))]
+    creator: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportFaceInfosResp {
+    import_report: ImportReport,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct StartSeasonReq {
+    #[validate(length(min = 1, max = MAX_STAR_NAME_LEN))]
+    name: String,
+    /// The share of the distance to the initial score taken off the scores,
+    /// from 0.0 to keep them to 1.0 to reset them
+    #[serde(default)]
+    #[validate(range(min = 0.0, max = 1.0))]
+    soft_reset: f64,
+    #[validate(length(min = 1, max = MAX_USER_NAME_LEN))]
+    creator: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StartSeasonResp {
+    season_start: SeasonStart,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GetSettingsResp {
+    version: u64,
+    loaded_on: i64,
+    rating: RatingSettings,
+    limits: LimitsSettings,
+    logging: LoggingSettings,
+    moderation: ModerationSettings,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReloadSettingsResp {
+    version: u64,
+    loaded_on: i64,
+    /// The reloaded sections which changed
+    changed: Vec<String>,
+    /// The sections which changed but need a restart
+    ignored: Vec<String>,
+}
+
+#[utoipa::path(
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses((status = 200, body = PurgeDeletedResp))
+)]
+#[post("/admin/purge_deleted")]
+pub async fn purge_deleted(
+    _admin: AdminAuth,
+    repos: web::Data<Repositories>,
+    req: web::Json<PurgeDeletedReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
+
+    req.validate()?;
+
+    let retention_days = req
+        .retention_days
+        .unwrap_or_else(admin_service::get_purge_retention_days);
+
+    let now = chrono::Utc::now().timestamp();
+    let deleted_before = admin_service::get_purge_deleted_before(retention_days, now)
+        .ok_or_else(|| AppError::BadRequest("retention_days is out of range!".to_string()))?;
+    let purge_result = admin_service::purge_deleted(&repos, deleted_before).await?;
+    info!("Purge deleted success, result: {:?}", purge_result);
+    Ok(HttpResponse::Ok().json(PurgeDeletedResp { purge_result }))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses((status = 200, body = ImportFaceInfosResp))
+)]
+#[post("/admin/import_face_infos")]
+pub async fn import_face_infos(
+    _admin: AdminAuth,
+    repos: web::Data<Repositories>,
+    req: web::Json<ImportFaceInfosReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
+
+    req.validate()?;
+
+    // Step 1: Both paths must be inside the import root
+    let import_root = config::get().import.root.clone();
+    if import_root.is_empty() {
+        return Err(AppError::Forbidden(
+            "The import over http is disabled, import.root is not set!".to_string(),
+        ));
+    }
+    let dir = import_service::resolve_import_path(Path::new(&import_root), &req.dir)
+        .map_err(AppError::BadRequest)?;
+    let manifest = import_service::resolve_import_path(Path::new(&import_root), &req.manifest)
+        .map_err(AppError::BadRequest)?;
+
+    // Step 2: Import the rows
+    let options = ImportOptions {
+        creator: req.creator.clone(),
+        approve: req.approve,
+    };
+    match import_service::import_face_infos(&repos, &dir, &manifest, &options).await {
+        Ok(import_report) => Ok(HttpResponse::Ok().json(ImportFaceInfosResp { import_report })),
+        Err(err) => Err(AppError::BadRequest(format!(
+            "Failed to import face_infos: {}",
+            err
+        ))),
+    }
+}
+
+/// Ends the current season, archiving its standings, and starts a new one.
+#[utoipa::path(
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses((status = 200, body = StartSeasonResp))
+)]
+#[post("/admin/start_season")]
+pub async fn start_season(
+    _admin: AdminAuth,
+    repos: web::Data<Repositories>,
+    req: web::Json<StartSeasonReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
+
+    req.validate()?;
+
+    let now = chrono::Utc::now().timestamp();
+    let season_start =
+        season_service::start_season(&repos, &req.name, req.soft_reset, &req.creator, now)
+            .await?
+            .ok_or_else(|| {
+                AppError::Conflict("The season has been started concurrently!".to_string())
+            })?;
+    Ok(HttpResponse::Ok().json(StartSeasonResp { season_start }))
+}
+
+/// Gets the version and the reloadable sections of the settings in use.
+#[utoipa::path(
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses((status = 200, body = GetSettingsResp))
+)]
+#[get("/admin/settings")]
+pub async fn get_settings(_admin: AdminAuth) -> AppResult<impl Responder> {
+    let settings = config::get();
+    Ok(HttpResponse::Ok().json(GetSettingsResp {
+        version: settings.version,
+        loaded_on: settings.loaded_on,
+        rating: settings.rating.clone(),
+        limits: settings.limits.clone(),
+        logging: settings.logging.clone(),
+        moderation: settings.moderation.clone(),
+    }))
+}
+
+#[utoipa::path(
+    tag = "admin",
+    security(("admin_token" = [])),
+    responses((status = 200, body = ReloadSettingsResp))
+)]
+#[post("/admin/reload_settings")]
+pub async fn reload_settings(_admin: AdminAuth) -> AppResult<impl Responder> {
+    info!("reload_settings start");
+
+    match admin_service::reload_settings() {
+        Ok(reload) => Ok(HttpResponse::Ok().json(ReloadSettingsResp {
+            version: reload.settings.version,
+            loaded_on: reload.settings.loaded_on,
+            changed: reload.changed.iter().map(|s| s.to_string()).collect(),
+            ignored: reload.ignored.iter().map(|s| s.to_string()).collect(),
+        })),
+        Err(err) => Err(AppError::BadRequest(format!(
+            "Failed to reload settings: {}",
+            err
+        ))),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+    use dotenv::dotenv;
+
+    use super::*;
+    use crate::config::settings::SettingsArgs;
+    use crate::controller;
+
+    /// The admin token of the tests, set in the settings they share.
+    pub const TEST_ADMIN_TOKEN: &str = "test-admin-token";
+
+    pub fn set_test_admin_token() {
+        dotenv().ok();
+        let mut settings = config::get().as_ref().clone();
+        settings.admin.token = TEST_ADMIN_TOKEN.to_string();
+        config::init(settings, SettingsArgs::default());
+    }
+
+    /// Sets the test admin token and returns the header carrying it.
+    pub fn test_admin_header() -> (header::HeaderName, String) {
+        set_test_admin_token();
+        (
+            header::AUTHORIZATION,
+            format!("Bearer {}", TEST_ADMIN_TOKEN),
+        )
+    }
+
+    #[actix_rt::test]
+    async fn test_admin_auth() {
+        set_test_admin_token();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Repositories::in_memory()))
+                .configure(controller::configure),
+        )
+        .await;
+        let settings_req = |authorization: Option<&str>| {
+            let mut req = test::TestRequest::get().uri("/admin/settings");
+            if let Some(authorization) = authorization {
+                req = req.insert_header((header::AUTHORIZATION, authorization));
+            }
+            req.to_request()
+        };
+
+        let resp = test::call_service(&app, settings_req(None)).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = test::call_service(&app, settings_req(Some("Bearer wrong-token"))).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = test::call_service(&app, settings_req(Some(TEST_ADMIN_TOKEN))).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let authorization = format!("Bearer {}", TEST_ADMIN_TOKEN);
+        let resp = test::call_service(&app, settings_req(Some(&authorization))).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // The import reads nothing outside `import.root`, which is not set
+        let req = test::TestRequest::post()
+            .uri("/admin/import_face_infos")
+            .insert_header((header::AUTHORIZATION, authorization.as_str()))
+            .set_json(serde_json::json!({
+                "dir": "/",
+                "manifest": "/etc/passwd",
+                "creator": "admin",
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+}
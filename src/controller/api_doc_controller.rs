@@ -0,0 +1,198 @@
+//! The OpenAPI document of the handlers, generated from their `#[utoipa::path]`s and
+//! served at `/api-docs/openapi.json`, browsable with the Swagger UI at `/swagger-ui/`.
+
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{Components, ContentBuilder, OpenApi as OpenApiDoc, Ref, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::controller::{
+    admin_controller, face_info_controller, file_controller, health_controller, metrics_controller,
+    moderation_controller, v2_controller,
+};
+use crate::error::ErrorResp;
+
+pub const OPENAPI_URL: &str = "/api-docs/openapi.json";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Facemash Backend", description = "The backend of the Facemash."),
+    paths(
+        face_info_controller::get_face_info_randomly,
+        face_info_controller::get_face_info_by_id,
+        face_info_controller::add_face_info,
+        face_info_controller::vote_face_info,
+        face_info_controller::delete_face_info,
+        face_info_controller::restore_face_info,
+        face_info_controller::update_face_info,
+        face_info_controller::get_face_info_audits,
+        face_info_controller::report_face_info,
+        file_controller::create_file_resource_by_stream,
+        file_controller::create_file_resource,
+        file_controller::download_local_file,
+        file_controller::delete_file_resource,
+        file_controller::restore_file_resource,
+        admin_controller::purge_deleted,
+        admin_controller::import_face_infos,
+        admin_controller::start_season,
+        admin_controller::get_settings,
+        admin_controller::reload_settings,
+        metrics_controller::get_metrics,
+        health_controller::get_liveness,
+        health_controller::get_readiness,
+        moderation_controller::list_face_infos,
+        moderation_controller::list_reported_face_infos,
+        moderation_controller::approve_face_info,
+        moderation_controller::reject_face_info,
+        v2_controller::list_faces,
+        v2_controller::search_faces,
+        v2_controller::get_face,
+        v2_controller::add_face,
+        v2_controller::add_vote,
+        v2_controller::get_category_leaderboard,
+        v2_controller::list_seasons,
+        v2_controller::get_season_leaderboard,
+        v2_controller::get_file,
+        v2_controller::get_file_content,
+    ),
+    components(schemas(ErrorResp)),
+    modifiers(&ErrorResponses, &AdminSecurity),
+    tags(
+        (name = "face_info", description = "The faces voted on"),
+        (name = "file_resource", description = "The images of the faces"),
+        (name = "moderation", description = "The review of the added and reported faces"),
+        (name = "season", description = "The seasons of the ratings and their standings"),
+        (name = "admin", description = "The maintenance of the server"),
+        (name = "ops", description = "The probes and the metrics"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Every operation may fail with an `ErrorResp`, see `error::AppError`.
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        let response = ResponseBuilder::new()
+            .description("The error, `code` tells its kind")
+            .content(
+                "application/json",
+                ContentBuilder::new()
+                    .schema(Some(Ref::from_schema_name("ErrorResp")))
+                    .build(),
+            )
+            .build();
+        for path_item in openapi.paths.paths.values_mut() {
+            let operations = [&mut path_item.get, &mut path_item.post];
+            for operation in operations.into_iter().flatten() {
+                operation
+                    .responses
+                    .responses
+                    .entry("default".to_string())
+                    .or_insert_with(|| response.clone().into());
+            }
+        }
+    }
+}
+
+/// The `/admin`, `/moderation` and delete/restore/update routes take the `admin.token` as a bearer token, see `admin_controller::AdminAuth`.
+struct AdminSecurity;
+
+impl Modify for AdminSecurity {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        openapi
+            .components
+            .get_or_insert_with(Components::new)
+            .add_security_scheme(
+                "admin_token",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+    }
+}
+
+/// Serves the document and the Swagger UI.
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new("/swagger-ui/{_:.*}").url(OPENAPI_URL, ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::fs;
+
+    use actix_web::{test, web, App, HttpRequest, HttpResponse};
+    use regex::Regex;
+
+    use super::*;
+    use crate::controller;
+
+    /// The routes of the document, e.g. `POST /vote_face_info`.
+    fn get_documented_routes(openapi: &OpenApiDoc) -> BTreeSet<String> {
+        let mut routes = BTreeSet::new();
+        for (path, path_item) in &openapi.paths.paths {
+            for (method, operation) in [("GET", &path_item.get), ("POST", &path_item.post)] {
+                if operation.is_some() {
+                    routes.insert(format!("{} {}", method, path));
+                }
+            }
+        }
+        routes
+    }
+
+    /// The documented paths not registered in the app.
+    async fn get_unregistered_paths(req: HttpRequest) -> HttpResponse {
+        let param = Regex::new(r"\{[^}]+\}").unwrap();
+        let unregistered_paths = ApiDoc::openapi()
+            .paths
+            .paths
+            .into_keys()
+            .filter(|path| {
+                !req.resource_map()
+                    .has_resource(&param.replace_all(path, "1"))
+            })
+            .collect::<Vec<_>>();
+        HttpResponse::Ok().json(unregistered_paths)
+    }
+
+    #[actix_rt::test]
+    async fn test_api_doc_in_sync() {
+        let openapi = ApiDoc::openapi();
+        let documented_routes = get_documented_routes(&openapi);
+
+        // Step 1: Every route of the handlers is documented, and nothing else
+        let route_macro = Regex::new(r#"#\[(get|post|put|delete|patch)\("([^"]+)"\)\]"#).unwrap();
+        let controller_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/controller");
+        let mut routes = BTreeSet::new();
+        for entry in fs::read_dir(controller_dir).unwrap() {
+            let source = fs::read_to_string(entry.unwrap().path()).unwrap();
+            for captures in route_macro.captures_iter(&source) {
+                routes.insert(format!("{} {}", captures[1].to_uppercase(), &captures[2]));
+            }
+        }
+        assert_eq!(
+            routes, documented_routes,
+            "the routes changed without the OpenAPI document, see `ApiDoc`"
+        );
+
+        // Step 2: Every documented path is registered by `controller::configure`
+        let app = test::init_service(
+            App::new()
+                .configure(controller::configure)
+                .route("/unregistered_paths", web::get().to(get_unregistered_paths)),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/unregistered_paths")
+            .to_request();
+        let unregistered_paths: Vec<String> = test::call_and_read_body_json(&app, req).await;
+        assert!(unregistered_paths.is_empty(), "{:?}", unregistered_paths);
+
+        // Step 3: The document is served
+        let req = test::TestRequest::get().uri(OPENAPI_URL).to_request();
+        let served: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(served, serde_json::to_value(&openapi).unwrap());
+        let req = test::TestRequest::get().uri("/swagger-ui/").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+    }
+}
@@ -1,278 +1,753 @@
-use crate::algorithm::elo_rating::{compete_uscf, EloScore, WIN};
-use actix_web::error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound};
-use actix_web::{post, web, Error, HttpResponse, Responder};
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-
-use crate::dao::rating_log_dao::add_rating_logs;
-use crate::entity::face_info::FaceInfo;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use crate::controller::admin_controller::AdminAuth;
+use crate::dto::face_info_dto::NewFaceInfo;
+use crate::dto::{
+    validate_categories, CATEGORY_REGEX, ID_REGEX, MAX_STAR_NAME_LEN, MAX_TEXT_LEN,
+    MAX_USER_NAME_LEN,
+};
+use crate::entity::face_info::{FaceInfo, FaceInfoPatch};
+use crate::entity::face_info_audit::FaceInfoAudit;
+use crate::entity::face_report::ReportReason;
 use crate::entity::file_resource::FileResource;
 use crate::entity::rating_log::RatingLog;
-use crate::resource;
+use crate::error::{AppError, AppResult};
+use crate::repository::{Repositories, RepositoryResult};
 use crate::service::face_info_service::update_face_info_rating;
-use crate::service::{face_info_service, file_resource_service};
-use crate::{doc, entity};
+use crate::service::report_service::ReportResult;
+use crate::service::{
+    category_service, face_info_service, file_resource_service, report_service, season_service,
+};
+use crate::{config, metrics, resource, shutdown};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct FaceAndFileResourceInfo {
     face_info: FaceInfo,
     file_resource: FileResource,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct GetRandomFaceInfoRandomlyReq {
     face_info_cnt: i64,
+    /// Samples only the face_infos in the category
+    #[serde(default)]
+    #[validate(regex(path = *CATEGORY_REGEX))]
+    category: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GetRandomFaceInfoRandomlyResp {
     face_and_file_infos: Vec<FaceAndFileResourceInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct GetFaceInfoByIdReq {
+    #[validate(regex(path = *ID_REGEX))]
     id: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GetFaceInfoByIdResp {
     face_and_file_info: FaceAndFileResourceInfo,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct AddFaceInfoReq {
-    face_info: FaceInfo,
+    #[validate(nested)]
+    face_info: NewFaceInfo,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AddFaceInfoResp {
     face_info_id: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+#[validate(schema(function = validate_vote_face_info_req))]
 pub struct VoteFaceInfoReq {
+    #[validate(regex(path = *ID_REGEX))]
     win_face_info_id: String,
+    #[validate(regex(path = *ID_REGEX))]
     lose_face_info_id: String,
+    #[validate(length(max = MAX_USER_NAME_LEN))]
     voter: String,
+    /// Rates the vote in the category as well, both face_infos must be in it
+    #[serde(default)]
+    #[validate(regex(path = *CATEGORY_REGEX))]
+    category: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct DeleteFaceInfoReq {
+    #[validate(regex(path = *ID_REGEX))]
+    id: String,
+    #[validate(length(max = MAX_USER_NAME_LEN))]
+    operator: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct RestoreFaceInfoReq {
+    #[validate(regex(path = *ID_REGEX))]
+    id: String,
+    #[validate(length(max = MAX_USER_NAME_LEN))]
+    operator: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateFaceInfoReq {
+    #[validate(regex(path = *ID_REGEX))]
+    id: String,
+    #[validate(length(min = 1, max = MAX_STAR_NAME_LEN))]
+    star_name: Option<String>,
+    #[validate(regex(path = *ID_REGEX))]
+    file_id: Option<String>,
+    /// Replaces the categories, their ratings are kept for when they come back
+    #[validate(custom(function = validate_categories))]
+    categories: Option<Vec<String>>,
+    #[validate(length(min = 1, max = MAX_USER_NAME_LEN))]
+    updater: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct GetFaceInfoAuditsReq {
+    #[validate(regex(path = *ID_REGEX))]
+    face_info_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GetFaceInfoAuditsResp {
+    face_info_audits: Vec<FaceInfoAudit>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ReportFaceInfoReq {
+    #[validate(regex(path = *ID_REGEX))]
+    face_info_id: String,
+    /// The name of the reporter, the distinct reporters are counted by their peer address
+    #[validate(length(min = 1, max = MAX_USER_NAME_LEN))]
+    reporter: String,
+    reason: ReportReason,
+    #[serde(default)]
+    #[validate(length(max = MAX_TEXT_LEN))]
+    comment: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReportFaceInfoResp {
+    report_result: ReportResult,
+}
+
+#[utoipa::path(
+    tag = "face_info",
+    responses((status = 200, body = GetRandomFaceInfoRandomlyResp))
+)]
 #[post("/get_face_info_randomly")]
 pub async fn get_face_info_randomly(
+    repos: web::Data<Repositories>,
     mut req: web::Json<GetRandomFaceInfoRandomlyReq>,
-) -> Result<impl Responder, Error> {
-    log::debug!("req: {:?}", &req);
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
 
+    req.validate()?;
+
+    let limits = config::get().limits.clone();
     if req.face_info_cnt <= 0 {
-        req.face_info_cnt = 2
+        req.face_info_cnt = limits.sample_size
     }
+    req.face_info_cnt = req.face_info_cnt.min(limits.max_sample_size);
 
-    let face_infos = face_info_service::get_face_info_randomly(req.face_info_cnt)
-        .await
-        .unwrap_or_default();
+    let face_infos = face_info_service::get_face_info_randomly(
+        &repos,
+        req.face_info_cnt,
+        req.category.as_deref(),
+    )
+    .await?;
+    let face_and_file_infos = get_face_and_file_infos(&repos, face_infos).await?;
+
+    Ok(HttpResponse::Ok().json(GetRandomFaceInfoRandomlyResp {
+        face_and_file_infos,
+    }))
+}
 
-    let mut face_and_file_infos = vec![];
+/// Joins the face_infos with their file_resources.
+pub async fn get_face_and_file_infos(
+    repos: &Repositories,
+    face_infos: Vec<FaceInfo>,
+) -> RepositoryResult<Vec<FaceAndFileResourceInfo>> {
+    let mut face_and_file_infos = Vec::with_capacity(face_infos.len());
     for face_info in face_infos {
+        let file_resource =
+            file_resource_service::get_file_resource_by_id(repos, &face_info.file_id)
+                .await?
+                .unwrap_or_default();
         face_and_file_infos.push(FaceAndFileResourceInfo {
-            file_resource: match file_resource_service::get_one_file_resource_by_doc_filter(
-                doc! {"id": &face_info.file_id},
-            )
-            .await
-            {
-                Ok(file_info) => match file_info {
-                    None => FileResource::default(),
-                    Some(file_info) => file_info,
-                },
-                Err(err) => {
-                    log::error!("Error: {:?}", err);
-                    return HttpResponse::InternalServerError().await;
-                }
-            },
             face_info,
+            file_resource,
         });
     }
-
-    Ok(HttpResponse::Ok().json(GetRandomFaceInfoRandomlyResp {
-        face_and_file_infos,
-    }))
+    Ok(face_and_file_infos)
 }
 
+#[utoipa::path(
+    tag = "face_info",
+    responses((status = 200, body = GetFaceInfoByIdResp))
+)]
 #[post("/get_face_info_by_id")]
 pub async fn get_face_info_by_id(
+    repos: web::Data<Repositories>,
     req: web::Json<GetFaceInfoByIdReq>,
-) -> Result<impl Responder, Error> {
-    info!("req: {:?}", &req);
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
 
-    let face_info_id = &req.id;
-    if face_info_id.is_empty() {
-        return HttpResponse::NotFound().await;
-    }
+    req.validate()?;
 
-    let face_info =
-        match face_info_service::get_one_face_info_by_doc_filter(doc! {"id": face_info_id}).await {
-            Ok(face_info) => match face_info {
-                None => {
-                    return HttpResponse::NotFound().await;
-                }
-                Some(face_info) => face_info,
-            },
-            Err(err) => {
-                log::error!("Error: {:?}", err);
-                return HttpResponse::InternalServerError().await;
-            }
-        };
-
-    let file_resource = match file_resource_service::get_one_file_resource_by_doc_filter(
-        doc! {"id": &face_info.file_id},
-    )
-    .await
-    {
-        Ok(file_info) => match file_info {
-            None => FileResource::default(),
-            Some(file_info) => file_info,
-        },
-        Err(err) => {
-            log::error!("Error: {:?}", err);
-            return HttpResponse::InternalServerError().await;
-        }
-    };
+    let face_and_file_info = get_face_and_file_info(&repos, &req.id).await?;
+    Ok(HttpResponse::Ok().json(GetFaceInfoByIdResp { face_and_file_info }))
+}
 
-    Ok(HttpResponse::Ok().json(GetFaceInfoByIdResp {
-        face_and_file_info: FaceAndFileResourceInfo {
-            face_info,
-            file_resource,
-        },
-    }))
+/// Gets the face_info with its file_resource, fails with 404 if not found.
+pub async fn get_face_and_file_info(
+    repos: &Repositories,
+    face_info_id: &str,
+) -> AppResult<FaceAndFileResourceInfo> {
+    let face_info = face_info_service::get_face_info_by_id(repos, face_info_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("FaceInfo not found!".to_string()))?;
+
+    let file_resource = file_resource_service::get_file_resource_by_id(repos, &face_info.file_id)
+        .await?
+        .unwrap_or_default();
+
+    Ok(FaceAndFileResourceInfo {
+        face_info,
+        file_resource,
+    })
 }
 
+#[utoipa::path(
+    tag = "face_info",
+    responses((status = 200, body = AddFaceInfoResp))
+)]
 #[post("/add_face_info")]
-pub async fn add_face_info(mut req: web::Json<AddFaceInfoReq>) -> Result<impl Responder, Error> {
-    info!("req: {:?}", &req);
-
-    let face_info_id = resource::id_generator::get_id().await;
-    req.face_info.id = face_info_id;
-    req.face_info.created_on = chrono::Utc::now().timestamp();
-    req.face_info.score = entity::face_info::DEFAULT_SCORE;
-
-    check_add_face_info_param(&req.face_info).await?;
-
-    match face_info_service::add_face_info(&req.face_info).await {
-        Ok(_) => Ok(HttpResponse::Ok().json(AddFaceInfoResp {
-            face_info_id: req.face_info.id.clone(),
-        })),
-        Err(err) => {
-            log::error!("Error: {:?}", err);
-            HttpResponse::InternalServerError().await
-        }
-    }
+pub async fn add_face_info(
+    repos: web::Data<Repositories>,
+    req: web::Json<AddFaceInfoReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
+
+    req.validate()?;
+
+    let face_info = create_face_info(&repos, req.into_inner().face_info).await?;
+    Ok(HttpResponse::Ok().json(AddFaceInfoResp {
+        face_info_id: face_info.id,
+    }))
 }
 
+/// Adds the face_info, pending until moderated, the input is expected to be validated.
+pub async fn create_face_info(
+    repos: &Repositories,
+    new_face_info: NewFaceInfo,
+) -> AppResult<FaceInfo> {
+    let face_info = new_face_info.into_face_info(
+        resource::id_generator::get_id().await,
+        config::get().rating.initial_score,
+        chrono::Utc::now().timestamp(),
+    );
+    face_info_service::add_face_info(repos, &face_info).await?;
+    Ok(face_info)
+}
+
+#[utoipa::path(
+    tag = "face_info",
+    responses((status = 200, description = "Done"))
+)]
 #[post("/vote_face_info")]
-pub async fn vote_face_info(req: web::Json<VoteFaceInfoReq>) -> Result<impl Responder, Error> {
-    info!("req: {:?}", &req);
+pub async fn vote_face_info(
+    repos: web::Data<Repositories>,
+    req: web::Json<VoteFaceInfoReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
 
-    if req.win_face_info_id.is_empty() || req.lose_face_info_id.is_empty() {
-        return Err(ErrorBadRequest("face_info_id is required!"));
-    };
+    cast_vote(&repos, &req).await?;
+    Ok(HttpResponse::Ok().json(()))
+}
+
+/// Rates the winner and the loser of the vote, and logs it.
+pub async fn cast_vote(repos: &Repositories, req: &VoteFaceInfoReq) -> AppResult<()> {
+    // Drained on shutdown, the new votes are refused once it starts
+    let _in_flight = shutdown::track_in_flight()
+        .ok_or_else(|| AppError::ServiceUnavailable("The server is shutting down!".to_string()))?;
+
+    req.validate()?;
 
     // Step 1: find corresponding face_info
-    let filter_doc = doc! {
-        "id" :{"$in": [req.win_face_info_id.as_str(), req.lose_face_info_id.as_str()]}
-    };
+    let face_info_ids = [
+        req.win_face_info_id.as_str(),
+        req.lose_face_info_id.as_str(),
+    ];
+    let res = face_info_service::get_approved_face_infos_by_ids(repos, &face_info_ids).await?;
+    if res.len() < 2 {
+        return Err(AppError::NotFound("FaceInfo not found!".to_string()));
+    }
     let face_info_map: HashMap<String, FaceInfo> =
-        match face_info_service::get_face_infos_by_doc_filter(filter_doc).await {
-            Ok(res) => {
-                if res.len() < 2 {
-                    return Err(ErrorNotFound("FaceInfo not found!"));
-                }
-
-                let mut ret_map = HashMap::new();
-                for x in res {
-                    ret_map.insert(x.id.clone(), x);
-                }
-                ret_map
-            }
-            Err(err) => {
-                log::error!("Error: {:?}", err);
-                return HttpResponse::InternalServerError().await;
-            }
-        };
+        res.into_iter().map(|x| (x.id.clone(), x)).collect();
 
     // Step 2：Calculate Score
-    let win_face_info = match face_info_map.get(req.win_face_info_id.as_str()) {
-        None => {
-            return Err(ErrorNotFound("Winner FaceInfo not found!"));
-        }
-        Some(win_face_info) => win_face_info,
-    };
-    let lose_face_info = match face_info_map.get(req.lose_face_info_id.as_str()) {
-        None => {
-            return Err(ErrorNotFound("Loser FaceInfo not found!"));
+    let win_face_info = face_info_map
+        .get(req.win_face_info_id.as_str())
+        .ok_or_else(|| AppError::NotFound("Winner FaceInfo not found!".to_string()))?;
+    let lose_face_info = face_info_map
+        .get(req.lose_face_info_id.as_str())
+        .ok_or_else(|| AppError::NotFound("Loser FaceInfo not found!".to_string()))?;
+    if let Some(category) = &req.category {
+        if !win_face_info.categories.contains(category)
+            || !lose_face_info.categories.contains(category)
+        {
+            return Err(AppError::invalid_field(
+                "category",
+                "mismatch",
+                "category is not shared by the face_infos!",
+            ));
         }
-        Some(lose_face_info) => lose_face_info,
-    };
+    }
 
-    let (win_score, lose_score) = compete_uscf(
-        win_face_info.score as EloScore,
-        lose_face_info.score as EloScore,
-        WIN,
-    );
+    let rating = config::get().rating.clone();
+    let (win_score, lose_score) =
+        face_info_service::get_vote_scores(win_face_info, lose_face_info, &rating);
 
     // Step 3：Update Score
     let now = chrono::Utc::now().timestamp();
-    if let Err(err) = update_face_info_rating(
+    update_face_info_rating(
+        repos,
         &win_face_info.id,
-        win_score as f64,
+        win_score,
         true,
         req.voter.as_str(),
         now,
     )
-    .await
-    {
-        log::error!("Error: {:?}", err);
-        return HttpResponse::InternalServerError().await;
-    }
-    if let Err(err) = update_face_info_rating(
+    .await?;
+    update_face_info_rating(
+        repos,
         &lose_face_info.id,
-        lose_score as f64,
+        lose_score,
         false,
         req.voter.as_str(),
         now,
     )
-    .await
-    {
-        log::error!("Error: {:?}", err);
-        return HttpResponse::InternalServerError().await;
+    .await?;
+    if let Some(category) = &req.category {
+        category_service::rate_category_vote(
+            repos,
+            category,
+            &win_face_info.id,
+            &lose_face_info.id,
+            &rating,
+            now,
+        )
+        .await?;
     }
 
-    // Step 4: Add vote logs
-    if let Err(err) = add_rating_logs(vec![RatingLog {
-        id: resource::id_generator::get_id().await,
-        win_face_id: win_face_info.id.clone(),
-        loss_face_id: lose_face_info.id.clone(),
-        creator: req.voter.clone(),
-        created_on: now,
-        ..RatingLog::default()
-    }])
-    .await
+    // Step 4: Add vote logs, tagged with the season
+    let season_id = season_service::get_current_season_id(repos).await?;
+    if let Err(err) = repos
+        .rating_log
+        .add_rating_logs(vec![RatingLog {
+            id: resource::id_generator::get_id().await,
+            win_face_id: win_face_info.id.clone(),
+            loss_face_id: lose_face_info.id.clone(),
+            creator: req.voter.clone(),
+            category: req.category.clone().unwrap_or_default(),
+            season_id,
+            created_on: now,
+            ..RatingLog::default()
+        }])
+        .await
     {
         log::error!("Error: {:?}", err);
     }
+    metrics::VOTES_TOTAL.inc();
+
+    Ok(())
+}
 
+#[utoipa::path(
+    tag = "face_info",
+    security(("admin_token" = [])),
+    responses((status = 200, description = "Done"))
+)]
+#[post("/delete_face_info")]
+pub async fn delete_face_info(
+    _admin: AdminAuth,
+    repos: web::Data<Repositories>,
+    req: web::Json<DeleteFaceInfoReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
+
+    req.validate()?;
+
+    let now = chrono::Utc::now().timestamp();
+    let deleted =
+        face_info_service::soft_delete_face_info(&repos, &req.id, &req.operator, now).await?;
+    if !deleted {
+        return Err(AppError::NotFound("FaceInfo not found!".to_string()));
+    }
     Ok(HttpResponse::Ok().json(()))
 }
 
-async fn check_add_face_info_param(face_info: &FaceInfo) -> Result<(), Error> {
-    if face_info.id.is_empty() {
-        return Err(ErrorInternalServerError("generate id failed"));
+#[utoipa::path(
+    tag = "face_info",
+    security(("admin_token" = [])),
+    responses((status = 200, description = "Done"))
+)]
+#[post("/restore_face_info")]
+pub async fn restore_face_info(
+    _admin: AdminAuth,
+    repos: web::Data<Repositories>,
+    req: web::Json<RestoreFaceInfoReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
+
+    req.validate()?;
+
+    let now = chrono::Utc::now().timestamp();
+    let restored =
+        face_info_service::restore_face_info(&repos, &req.id, &req.operator, now).await?;
+    if !restored {
+        return Err(AppError::NotFound(
+            "Deleted FaceInfo not found!".to_string(),
+        ));
     }
+    Ok(HttpResponse::Ok().json(()))
+}
 
-    if face_info.file_id.is_empty() {
-        return Err(ErrorBadRequest("file id is empty"));
+#[utoipa::path(
+    tag = "face_info",
+    security(("admin_token" = [])),
+    responses((status = 200, description = "Done"))
+)]
+#[post("/update_face_info")]
+pub async fn update_face_info(
+    _admin: AdminAuth,
+    repos: web::Data<Repositories>,
+    req: web::Json<UpdateFaceInfoReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
+
+    check_update_face_info_param(&req).await?;
+
+    // Step 1: find the face_info
+    let face_info = face_info_service::get_face_info_by_id(&repos, &req.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("FaceInfo not found!".to_string()))?;
+
+    let patch = FaceInfoPatch {
+        star_name: req.star_name.clone(),
+        file_id: req.file_id.clone(),
+        categories: req.categories.clone().map(|mut categories| {
+            categories.sort();
+            categories
+        }),
+    };
+    if face_info_service::get_face_info_changes(&face_info, &patch).is_empty() {
+        return Ok(HttpResponse::Ok().json(()));
     }
 
-    if face_info.star_name.is_empty() {
-        return Err(ErrorBadRequest("start name is empty"));
+    // Step 2: the new file_resource must exist
+    if let Some(file_id) = &req.file_id {
+        if file_id != &face_info.file_id
+            && file_resource_service::get_file_resource_by_id(&repos, file_id)
+                .await?
+                .is_none()
+        {
+            return Err(AppError::Validation("FileResource not found!".to_string()));
+        }
+    }
+
+    // Step 3: update the face_info & write the audits
+    let now = chrono::Utc::now().timestamp();
+    let updated =
+        face_info_service::update_face_info(&repos, &face_info, &patch, &req.updater, now).await?;
+    if !updated {
+        return Err(AppError::Conflict(
+            "FaceInfo has been changed concurrently!".to_string(),
+        ));
+    }
+    Ok(HttpResponse::Ok().json(()))
+}
+
+#[utoipa::path(
+    tag = "face_info",
+    responses((status = 200, body = GetFaceInfoAuditsResp))
+)]
+#[post("/get_face_info_audits")]
+pub async fn get_face_info_audits(
+    repos: web::Data<Repositories>,
+    req: web::Json<GetFaceInfoAuditsReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
+
+    req.validate()?;
+
+    let face_info_audits =
+        face_info_service::get_face_info_audits(&repos, &req.face_info_id).await?;
+    Ok(HttpResponse::Ok().json(GetFaceInfoAuditsResp { face_info_audits }))
+}
+
+#[utoipa::path(
+    tag = "face_info",
+    responses((status = 200, body = ReportFaceInfoResp))
+)]
+#[post("/report_face_info")]
+pub async fn report_face_info(
+    repos: web::Data<Repositories>,
+    http_req: HttpRequest,
+    req: web::Json<ReportFaceInfoReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
+
+    req.validate()?;
+
+    // The reporter is the peer address, the forwarded headers are set by the client
+    let reporter = http_req
+        .peer_addr()
+        .map(|addr| addr.ip().to_string())
+        .ok_or_else(|| AppError::BadRequest("Unknown peer address!".to_string()))?;
+
+    let face_info = face_info_service::get_face_info_by_id(&repos, &req.face_info_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("FaceInfo not found!".to_string()))?;
+
+    let now = chrono::Utc::now().timestamp();
+    let report_result = report_service::report_face_info(
+        &repos,
+        &face_info,
+        &reporter,
+        &req.reporter,
+        req.reason,
+        &req.comment,
+        now,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(ReportFaceInfoResp { report_result }))
+}
+
+async fn check_update_face_info_param(req: &UpdateFaceInfoReq) -> AppResult<()> {
+    req.validate()?;
+
+    if req.star_name.is_none() && req.file_id.is_none() && req.categories.is_none() {
+        return Err(AppError::Validation("nothing to update".to_string()));
     }
 
     Ok(())
 }
+
+/// A face_info can't be voted against itself.
+fn validate_vote_face_info_req(req: &VoteFaceInfoReq) -> Result<(), ValidationError> {
+    if req.win_face_info_id == req.lose_face_info_id {
+        let mut err = ValidationError::new("distinct")
+            .with_message("must differ from win_face_info_id!".into());
+        err.add_param("field".into(), &"lose_face_info_id");
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+    use dotenv::dotenv;
+
+    use super::*;
+    use crate::controller;
+    use crate::controller::admin_controller::tests::test_admin_header;
+    use crate::entity::face_info::FaceStatus;
+    use crate::error::ErrorResp;
+
+    #[actix_rt::test]
+    async fn test_vote_face_info() {
+        dotenv().ok();
+        let repos = Repositories::in_memory();
+        for id in ["1", "2"] {
+            repos
+                .face_info
+                .add_face_info(&FaceInfo {
+                    id: id.to_string(),
+                    status: FaceStatus::Approved,
+                    ..FaceInfo::default()
+                })
+                .await
+                .unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(repos.clone()))
+                .configure(controller::configure),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/vote_face_info")
+            .set_json(serde_json::json!({
+                "win_face_info_id": "1",
+                "lose_face_info_id": "2",
+                "voter": "tester",
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let win_face_info = repos.face_info.get_face_info_by_id("1").await.unwrap();
+        let lose_face_info = repos.face_info.get_face_info_by_id("2").await.unwrap();
+        let (win_face_info, lose_face_info) = (win_face_info.unwrap(), lose_face_info.unwrap());
+        assert_eq!(win_face_info.upvote_count, 1);
+        assert_eq!(lose_face_info.downvote_count, 1);
+        assert!(win_face_info.score > lose_face_info.score);
+        assert_eq!(repos.rating_log.count_all_rating_logs().await.unwrap(), 1);
+
+        // A pending face_info can't be voted
+        repos
+            .face_info
+            .update_face_info_status("2", FaceStatus::Approved, FaceStatus::Pending, "", "", 0)
+            .await
+            .unwrap();
+        let req = test::TestRequest::post()
+            .uri("/vote_face_info")
+            .set_json(serde_json::json!({
+                "win_face_info_id": "1",
+                "lose_face_info_id": "2",
+                "voter": "tester",
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_add_face_info_validation() {
+        dotenv().ok();
+        let repos = Repositories::in_memory();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(repos.clone()))
+                .configure(controller::configure),
+        )
+        .await;
+
+        // Every invalid field is reported
+        let req = test::TestRequest::post()
+            .uri("/add_face_info")
+            .set_json(serde_json::json!({
+                "face_info": {"star_name": "", "file_id": "../1"},
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body: ErrorResp = test::read_body_json(resp).await;
+        assert_eq!(body.code, "validation_error");
+        let fields = body
+            .field_errors
+            .iter()
+            .map(|field_error| (field_error.field.as_str(), field_error.code.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                ("face_info.file_id", "regex"),
+                ("face_info.star_name", "length")
+            ]
+        );
+
+        // The fields owned by the server can't be set
+        let req = test::TestRequest::post()
+            .uri("/add_face_info")
+            .set_json(serde_json::json!({
+                "face_info": {"star_name": "star", "file_id": "1", "score": 9999.0},
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post()
+            .uri("/add_face_info")
+            .set_json(serde_json::json!({
+                "face_info": {"star_name": "star", "file_id": "1"},
+            }))
+            .to_request();
+        let resp: AddFaceInfoResp = test::call_and_read_body_json(&app, req).await;
+        let face_info = repos
+            .face_info
+            .get_face_info_by_id(&resp.face_info_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(face_info.status, FaceStatus::Pending);
+        assert_eq!(face_info.score, config::get().rating.initial_score);
+    }
+
+    #[actix_rt::test]
+    async fn test_manage_face_info_requires_admin() {
+        dotenv().ok();
+        let admin_header = test_admin_header();
+        let repos = Repositories::in_memory();
+        repos
+            .face_info
+            .add_face_info(&FaceInfo {
+                id: "1".to_string(),
+                star_name: "star".to_string(),
+                status: FaceStatus::Approved,
+                ..FaceInfo::default()
+            })
+            .await
+            .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(repos.clone()))
+                .configure(controller::configure),
+        )
+        .await;
+        let requests = [
+            (
+                "/update_face_info",
+                serde_json::json!({"id": "1", "star_name": "renamed", "updater": "tester"}),
+            ),
+            (
+                "/delete_face_info",
+                serde_json::json!({"id": "1", "operator": "tester"}),
+            ),
+            (
+                "/restore_face_info",
+                serde_json::json!({"id": "1", "operator": "tester"}),
+            ),
+        ];
+
+        // Step 1: Without the admin token nothing is changed
+        for (uri, body) in &requests {
+            let req = test::TestRequest::post()
+                .uri(uri)
+                .set_json(body)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+        }
+        let face_info = repos.face_info.get_face_info_by_id("1").await.unwrap();
+        assert_eq!(face_info.unwrap().star_name, "star");
+
+        // Step 2: With the admin token
+        for (uri, body) in &requests {
+            let req = test::TestRequest::post()
+                .uri(uri)
+                .insert_header(admin_header.clone())
+                .set_json(body)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(resp.status().is_success(), "{}", uri);
+        }
+        let face_info = repos.face_info.get_face_info_by_id("1").await.unwrap();
+        assert_eq!(face_info.unwrap().star_name, "renamed");
+    }
+}
@@ -1,201 +1,301 @@
+use std::io;
+
 use actix_multipart::Multipart;
-use actix_web::{get, post, web, Error, HttpResponse, Responder};
-use mongodb::bson::doc;
+use actix_web::{get, post, web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
 
 use service::{face_info_service, file_resource_service};
 
-use crate::entity::file_resource::{FileResource, UriType};
-use crate::{resource, service, utils};
+use crate::controller::admin_controller::AdminAuth;
+use crate::dto::file_resource_dto::NewFileResource;
+use crate::dto::{ID_REGEX, MAX_USER_NAME_LEN};
+use crate::error::{AppError, AppResult};
+use crate::repository::Repositories;
+use crate::{resource, service, shutdown};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The multipart form of the uploads, only documented since the stream is read as is.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct CreateFileResourceByStreamForm {
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateFileResourceByStreamResp {
     file_id: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateFileResourceReq {
-    file_resource: FileResource,
+    #[validate(nested)]
+    file_resource: NewFileResource,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateFileResourceResp {
     file_resource_id: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct DownloadFileReq {
     face_info_id: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct DeleteFileResourceReq {
+    #[validate(regex(path = *ID_REGEX))]
+    id: String,
+    #[validate(length(max = MAX_USER_NAME_LEN))]
+    operator: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct RestoreFileResourceReq {
+    #[validate(regex(path = *ID_REGEX))]
+    id: String,
+    #[validate(length(max = MAX_USER_NAME_LEN))]
+    operator: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct DownloadLocalFilePath {
+    #[validate(regex(path = *ID_REGEX))]
+    face_info_id: String,
+}
+
+#[utoipa::path(
+    tag = "file_resource",
+    request_body(content = CreateFileResourceByStreamForm, content_type = "multipart/form-data"),
+    responses((status = 200, body = CreateFileResourceByStreamResp))
+)]
 #[post("/create_file_resource_by_stream")]
-pub async fn create_file_resource_by_stream(payload: Multipart) -> Result<HttpResponse, Error> {
+pub async fn create_file_resource_by_stream(
+    repos: web::Data<Repositories>,
+    payload: Multipart,
+) -> AppResult<HttpResponse> {
     info!("create_file_resource_by_stream start");
 
+    // Drained on shutdown, the new uploads are refused once it starts
+    let _in_flight = shutdown::track_in_flight()
+        .ok_or_else(|| AppError::ServiceUnavailable("The server is shutting down!".to_string()))?;
+
     // Step 0: Generate id
     let file_resource_id = resource::id_generator::get_id().await;
 
-    // Step 1: Save the file & calculate md5 hash
-    let file_name = match service::file_resource_service::create_file_resource_with_stream(
+    // Step 1: Save the file
+    let file_name = service::file_resource_service::create_file_resource_with_stream(
         payload,
         &file_resource_id,
     )
-    .await
-    {
-        Ok(file_name) => file_name,
-        Err(err) => {
-            error!("Failed to save_file, error: {:?}", err);
-            return HttpResponse::InternalServerError().await;
-        }
-    };
-    let file_uri = file_resource_service::get_local_filepath(&file_resource_id, &file_name);
-    let file_md5 = match utils::md5::get_file_md5(&file_uri).await {
-        Ok(file_md5) => file_md5,
-        Err(err) => {
-            error!("Failed to get_file_md5, error: {:?}", err);
-            return HttpResponse::InternalServerError().await;
-        }
-    };
+    .await?;
 
+    // Step 2: Check file md5 is repeated & save file_resource
+    let file_resource =
+        file_resource_service::save_local_file_resource(&repos, &file_resource_id, &file_name, "")
+            .await?;
     info!(
-        "Saving file success, file_name: {:?}, md5: {:?}",
-        file_name, file_md5
+        "Saving file success, file_uri: {:?}, md5: {:?}",
+        file_resource.file_uri, file_resource.md5
     );
-
-    // Step 2: Check file md5 is repeated
-    match face_info_service::get_one_face_info_by_doc_filter(doc! {"md5": &file_md5}).await {
-        Ok(res) => match res {
-            None => {
-                info!("Saving file success, file_uri: {:?}", file_uri);
-            }
-            Some(_) => {
-                error!("File has already been saved!");
-
-                file_resource_service::delete_file(&file_uri).await;
-                info!(
-                    "Delete file success, file_name: {:?}, md5: {:?}",
-                    file_name, file_md5
-                );
-
-                return HttpResponse::Forbidden().await;
-            }
-        },
-        Err(err) => {
-            error!("Failed to get_one_face_info_by_doc, error: {:?}", err);
-            return HttpResponse::InternalServerError().await;
-        }
-    };
-
-    // Step 3：Save file_resource
-    match file_resource_service::create_file_resource(&FileResource {
-        id: file_resource_id.clone(),
-        md5: file_md5,
-        created_on: chrono::Utc::now().timestamp(),
-        file_name,
-        file_uri,
-        ..FileResource::default()
-    })
-    .await
-    {
-        Ok(_) => Ok(HttpResponse::Ok().json(CreateFileResourceByStreamResp {
-            file_id: file_resource_id,
-        })),
-        Err(err) => {
-            log::error!("Error: {:?}", err);
-            HttpResponse::InternalServerError().await
-        }
-    }
+    Ok(HttpResponse::Ok().json(CreateFileResourceByStreamResp {
+        file_id: file_resource_id,
+    }))
 }
 
+#[utoipa::path(
+    tag = "file_resource",
+    responses((status = 200, body = CreateFileResourceResp))
+)]
 #[post("/create_file_resource")]
 pub async fn create_file_resource(
-    mut req: web::Json<CreateFileResourceReq>,
-) -> Result<impl Responder, Error> {
-    info!("req: {:?}", &req);
+    repos: web::Data<Repositories>,
+    req: web::Json<CreateFileResourceReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
 
-    let file_resource_id = resource::id_generator::get_id().await;
-    req.file_resource.id = file_resource_id;
-    req.file_resource.created_on = chrono::Utc::now().timestamp();
-
-    check_create_file_resource_req(&req.file_resource).await?;
-
-    match file_resource_service::create_file_resource(&req.file_resource).await {
-        Ok(_) => Ok(HttpResponse::Ok().json(CreateFileResourceResp {
-            file_resource_id: req.file_resource.id.clone(),
-        })),
-        Err(err) => {
-            log::error!("Error: {:?}", err);
-            HttpResponse::InternalServerError().await
-        }
+    req.validate()?;
+
+    let file_resource = req.into_inner().file_resource.into_file_resource(
+        resource::id_generator::get_id().await,
+        chrono::Utc::now().timestamp(),
+    );
+    file_resource_service::create_file_resource(&repos, &file_resource).await?;
+    Ok(HttpResponse::Ok().json(CreateFileResourceResp {
+        file_resource_id: file_resource.id,
+    }))
+}
+
+#[utoipa::path(
+    tag = "file_resource",
+    security(("admin_token" = [])),
+    responses((status = 200, description = "Done"))
+)]
+#[post("/delete_file_resource")]
+pub async fn delete_file_resource(
+    _admin: AdminAuth,
+    repos: web::Data<Repositories>,
+    req: web::Json<DeleteFileResourceReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
+
+    req.validate()?;
+
+    // Step 1: The file_resource must not be used by any live face_info
+    if let Some(face_info) = face_info_service::get_face_info_by_file_id(&repos, &req.id).await? {
+        info!(
+            "file_resource is still used, file_id: {:?}, face_info_id: {:?}",
+            req.id, face_info.id
+        );
+        return Err(AppError::Conflict(
+            "FileResource is still used by FaceInfo!".to_string(),
+        ));
+    }
+
+    // Step 2: Soft delete the file_resource
+    let now = chrono::Utc::now().timestamp();
+    let deleted =
+        file_resource_service::soft_delete_file_resource(&repos, &req.id, &req.operator, now)
+            .await?;
+    if !deleted {
+        return Err(AppError::NotFound("FileResource not found!".to_string()));
     }
+    Ok(HttpResponse::Ok().json(()))
 }
 
-async fn check_create_file_resource_req(file_resource: &FileResource) -> Result<(), Error> {
-    match file_resource.uri_type {
-        UriType::Local => {}
-        UriType::Url => {}
-    };
+#[utoipa::path(
+    tag = "file_resource",
+    security(("admin_token" = [])),
+    responses((status = 200, description = "Done"))
+)]
+#[post("/restore_file_resource")]
+pub async fn restore_file_resource(
+    _admin: AdminAuth,
+    repos: web::Data<Repositories>,
+    req: web::Json<RestoreFileResourceReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
 
-    Ok(())
+    req.validate()?;
+
+    let now = chrono::Utc::now().timestamp();
+    let restored =
+        file_resource_service::restore_file_resource(&repos, &req.id, &req.operator, now).await?;
+    if !restored {
+        return Err(AppError::NotFound(
+            "Deleted FileResource not found!".to_string(),
+        ));
+    }
+    Ok(HttpResponse::Ok().json(()))
 }
 
+#[utoipa::path(
+    tag = "file_resource",
+    params(DownloadLocalFilePath),
+    responses((status = 200, description = "The file", content_type = "application/octet-stream"))
+)]
 #[get("/download_local_file/{face_info_id}")]
 pub async fn download_local_file(
+    repos: web::Data<Repositories>,
     req: actix_web::HttpRequest,
-    face_info_id: web::Path<String>,
-) -> Result<HttpResponse, actix_web::Error> {
-    info!("req: {:?}", &req);
+    path: web::Path<DownloadLocalFilePath>,
+) -> AppResult<HttpResponse> {
+    debug!("req: {:?}", &req);
 
-    let face_info_id = face_info_id.into_inner();
-
-    if face_info_id.is_empty() {
-        info!("not found face_info, face_info_id is empty");
-        return HttpResponse::NotFound().await;
-    }
+    path.validate()?;
 
     // Step 1: Find face info
-    let face_info = match face_info_service::get_one_face_info_by_doc_filter(
-        doc! {"id": &face_info_id},
-    )
-    .await
-    {
-        Ok(face_info) => match face_info {
-            None => {
-                info!("face_info not found, face_info_id: {:?}", face_info_id);
-                return HttpResponse::NotFound().await;
-            }
-            Some(face_info) => face_info,
-        },
-        Err(err) => {
-            log::error!("Error: {:?}", err);
-            return HttpResponse::InternalServerError().await;
-        }
-    };
+    let face_info = face_info_service::get_face_info_by_id(&repos, &path.face_info_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("FaceInfo not found!".to_string()))?;
 
     // Step 2: Get file
-    let file_resource_info = match file_resource_service::get_one_file_resource_by_doc_filter(
-        doc! {"id": &face_info.file_id},
-    )
-    .await
-    {
-        Ok(file_resource_info) => match file_resource_info {
-            None => {
-                info!(
-                    "file_resource_info not found, file_id: {:?}",
-                    face_info.file_id
-                );
-                return HttpResponse::NotFound().await;
-            }
-            Some(file_resource_info) => file_resource_info,
-        },
-        Err(err) => {
-            log::error!("Error: {:?}", err);
-            return HttpResponse::InternalServerError().await;
-        }
-    };
+    let file_resource_info =
+        file_resource_service::get_file_resource_by_id(&repos, &face_info.file_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("FileResource not found!".to_string()))?;
 
-    let file = actix_files::NamedFile::open_async(file_resource_info.file_uri)
-        .await
-        .unwrap();
+    let file = open_local_file(&file_resource_info.file_uri).await?;
     Ok(file.into_response(&req))
 }
+
+/// Opens the saved file, fails with 404 if it's gone.
+pub async fn open_local_file(file_uri: &str) -> AppResult<actix_files::NamedFile> {
+    actix_files::NamedFile::open_async(file_uri)
+        .await
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => AppError::NotFound("File not found!".to_string()),
+            _ => AppError::Io(err),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+    use dotenv::dotenv;
+
+    use super::*;
+    use crate::controller;
+    use crate::controller::admin_controller::tests::test_admin_header;
+    use crate::entity::file_resource::FileResource;
+
+    #[actix_rt::test]
+    async fn test_manage_file_resource_requires_admin() {
+        dotenv().ok();
+        let admin_header = test_admin_header();
+        let repos = Repositories::in_memory();
+        repos
+            .file_resource
+            .add_file_resource(&FileResource {
+                id: "1".to_string(),
+                ..FileResource::default()
+            })
+            .await
+            .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(repos.clone()))
+                .configure(controller::configure),
+        )
+        .await;
+        let body = serde_json::json!({"id": "1", "operator": "tester"});
+
+        // Step 1: Without the admin token nothing is changed
+        for uri in ["/delete_file_resource", "/restore_file_resource"] {
+            let req = test::TestRequest::post()
+                .uri(uri)
+                .set_json(&body)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+        }
+        let file_resource = repos.file_resource.get_file_resource_by_id("1").await;
+        assert!(file_resource.unwrap().is_some());
+
+        // Step 2: With the admin token
+        let req = test::TestRequest::post()
+            .uri("/delete_file_resource")
+            .insert_header(admin_header.clone())
+            .set_json(&body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let file_resource = repos.file_resource.get_file_resource_by_id("1").await;
+        assert!(file_resource.unwrap().is_none());
+
+        let req = test::TestRequest::post()
+            .uri("/restore_file_resource")
+            .insert_header(admin_header)
+            .set_json(&body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let file_resource = repos.file_resource.get_file_resource_by_id("1").await;
+        assert!(file_resource.unwrap().is_some());
+    }
+}
@@ -0,0 +1,102 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::repository::Repositories;
+use crate::service::health_service;
+use crate::service::health_service::HealthStatus;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LivenessResp {
+    status: HealthStatus,
+}
+
+/// The liveness probe, up as long as the process serves requests.
+#[utoipa::path(
+    tag = "ops",
+    responses((status = 200, body = LivenessResp))
+)]
+#[get("/healthz")]
+pub async fn get_liveness() -> impl Responder {
+    HttpResponse::Ok().json(LivenessResp {
+        status: HealthStatus::Up,
+    })
+}
+
+/// The readiness probe, 503 with the components down if not ready.
+#[utoipa::path(
+    tag = "ops",
+    responses(
+        (status = 200, body = health_service::Readiness),
+        (status = 503, body = health_service::Readiness),
+    )
+)]
+#[get("/readyz")]
+pub async fn get_readiness(repos: web::Data<Repositories>) -> impl Responder {
+    let readiness = health_service::check_readiness(&repos).await;
+    if readiness.status == HealthStatus::Up {
+        HttpResponse::Ok().json(readiness)
+    } else {
+        for (name, component) in &readiness.components {
+            if let Some(err) = &component.error {
+                warn!("Component {} is down, error: {}", name, err);
+            }
+        }
+        HttpResponse::ServiceUnavailable().json(readiness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+    use dotenv::dotenv;
+
+    use super::*;
+    use crate::controller;
+    use crate::repository::memory::InMemoryDatabaseRepository;
+    use crate::service;
+
+    #[actix_rt::test]
+    async fn test_get_readiness() {
+        dotenv().ok();
+        service::init_file_service().await.unwrap();
+        let repos = Repositories::in_memory();
+        let down_repos = Repositories {
+            database: Arc::new(InMemoryDatabaseRepository { is_down: true }),
+            ..repos.clone()
+        };
+
+        for (repos, status) in [
+            (repos, StatusCode::OK),
+            (down_repos, StatusCode::SERVICE_UNAVAILABLE),
+        ] {
+            let app = test::init_service(
+                App::new()
+                    .app_data(web::Data::new(repos))
+                    .configure(controller::configure),
+            )
+            .await;
+
+            let req = test::TestRequest::get().uri("/healthz").to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(resp.status().is_success());
+
+            let req = test::TestRequest::get().uri("/readyz").to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), status);
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            assert_eq!(body["components"]["file_storage"]["status"], "up");
+            assert_eq!(body["components"]["id_generator"]["status"], "up");
+            if status.is_success() {
+                assert_eq!(body["status"], "up");
+            } else {
+                assert_eq!(body["status"], "down");
+                assert_eq!(body["components"]["database"]["status"], "down");
+                assert!(body["components"]["database"]["error"].is_string());
+            }
+        }
+    }
+}
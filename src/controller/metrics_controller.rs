@@ -0,0 +1,92 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use prometheus::{Encoder, TextEncoder};
+
+use crate::entity::face_info::FaceStatus;
+use crate::error::{AppError, AppResult};
+use crate::metrics;
+use crate::repository::Repositories;
+
+/// Exports the metrics in the prometheus text format.
+#[utoipa::path(
+    tag = "ops",
+    responses((status = 200, description = "The metrics", content_type = "text/plain"))
+)]
+#[get("/metrics")]
+pub async fn get_metrics(repos: web::Data<Repositories>) -> AppResult<impl Responder> {
+    // Step 1: Refresh the gauges counted from the repositories
+    for status in [
+        FaceStatus::Pending,
+        FaceStatus::Approved,
+        FaceStatus::Rejected,
+    ] {
+        match repos.face_info.count_face_infos_by_status(status).await {
+            Ok(cnt) => metrics::FACE_INFOS
+                .with_label_values(&[status.as_str()])
+                .set(cnt as i64),
+            // The gauge keeps the last value, the other metrics are still exported
+            Err(err) => log::error!("Error: {:?}", err),
+        }
+    }
+
+    // Step 2: Encode all the metrics
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+    encoder
+        .encode(&prometheus::gather(), &mut buffer)
+        .map_err(|err| AppError::Internal(err.to_string()))?;
+    Ok(HttpResponse::Ok()
+        .content_type(encoder.format_type())
+        .body(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::middleware::from_fn;
+    use actix_web::{test, App};
+    use dotenv::dotenv;
+
+    use super::*;
+    use crate::controller;
+    use crate::entity::face_info::FaceInfo;
+    use crate::metrics::metrics_middleware;
+
+    #[actix_rt::test]
+    async fn test_get_metrics() {
+        dotenv().ok();
+        let repos = Repositories::in_memory();
+        repos
+            .face_info
+            .add_face_info(&FaceInfo {
+                id: "1".to_string(),
+                status: FaceStatus::Pending,
+                ..FaceInfo::default()
+            })
+            .await
+            .unwrap();
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(metrics_middleware))
+                .app_data(web::Data::new(repos))
+                .configure(controller::configure),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/get_face_info_by_id")
+            .set_json(serde_json::json!({"id": "1"}))
+            .to_request();
+        test::call_service(&app, req).await;
+        let req = test::TestRequest::get()
+            .uri("/no_such_route/1")
+            .to_request();
+        test::call_service(&app, req).await;
+
+        let req = test::TestRequest::get().uri("/metrics").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains(r#"facemash_face_infos{status="pending"} 1"#));
+        assert!(body.contains(r#"route="/get_face_info_by_id""#), "{}", body);
+        assert!(body.contains(r#"route="unmatched""#), "{}", body);
+    }
+}
@@ -1,2 +1,60 @@
+use actix_web::web;
+
+use crate::error;
+
+pub mod admin_controller;
+pub mod api_doc_controller;
 pub mod face_info_controller;
 pub mod file_controller;
+pub mod health_controller;
+pub mod metrics_controller;
+pub mod moderation_controller;
+pub mod v2_controller;
+
+/// Registers all the handlers, the `Repositories` are expected in the app data.
+///
+/// The errors, including the unreadable json bodies or query strings and the unknown routes,
+/// are responded in the envelope of `error::ErrorResp`.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(web::JsonConfig::default().error_handler(error::handle_json_error))
+        .app_data(web::QueryConfig::default().error_handler(error::handle_query_error))
+        .service(face_info_controller::get_face_info_randomly)
+        .service(face_info_controller::get_face_info_by_id)
+        .service(face_info_controller::add_face_info)
+        .service(face_info_controller::vote_face_info)
+        .service(face_info_controller::delete_face_info)
+        .service(face_info_controller::restore_face_info)
+        .service(face_info_controller::update_face_info)
+        .service(face_info_controller::get_face_info_audits)
+        .service(face_info_controller::report_face_info)
+        .service(file_controller::create_file_resource_by_stream)
+        .service(file_controller::create_file_resource)
+        .service(file_controller::download_local_file)
+        .service(file_controller::delete_file_resource)
+        .service(file_controller::restore_file_resource)
+        .service(admin_controller::purge_deleted)
+        .service(admin_controller::import_face_infos)
+        .service(admin_controller::start_season)
+        .service(admin_controller::get_settings)
+        .service(admin_controller::reload_settings)
+        .service(metrics_controller::get_metrics)
+        .service(health_controller::get_liveness)
+        .service(health_controller::get_readiness)
+        .service(moderation_controller::list_face_infos)
+        .service(moderation_controller::list_reported_face_infos)
+        .service(moderation_controller::approve_face_info)
+        .service(moderation_controller::reject_face_info)
+        .service(v2_controller::list_faces)
+        // Ahead of `get_face`, which would take "search" for an id
+        .service(v2_controller::search_faces)
+        .service(v2_controller::get_face)
+        .service(v2_controller::add_face)
+        .service(v2_controller::add_vote)
+        .service(v2_controller::get_category_leaderboard)
+        .service(v2_controller::list_seasons)
+        .service(v2_controller::get_season_leaderboard)
+        .service(v2_controller::get_file)
+        .service(v2_controller::get_file_content)
+        .service(api_doc_controller::swagger_ui())
+        .default_service(web::to(error::handle_not_found));
+}
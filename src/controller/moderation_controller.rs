@@ -0,0 +1,358 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::controller::admin_controller::AdminAuth;
+use crate::controller::face_info_controller::{get_face_and_file_infos, FaceAndFileResourceInfo};
+use crate::dto::{ID_REGEX, MAX_TEXT_LEN, MAX_USER_NAME_LEN};
+use crate::entity::face_info::FaceStatus;
+use crate::entity::face_report::FaceReportSummary;
+use crate::error::{AppError, AppResult};
+use crate::repository::Repositories;
+use crate::service::moderation_service::{check_page, DEFAULT_PAGE_SIZE, MAX_PAGE, MAX_PAGE_SIZE};
+use crate::service::{face_info_service, moderation_service, report_service};
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ListFaceInfosByStatusReq {
+    status: FaceStatus,
+    #[serde(default)]
+    #[validate(range(max = MAX_PAGE))]
+    page: u64,
+    #[serde(default)]
+    page_size: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ListFaceInfosByStatusResp {
+    face_and_file_infos: Vec<FaceAndFileResourceInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ModerateFaceInfoReq {
+    #[validate(regex(path = *ID_REGEX))]
+    id: String,
+    #[validate(length(min = 1, max = MAX_USER_NAME_LEN))]
+    moderator: String,
+    #[serde(default)]
+    #[validate(length(max = MAX_TEXT_LEN))]
+    reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ListReportedFaceInfosReq {
+    #[serde(default)]
+    #[validate(range(max = MAX_PAGE))]
+    page: u64,
+    #[serde(default)]
+    page_size: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReportedFaceInfo {
+    face_and_file_info: FaceAndFileResourceInfo,
+    report_summary: FaceReportSummary,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ListReportedFaceInfosResp {
+    reported_face_infos: Vec<ReportedFaceInfo>,
+}
+
+#[utoipa::path(
+    tag = "moderation",
+    security(("admin_token" = [])),
+    responses((status = 200, body = ListFaceInfosByStatusResp))
+)]
+#[post("/moderation/list_face_infos")]
+pub async fn list_face_infos(
+    _admin: AdminAuth,
+    repos: web::Data<Repositories>,
+    mut req: web::Json<ListFaceInfosByStatusReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
+
+    req.validate()?;
+
+    if req.page_size <= 0 {
+        req.page_size = DEFAULT_PAGE_SIZE
+    }
+    req.page_size = req.page_size.min(MAX_PAGE_SIZE);
+    check_page(req.page, req.page_size)?;
+
+    let face_infos =
+        moderation_service::get_face_infos_by_status(&repos, req.status, req.page, req.page_size)
+            .await?;
+
+    let face_and_file_infos = get_face_and_file_infos(&repos, face_infos).await?;
+    Ok(HttpResponse::Ok().json(ListFaceInfosByStatusResp {
+        face_and_file_infos,
+    }))
+}
+
+#[utoipa::path(
+    tag = "moderation",
+    security(("admin_token" = [])),
+    responses((status = 200, body = ListReportedFaceInfosResp))
+)]
+#[post("/moderation/list_reported_face_infos")]
+pub async fn list_reported_face_infos(
+    _admin: AdminAuth,
+    repos: web::Data<Repositories>,
+    mut req: web::Json<ListReportedFaceInfosReq>,
+) -> AppResult<impl Responder> {
+    debug!("req: {:?}", &req);
+
+    req.validate()?;
+
+    if req.page_size <= 0 {
+        req.page_size = DEFAULT_PAGE_SIZE
+    }
+    req.page_size = req.page_size.min(MAX_PAGE_SIZE);
+    check_page(req.page, req.page_size)?;
+
+    let report_summaries =
+        report_service::get_reported_face_infos(&repos, req.page, req.page_size).await?;
+
+    let mut reported_face_infos = Vec::with_capacity(report_summaries.len());
+    for report_summary in report_summaries {
+        let face_info =
+            match face_info_service::get_face_info_by_id(&repos, &report_summary.face_info_id)
+                .await?
+            {
+                Some(face_info) => face_info,
+                // The reported face_info has been deleted
+                None => continue,
+            };
+        let face_and_file_info = get_face_and_file_infos(&repos, vec![face_info])
+            .await?
+            .remove(0);
+        reported_face_infos.push(ReportedFaceInfo {
+            face_and_file_info,
+            report_summary,
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(ListReportedFaceInfosResp {
+        reported_face_infos,
+    }))
+}
+
+#[utoipa::path(
+    tag = "moderation",
+    security(("admin_token" = [])),
+    responses((status = 200, description = "Done"))
+)]
+#[post("/moderation/approve_face_info")]
+pub async fn approve_face_info(
+    _admin: AdminAuth,
+    repos: web::Data<Repositories>,
+    req: web::Json<ModerateFaceInfoReq>,
+) -> AppResult<HttpResponse> {
+    debug!("req: {:?}", &req);
+
+    moderate_face_info(&repos, &req, FaceStatus::Approved).await
+}
+
+#[utoipa::path(
+    tag = "moderation",
+    security(("admin_token" = [])),
+    responses((status = 200, description = "Done"))
+)]
+#[post("/moderation/reject_face_info")]
+pub async fn reject_face_info(
+    _admin: AdminAuth,
+    repos: web::Data<Repositories>,
+    req: web::Json<ModerateFaceInfoReq>,
+) -> AppResult<HttpResponse> {
+    debug!("req: {:?}", &req);
+
+    if req.reason.is_empty() {
+        return Err(AppError::invalid_field(
+            "reason",
+            "required",
+            "reason is required to reject!",
+        ));
+    }
+
+    moderate_face_info(&repos, &req, FaceStatus::Rejected).await
+}
+
+async fn moderate_face_info(
+    repos: &Repositories,
+    req: &ModerateFaceInfoReq,
+    status: FaceStatus,
+) -> AppResult<HttpResponse> {
+    req.validate()?;
+
+    // Step 1: find the face_info
+    let face_info = face_info_service::get_face_info_by_id(repos, &req.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("FaceInfo not found!".to_string()))?;
+
+    if face_info.status == status {
+        return Err(AppError::Conflict(format!(
+            "FaceInfo is already {}!",
+            status.as_str()
+        )));
+    }
+
+    // Step 2: move the face_info into the status
+    let now = chrono::Utc::now().timestamp();
+    let moderated = moderation_service::moderate_face_info(
+        repos,
+        &face_info,
+        status,
+        &req.moderator,
+        &req.reason,
+        now,
+    )
+    .await?;
+    if !moderated {
+        return Err(AppError::Conflict(
+            "FaceInfo has been moderated concurrently!".to_string(),
+        ));
+    }
+    Ok(HttpResponse::Ok().json(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+    use dotenv::dotenv;
+
+    use super::*;
+    use crate::controller;
+    use crate::controller::admin_controller::tests::test_admin_header;
+    use crate::entity::face_info::FaceInfo;
+    use crate::service::report_service::get_report_hide_threshold;
+
+    #[actix_rt::test]
+    async fn test_report_and_approve_face_info() {
+        dotenv().ok();
+        let admin_header = test_admin_header();
+        let repos = Repositories::in_memory();
+        repos
+            .face_info
+            .add_face_info(&FaceInfo {
+                id: "1".to_string(),
+                status: FaceStatus::Approved,
+                ..FaceInfo::default()
+            })
+            .await
+            .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(repos.clone()))
+                .configure(controller::configure),
+        )
+        .await;
+
+        // Step 1: Enough distinct reporters hide the face_info
+        let threshold = get_report_hide_threshold();
+        for reporter in 0..threshold {
+            let req = test::TestRequest::post()
+                .uri("/report_face_info")
+                .peer_addr(format!("10.0.0.{}:8080", reporter + 1).parse().unwrap())
+                .set_json(serde_json::json!({
+                    "face_info_id": "1",
+                    "reporter": "tester",
+                    "reason": "duplicate",
+                }))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(resp.status().is_success());
+        }
+        let face_info = repos.face_info.get_face_info_by_id("1").await.unwrap();
+        assert_eq!(face_info.unwrap().status, FaceStatus::Pending);
+
+        let req = test::TestRequest::post()
+            .uri("/moderation/list_reported_face_infos")
+            .insert_header(admin_header.clone())
+            .set_json(serde_json::json!({}))
+            .to_request();
+        let resp: ListReportedFaceInfosResp = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.reported_face_infos.len(), 1);
+        assert_eq!(
+            resp.reported_face_infos[0].report_summary.report_cnt,
+            threshold as i64
+        );
+
+        // Step 2: Only the admin approves the face_info
+        let req = test::TestRequest::post()
+            .uri("/moderation/approve_face_info")
+            .set_json(serde_json::json!({"id": "1", "moderator": "admin"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let face_info = repos.face_info.get_face_info_by_id("1").await.unwrap();
+        assert_eq!(face_info.unwrap().status, FaceStatus::Pending);
+
+        // Step 3: Approving the face_info resolves its reports
+        let req = test::TestRequest::post()
+            .uri("/moderation/approve_face_info")
+            .insert_header(admin_header)
+            .set_json(serde_json::json!({"id": "1", "moderator": "admin"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let face_info = repos.face_info.get_face_info_by_id("1").await.unwrap();
+        assert_eq!(face_info.unwrap().status, FaceStatus::Approved);
+        assert_eq!(
+            repos
+                .face_report
+                .count_unresolved_face_reports("1")
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_report_face_info_once_per_peer() {
+        dotenv().ok();
+        let repos = Repositories::in_memory();
+        repos
+            .face_info
+            .add_face_info(&FaceInfo {
+                id: "1".to_string(),
+                status: FaceStatus::Approved,
+                ..FaceInfo::default()
+            })
+            .await
+            .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(repos.clone()))
+                .configure(controller::configure),
+        )
+        .await;
+
+        // The reporter names of the body don't count as distinct reporters
+        for reporter in 0..get_report_hide_threshold() + 1 {
+            let req = test::TestRequest::post()
+                .uri("/report_face_info")
+                .peer_addr("10.0.0.1:8080".parse().unwrap())
+                .set_json(serde_json::json!({
+                    "face_info_id": "1",
+                    "reporter": reporter.to_string(),
+                    "reason": "duplicate",
+                }))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(resp.status().is_success());
+        }
+        let face_info = repos.face_info.get_face_info_by_id("1").await.unwrap();
+        assert_eq!(face_info.unwrap().status, FaceStatus::Approved);
+        assert_eq!(
+            repos
+                .face_report
+                .count_unresolved_face_reports("1")
+                .await
+                .unwrap(),
+            1
+        );
+    }
+}
@@ -0,0 +1,869 @@
+//! The resource-oriented API under `/api/v2`, e.g. `GET /api/v2/faces/{id}`.
+//!
+//! The RPC-style routes of the other controllers are the v1 and keep working,
+//! both versions share the handling beneath the routes.
+//!
+//! The reads are `GET`s with a short `Cache-Control` max-age, so the clients and
+//! proxies may cache them while the scores stay fresh.
+
+use actix_web::http::header::{self, CacheControl, CacheDirective};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, HttpResponseBuilder, Responder};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::config;
+use crate::controller::face_info_controller::{
+    cast_vote, create_face_info, get_face_and_file_info, get_face_and_file_infos,
+    FaceAndFileResourceInfo, VoteFaceInfoReq,
+};
+use crate::controller::file_controller::open_local_file;
+use crate::dto::face_info_dto::NewFaceInfo;
+use crate::dto::{CATEGORY_REGEX, ID_REGEX, MAX_STAR_NAME_LEN};
+use crate::entity::category_rating::CategoryRating;
+use crate::entity::face_info::{FaceInfo, FaceInfoOrder};
+use crate::entity::file_resource::{FileResource, UriType};
+use crate::entity::season::Season;
+use crate::entity::season_snapshot::SeasonSnapshot;
+use crate::error::{AppError, AppResult};
+use crate::repository::Repositories;
+use crate::service::moderation_service::{check_page, DEFAULT_PAGE_SIZE, MAX_PAGE, MAX_PAGE_SIZE};
+use crate::service::{category_service, face_info_service, file_resource_service, season_service};
+
+pub const API_V2: &str = "/api/v2";
+
+/// The seconds the reads may be cached for.
+pub const CACHE_MAX_AGE: u32 = 10;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FaceSort {
+    /// The highest score first
+    #[default]
+    Score,
+    /// The newest first
+    CreatedOn,
+    /// A random sample, the page is ignored
+    Random,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListFacesQuery {
+    #[serde(default)]
+    sort: FaceSort,
+    #[serde(default)]
+    #[validate(range(max = MAX_PAGE))]
+    page: u64,
+    #[serde(default)]
+    page_size: i64,
+    /// Samples only the faces in the category, for the random sort
+    #[validate(regex(path = *CATEGORY_REGEX))]
+    category: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ListFacesResp {
+    faces: Vec<FaceAndFileResourceInfo>,
+    /// The count of the approved faces
+    total: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchFacesQuery {
+    /// The star_name or its beginning, typos are tolerated
+    #[validate(length(min = 1, max = MAX_STAR_NAME_LEN))]
+    q: String,
+    #[serde(default)]
+    #[validate(range(max = MAX_PAGE))]
+    page: u64,
+    #[serde(default)]
+    page_size: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SearchFacesResp {
+    faces: Vec<FaceAndFileResourceInfo>,
+    /// The count of the matched faces
+    total: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct IdPath {
+    #[validate(regex(path = *ID_REGEX))]
+    id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct CategoryPath {
+    #[validate(regex(path = *CATEGORY_REGEX))]
+    category: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LeaderboardQuery {
+    #[serde(default)]
+    #[validate(range(max = MAX_PAGE))]
+    page: u64,
+    #[serde(default)]
+    page_size: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LeaderboardEntry {
+    face_and_file_info: FaceAndFileResourceInfo,
+    /// The rating in the category, apart from the overall score
+    rating: CategoryRating,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LeaderboardResp {
+    entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ListSeasonsResp {
+    /// The latest first, the current one has no `ended_on`
+    seasons: Vec<Season>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SeasonLeaderboardResp {
+    season: Season,
+    standings: Vec<SeasonSnapshot>,
+}
+
+#[utoipa::path(
+    tag = "face_info",
+    params(ListFacesQuery),
+    responses((status = 200, body = ListFacesResp))
+)]
+#[get("/api/v2/faces")]
+pub async fn list_faces(
+    repos: web::Data<Repositories>,
+    mut query: web::Query<ListFacesQuery>,
+) -> AppResult<impl Responder> {
+    debug!("query: {:?}", &query);
+
+    query.validate()?;
+    if query.category.is_some() && query.sort != FaceSort::Random {
+        return Err(AppError::invalid_field(
+            "category",
+            "sort",
+            "category only samples the random sort, see the leaderboard of the category!",
+        ));
+    }
+
+    let face_infos = match query.sort {
+        FaceSort::Random => {
+            let limits = config::get().limits.clone();
+            if query.page_size <= 0 {
+                query.page_size = limits.sample_size
+            }
+            query.page_size = query.page_size.min(limits.max_sample_size);
+            face_info_service::get_face_info_randomly(
+                &repos,
+                query.page_size,
+                query.category.as_deref(),
+            )
+            .await?
+        }
+        FaceSort::Score | FaceSort::CreatedOn => {
+            if query.page_size <= 0 {
+                query.page_size = DEFAULT_PAGE_SIZE
+            }
+            query.page_size = query.page_size.min(MAX_PAGE_SIZE);
+            check_page(query.page, query.page_size)?;
+            let order = match query.sort {
+                FaceSort::CreatedOn => FaceInfoOrder::CreatedOn,
+                _ => FaceInfoOrder::Score,
+            };
+            face_info_service::get_approved_face_infos(&repos, order, query.page, query.page_size)
+                .await?
+        }
+    };
+
+    let faces = get_face_and_file_infos(&repos, face_infos).await?;
+    let total = face_info_service::count_approved_face_infos(&repos).await?;
+    let mut resp = match query.sort {
+        // A new sample is drawn on every request
+        FaceSort::Random => uncached_ok(),
+        FaceSort::Score | FaceSort::CreatedOn => cached_ok(),
+    };
+    Ok(resp.json(ListFacesResp { faces, total }))
+}
+
+/// Searches the approved faces by star_name, the names starting with `q` come first.
+#[utoipa::path(
+    tag = "face_info",
+    params(SearchFacesQuery),
+    responses((status = 200, body = SearchFacesResp))
+)]
+#[get("/api/v2/faces/search")]
+pub async fn search_faces(
+    repos: web::Data<Repositories>,
+    mut query: web::Query<SearchFacesQuery>,
+) -> AppResult<impl Responder> {
+    debug!("query: {:?}", &query);
+
+    query.validate()?;
+
+    if query.page_size <= 0 {
+        query.page_size = DEFAULT_PAGE_SIZE
+    }
+    query.page_size = query.page_size.min(MAX_PAGE_SIZE);
+    check_page(query.page, query.page_size)?;
+
+    let search_page =
+        face_info_service::search_face_infos(&repos, &query.q, query.page, query.page_size).await?;
+    let faces = get_face_and_file_infos(&repos, search_page.face_infos).await?;
+    Ok(cached_ok().json(SearchFacesResp {
+        faces,
+        total: search_page.total,
+    }))
+}
+
+#[utoipa::path(
+    tag = "face_info",
+    params(IdPath),
+    responses((status = 200, body = FaceAndFileResourceInfo))
+)]
+#[get("/api/v2/faces/{id}")]
+pub async fn get_face(
+    repos: web::Data<Repositories>,
+    path: web::Path<IdPath>,
+) -> AppResult<impl Responder> {
+    debug!("path: {:?}", &path);
+
+    path.validate()?;
+
+    let face_and_file_info = get_face_and_file_info(&repos, &path.id).await?;
+    Ok(cached_ok().json(face_and_file_info))
+}
+
+#[utoipa::path(
+    tag = "face_info",
+    responses((
+        status = 201,
+        body = FaceInfo,
+        headers(("Location" = String, description = "The url of the added face"))
+    ))
+)]
+#[post("/api/v2/faces")]
+pub async fn add_face(
+    repos: web::Data<Repositories>,
+    new_face_info: web::Json<NewFaceInfo>,
+) -> AppResult<impl Responder> {
+    debug!("new_face_info: {:?}", &new_face_info);
+
+    new_face_info.validate()?;
+
+    let face_info = create_face_info(&repos, new_face_info.into_inner()).await?;
+    Ok(HttpResponse::Created()
+        .insert_header((
+            header::LOCATION,
+            format!("{}/faces/{}", API_V2, face_info.id),
+        ))
+        .json(face_info))
+}
+
+#[utoipa::path(
+    tag = "face_info",
+    responses((status = 204, description = "Voted"))
+)]
+#[post("/api/v2/votes")]
+pub async fn add_vote(
+    repos: web::Data<Repositories>,
+    vote: web::Json<VoteFaceInfoReq>,
+) -> AppResult<impl Responder> {
+    debug!("vote: {:?}", &vote);
+
+    cast_vote(&repos, &vote).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Ranks the faces in the category by their ratings in it, the highest first.
+#[utoipa::path(
+    tag = "face_info",
+    params(CategoryPath, LeaderboardQuery),
+    responses((status = 200, body = LeaderboardResp))
+)]
+#[get("/api/v2/categories/{category}/leaderboard")]
+pub async fn get_category_leaderboard(
+    repos: web::Data<Repositories>,
+    path: web::Path<CategoryPath>,
+    mut query: web::Query<LeaderboardQuery>,
+) -> AppResult<impl Responder> {
+    debug!("path: {:?}, query: {:?}", &path, &query);
+
+    path.validate()?;
+    query.validate()?;
+
+    if query.page_size <= 0 {
+        query.page_size = DEFAULT_PAGE_SIZE
+    }
+    query.page_size = query.page_size.min(MAX_PAGE_SIZE);
+    check_page(query.page, query.page_size)?;
+
+    let (face_infos, ratings): (Vec<_>, Vec<_>) = category_service::get_category_leaderboard(
+        &repos,
+        &path.category,
+        query.page,
+        query.page_size,
+    )
+    .await?
+    .into_iter()
+    .unzip();
+    let entries = get_face_and_file_infos(&repos, face_infos)
+        .await?
+        .into_iter()
+        .zip(ratings)
+        .map(|(face_and_file_info, rating)| LeaderboardEntry {
+            face_and_file_info,
+            rating,
+        })
+        .collect();
+    Ok(cached_ok().json(LeaderboardResp { entries }))
+}
+
+#[utoipa::path(
+    tag = "season",
+    responses((status = 200, body = ListSeasonsResp))
+)]
+#[get("/api/v2/seasons")]
+pub async fn list_seasons(repos: web::Data<Repositories>) -> AppResult<impl Responder> {
+    let seasons = repos.season.get_seasons().await?;
+    Ok(cached_ok().json(ListSeasonsResp { seasons }))
+}
+
+/// Ranks the faces by their scores in the season, the highest first, as they stood
+/// at its end for the ended seasons.
+#[utoipa::path(
+    tag = "season",
+    params(IdPath, LeaderboardQuery),
+    responses((status = 200, body = SeasonLeaderboardResp))
+)]
+#[get("/api/v2/seasons/{id}/leaderboard")]
+pub async fn get_season_leaderboard(
+    repos: web::Data<Repositories>,
+    path: web::Path<IdPath>,
+    mut query: web::Query<LeaderboardQuery>,
+) -> AppResult<impl Responder> {
+    debug!("path: {:?}, query: {:?}", &path, &query);
+
+    path.validate()?;
+    query.validate()?;
+
+    if query.page_size <= 0 {
+        query.page_size = DEFAULT_PAGE_SIZE
+    }
+    query.page_size = query.page_size.min(MAX_PAGE_SIZE);
+    check_page(query.page, query.page_size)?;
+
+    let season = repos
+        .season
+        .get_season_by_id(&path.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Season not found!".to_string()))?;
+    let now = chrono::Utc::now().timestamp();
+    let standings =
+        season_service::get_season_leaderboard(&repos, &season, query.page, query.page_size, now)
+            .await?;
+    Ok(cached_ok().json(SeasonLeaderboardResp { season, standings }))
+}
+
+#[utoipa::path(
+    tag = "file_resource",
+    params(IdPath),
+    responses((status = 200, body = FileResource))
+)]
+#[get("/api/v2/files/{id}")]
+pub async fn get_file(
+    repos: web::Data<Repositories>,
+    path: web::Path<IdPath>,
+) -> AppResult<impl Responder> {
+    debug!("path: {:?}", &path);
+
+    path.validate()?;
+
+    let file_resource = find_file_resource(&repos, &path.id).await?;
+    Ok(cached_ok().json(file_resource))
+}
+
+/// Serves the saved file, or redirects to the url the file is at.
+#[utoipa::path(
+    tag = "file_resource",
+    params(IdPath),
+    responses(
+        (status = 200, description = "The file", content_type = "application/octet-stream"),
+        (status = 302, description = "The file is at the `Location`")
+    )
+)]
+#[get("/api/v2/files/{id}/content")]
+pub async fn get_file_content(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    path: web::Path<IdPath>,
+) -> AppResult<HttpResponse> {
+    debug!("req: {:?}", &req);
+
+    path.validate()?;
+
+    let file_resource = find_file_resource(&repos, &path.id).await?;
+    match file_resource.uri_type {
+        UriType::Local => {
+            let file = open_local_file(&file_resource.file_uri).await?;
+            Ok(file.into_response(&req))
+        }
+        UriType::Url => Ok(HttpResponse::Found()
+            .insert_header((header::LOCATION, file_resource.file_uri))
+            .finish()),
+    }
+}
+
+/// An ok response which may be cached for `CACHE_MAX_AGE`.
+fn cached_ok() -> HttpResponseBuilder {
+    let mut resp = HttpResponse::Ok();
+    resp.insert_header(CacheControl(vec![
+        CacheDirective::Public,
+        CacheDirective::MaxAge(CACHE_MAX_AGE),
+    ]));
+    resp
+}
+
+/// An ok response which must not be cached.
+fn uncached_ok() -> HttpResponseBuilder {
+    let mut resp = HttpResponse::Ok();
+    resp.insert_header(CacheControl(vec![CacheDirective::NoStore]));
+    resp
+}
+
+async fn find_file_resource(repos: &Repositories, file_id: &str) -> AppResult<FileResource> {
+    file_resource_service::get_file_resource_by_id(repos, file_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("FileResource not found!".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+    use dotenv::dotenv;
+
+    use super::*;
+    use crate::controller;
+    use crate::controller::admin_controller;
+    use crate::entity::face_info::FaceStatus;
+    use crate::error::ErrorResp;
+
+    #[actix_rt::test]
+    async fn test_v2_faces_and_votes() {
+        dotenv().ok();
+        let repos = Repositories::in_memory();
+        for (id, score) in [("1", 1400.0), ("2", 1500.0), ("3", 1450.0)] {
+            repos
+                .face_info
+                .add_face_info(&FaceInfo {
+                    id: id.to_string(),
+                    score,
+                    status: FaceStatus::Approved,
+                    ..FaceInfo::default()
+                })
+                .await
+                .unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(repos.clone()))
+                .configure(controller::configure),
+        )
+        .await;
+
+        // Step 1: The faces are listed by score, paginated
+        let req = test::TestRequest::get()
+            .uri("/api/v2/faces?sort=score&page_size=2")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(header::CACHE_CONTROL).unwrap(),
+            &format!("public, max-age={}", CACHE_MAX_AGE)
+        );
+        let resp: serde_json::Value = test::read_body_json(resp).await;
+        let ids: Vec<&str> = resp["faces"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|face| face["face_info"]["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, vec!["2", "3"]);
+        assert_eq!(resp["total"], 3);
+
+        let req = test::TestRequest::get()
+            .uri("/api/v2/faces?sort=random")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-store"
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/api/v2/faces?sort=stars")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp: ErrorResp = test::read_body_json(resp).await;
+        assert_eq!(resp.code, "bad_request");
+
+        let req = test::TestRequest::get()
+            .uri("/api/v2/faces?sort=score&page=18446744073709551615")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_client_error());
+
+        // Step 2: A face is got by its id
+        let req = test::TestRequest::get().uri("/api/v2/faces/3").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.headers().contains_key(header::CACHE_CONTROL));
+        let resp: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(resp["face_info"]["score"], 1450.0);
+
+        let req = test::TestRequest::get().uri("/api/v2/faces/4").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // Step 3: A vote is created without content
+        let req = test::TestRequest::post()
+            .uri("/api/v2/votes")
+            .set_json(serde_json::json!({
+                "win_face_info_id": "1",
+                "lose_face_info_id": "2",
+                "voter": "tester",
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let win_face_info = repos.face_info.get_face_info_by_id("1").await.unwrap();
+        assert_eq!(win_face_info.unwrap().upvote_count, 1);
+
+        // Step 4: An added face is located under v2
+        let req = test::TestRequest::post()
+            .uri("/api/v2/faces")
+            .set_json(serde_json::json!({
+                "star_name": "star",
+                "file_id": "1",
+                "creator": "tester",
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let location = resp
+            .headers()
+            .get(header::LOCATION)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        let face_info: FaceInfo = test::read_body_json(resp).await;
+        assert_eq!(location, format!("/api/v2/faces/{}", face_info.id));
+        assert_eq!(face_info.status, FaceStatus::Pending);
+    }
+
+    #[actix_rt::test]
+    async fn test_v2_search_faces() {
+        dotenv().ok();
+        let repos = Repositories::in_memory();
+        let star_names = [
+            ("1", "Scarlett Johansson", FaceStatus::Approved),
+            ("2", "Scott Eastwood", FaceStatus::Approved),
+            ("3", "Charlize Theron", FaceStatus::Approved),
+            ("4", "Scarlett Pending", FaceStatus::Pending),
+        ];
+        for (id, star_name, status) in star_names {
+            repos
+                .face_info
+                .add_face_info(&FaceInfo {
+                    id: id.to_string(),
+                    star_name: star_name.to_string(),
+                    status,
+                    ..FaceInfo::default()
+                })
+                .await
+                .unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(repos.clone()))
+                .configure(controller::configure),
+        )
+        .await;
+
+        let search = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+        let ids = |resp: &SearchFacesResp| -> Vec<String> {
+            let faces = serde_json::to_value(&resp.faces).unwrap();
+            faces
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|face| face["face_info"]["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // Step 1: The prefixes of the star_names or their words, ignoring the case
+        let resp: SearchFacesResp =
+            test::call_and_read_body_json(&app, search("/api/v2/faces/search?q=sc")).await;
+        assert_eq!(ids(&resp), vec!["1", "2"]);
+        assert_eq!(resp.total, 2);
+        let resp: SearchFacesResp =
+            test::call_and_read_body_json(&app, search("/api/v2/faces/search?q=THER")).await;
+        assert_eq!(ids(&resp), vec!["3"]);
+
+        // Step 2: The typos are tolerated
+        let resp: SearchFacesResp =
+            test::call_and_read_body_json(&app, search("/api/v2/faces/search?q=scralett")).await;
+        assert_eq!(ids(&resp), vec!["1"]);
+        let resp: SearchFacesResp =
+            test::call_and_read_body_json(&app, search("/api/v2/faces/search?q=charlise%20theron"))
+                .await;
+        assert_eq!(ids(&resp), vec!["3"]);
+
+        // Step 3: The matches are paginated
+        let resp: SearchFacesResp = test::call_and_read_body_json(
+            &app,
+            search("/api/v2/faces/search?q=sc&page=1&page_size=1"),
+        )
+        .await;
+        assert_eq!(ids(&resp), vec!["2"]);
+        assert_eq!(resp.total, 2);
+
+        let resp = test::call_service(&app, search("/api/v2/faces/search?q=")).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp: ErrorResp = test::read_body_json(resp).await;
+        assert_eq!(resp.field_errors[0].field, "q");
+    }
+
+    #[actix_rt::test]
+    async fn test_v2_category_arena() {
+        dotenv().ok();
+        let repos = Repositories::in_memory();
+        let faces = [
+            ("1", 1500.0, "actors"),
+            ("2", 1400.0, "actors"),
+            ("3", 1600.0, "singers"),
+        ];
+        for (id, score, category) in faces {
+            repos
+                .face_info
+                .add_face_info(&FaceInfo {
+                    id: id.to_string(),
+                    score,
+                    categories: vec![category.to_string()],
+                    status: FaceStatus::Approved,
+                    ..FaceInfo::default()
+                })
+                .await
+                .unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(repos.clone()))
+                .configure(controller::configure),
+        )
+        .await;
+
+        // Step 1: The random faces are sampled in the category
+        let req = test::TestRequest::get()
+            .uri("/api/v2/faces?sort=random&page_size=3&category=actors")
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let mut ids: Vec<&str> = resp["faces"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|face| face["face_info"]["id"].as_str().unwrap())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["1", "2"]);
+
+        let req = test::TestRequest::get()
+            .uri("/api/v2/faces?sort=score&category=actors")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // Step 2: A vote in the category rates the faces in it as well
+        let vote = |win: &str, lose: &str, category: &str| {
+            test::TestRequest::post()
+                .uri("/api/v2/votes")
+                .set_json(serde_json::json!({
+                    "win_face_info_id": win,
+                    "lose_face_info_id": lose,
+                    "voter": "tester",
+                    "category": category,
+                }))
+                .to_request()
+        };
+        let resp = test::call_service(&app, vote("2", "1", "actors")).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let face_info = repos.face_info.get_face_info_by_id("2").await.unwrap();
+        assert_eq!(face_info.unwrap().upvote_count, 1);
+
+        let resp = test::call_service(&app, vote("3", "1", "actors")).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp: ErrorResp = test::read_body_json(resp).await;
+        assert_eq!(resp.field_errors[0].field, "category");
+
+        // Step 3: The leaderboard ranks by the ratings in the category
+        let req = test::TestRequest::get()
+            .uri("/api/v2/categories/actors/leaderboard")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.headers().contains_key(header::CACHE_CONTROL));
+        let resp: LeaderboardResp = test::read_body_json(resp).await;
+        let ranks: Vec<(&str, f64)> = resp
+            .entries
+            .iter()
+            .map(|entry| (entry.rating.face_info_id.as_str(), entry.rating.score))
+            .collect();
+        assert_eq!(ranks, vec![("2", 1416.0), ("1", 1384.0)]);
+        assert_eq!(resp.entries[0].rating.upvote_count, 1);
+
+        let req = test::TestRequest::get()
+            .uri("/api/v2/categories/singers/leaderboard")
+            .to_request();
+        let resp: LeaderboardResp = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.entries.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_v2_seasons() {
+        dotenv().ok();
+        let repos = Repositories::in_memory();
+        for (id, score) in [("1", 1500.0), ("2", 1300.0)] {
+            repos
+                .face_info
+                .add_face_info(&FaceInfo {
+                    id: id.to_string(),
+                    score,
+                    status: FaceStatus::Approved,
+                    ..FaceInfo::default()
+                })
+                .await
+                .unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(repos.clone()))
+                .configure(controller::configure),
+        )
+        .await;
+        admin_controller::tests::set_test_admin_token();
+        let start_season = |name: &str, soft_reset: f64| {
+            test::TestRequest::post()
+                .uri("/admin/start_season")
+                .insert_header((
+                    header::AUTHORIZATION,
+                    format!("Bearer {}", admin_controller::tests::TEST_ADMIN_TOKEN),
+                ))
+                .set_json(serde_json::json!({
+                    "name": name,
+                    "soft_reset": soft_reset,
+                    "creator": "admin",
+                }))
+                .to_request()
+        };
+        let scores = || async {
+            let face_infos = repos
+                .face_info
+                .get_face_infos_by_ids(&["1", "2"], FaceStatus::Approved)
+                .await
+                .unwrap();
+            let mut scores: Vec<(String, f64)> = face_infos
+                .into_iter()
+                .map(|face_info| (face_info.id, face_info.score))
+                .collect();
+            scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+            scores
+        };
+
+        // Step 1: The first season ends none, the standings before it are archived
+        let resp: serde_json::Value =
+            test::call_and_read_body_json(&app, start_season("s1", 0.0)).await;
+        let first_season_id = resp["season_start"]["season"]["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(resp["season_start"]["ended_season_id"].is_null());
+        assert_eq!(resp["season_start"]["snapshot_cnt"], 2);
+        assert_eq!(resp["season_start"]["reset_cnt"], 0);
+
+        // Step 2: The votes are tagged with the current season
+        let req = test::TestRequest::post()
+            .uri("/api/v2/votes")
+            .set_json(serde_json::json!({
+                "win_face_info_id": "2",
+                "lose_face_info_id": "1",
+                "voter": "tester",
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        let rating_logs = repos.rating_log.get_all_rating_logs().await.unwrap();
+        assert_eq!(rating_logs[0].season_id, first_season_id);
+        let first_season_scores = scores().await;
+
+        // Step 3: The next season soft resets the scores halfway to the initial score
+        let resp = test::call_service(&app, start_season("s2", 1.5)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp: serde_json::Value =
+            test::call_and_read_body_json(&app, start_season("s2", 0.5)).await;
+        assert_eq!(resp["season_start"]["ended_season_id"], first_season_id);
+        assert_eq!(resp["season_start"]["reset_cnt"], 2);
+        let initial_score = config::get().rating.initial_score;
+        for ((_, score), (_, first_season_score)) in scores().await.iter().zip(&first_season_scores)
+        {
+            assert_eq!(
+                *score,
+                initial_score + (first_season_score - initial_score) * 0.5
+            );
+        }
+
+        // Step 4: The ended season is ranked as it stood at its end
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/v2/seasons/{}/leaderboard", first_season_id))
+            .to_request();
+        let resp: SeasonLeaderboardResp = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.season.ended_on > 0);
+        let standings: Vec<(String, f64)> = resp
+            .standings
+            .into_iter()
+            .map(|standing| (standing.face_info_id, standing.score))
+            .collect();
+        assert_eq!(standings, first_season_scores);
+
+        let req = test::TestRequest::get().uri("/api/v2/seasons").to_request();
+        let resp: ListSeasonsResp = test::call_and_read_body_json(&app, req).await;
+        let names: Vec<&str> = resp.seasons.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["s2", "s1"]);
+        assert_eq!(resp.seasons[0].ended_on, 0);
+
+        // Step 5: The current season is ranked by the live scores
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/api/v2/seasons/{}/leaderboard",
+                resp.seasons[0].id
+            ))
+            .to_request();
+        let resp: SeasonLeaderboardResp = test::call_and_read_body_json(&app, req).await;
+        let standings: Vec<(String, f64)> = resp
+            .standings
+            .into_iter()
+            .map(|standing| (standing.face_info_id, standing.score))
+            .collect();
+        assert_eq!(standings, scores().await);
+    }
+}
@@ -0,0 +1,181 @@
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::{FindOptions, UpdateOptions};
+use mongodb::results::{DeleteResult, InsertManyResult, UpdateResult};
+use mongodb::{bson, Collection};
+
+use crate::dao::start_operation;
+use crate::entity::category_rating::CategoryRating;
+use crate::mongo;
+use crate::repository::{get_page_skip, CategoryRatingRepository, RepositoryResult};
+
+/// The category_rating repository backed by the "category_rating" collection.
+pub struct MongoCategoryRatingDao;
+
+#[async_trait]
+impl CategoryRatingRepository for MongoCategoryRatingDao {
+    async fn add_category_ratings(
+        &self,
+        category_ratings: Vec<CategoryRating>,
+    ) -> RepositoryResult<()> {
+        let operation = start_operation("mongo", "category_rating", "add_category_ratings");
+        operation.record("category_rating.count", category_ratings.len() as i64);
+        if !category_ratings.is_empty() {
+            add_category_ratings(category_ratings).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_all_category_ratings(&self) -> RepositoryResult<Vec<CategoryRating>> {
+        let _operation = start_operation("mongo", "category_rating", "get_all_category_ratings");
+        Ok(get_category_ratings_by_doc_filter(doc! {}, None).await?)
+    }
+
+    async fn count_all_category_ratings(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "category_rating", "count_all_category_ratings");
+        Ok(count_category_ratings_by_doc_filter(doc! {}).await?)
+    }
+
+    async fn get_category_ratings(
+        &self,
+        category: &str,
+        face_info_ids: &[&str],
+    ) -> RepositoryResult<Vec<CategoryRating>> {
+        let operation = start_operation("mongo", "category_rating", "get_category_ratings");
+        operation.record("face_info.ids", face_info_ids.join(","));
+        Ok(get_category_ratings_by_doc_filter(
+            doc! {"category": category, "face_info_id": {"$in": face_info_ids}},
+            None,
+        )
+        .await?)
+    }
+
+    async fn update_category_rating(
+        &self,
+        category: &str,
+        face_info_id: &str,
+        score: f64,
+        upvote: bool,
+        now: i64,
+    ) -> RepositoryResult<()> {
+        let operation = start_operation("mongo", "category_rating", "update_category_rating");
+        operation.record("face_info.id", face_info_id.to_string());
+        let (vote_field, other_vote_field) = if upvote {
+            ("upvote_count", "downvote_count")
+        } else {
+            ("downvote_count", "upvote_count")
+        };
+        upsert_category_rating_by_doc_filter(
+            doc! {"category": category, "face_info_id": face_info_id},
+            doc! {
+                "$set": {"score": score, "updated_on": now},
+                "$inc": {vote_field: 1_i64},
+                "$setOnInsert": {other_vote_field: 0_i64, "created_on": now},
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn get_category_ratings_page(
+        &self,
+        category: &str,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<CategoryRating>> {
+        let _operation = start_operation("mongo", "category_rating", "get_category_ratings_page");
+        let options = FindOptions::builder()
+            .sort(doc! {"score": -1, "face_info_id": 1})
+            .skip(get_page_skip(page, page_size))
+            .limit(page_size)
+            .build();
+        Ok(get_category_ratings_by_doc_filter(doc! {"category": category}, options).await?)
+    }
+
+    async fn delete_category_ratings_by_face_info_ids(
+        &self,
+        face_info_ids: &[String],
+    ) -> RepositoryResult<u64> {
+        let _operation = start_operation(
+            "mongo",
+            "category_rating",
+            "delete_category_ratings_by_face_info_ids",
+        );
+        let res =
+            delete_category_ratings_by_doc_filter(doc! {"face_info_id": {"$in": face_info_ids}})
+                .await?;
+        Ok(res.deleted_count)
+    }
+}
+
+/// Adds new category_ratings to the "category_rating" collection in the database.
+async fn add_category_ratings(
+    category_ratings: Vec<CategoryRating>,
+) -> mongodb::error::Result<InsertManyResult> {
+    let collection: Collection<CategoryRating> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(CategoryRating::db_name())
+        .collection(CategoryRating::coll_name());
+    collection.insert_many(category_ratings, None).await
+}
+
+/// Counts the category_ratings by doc filter.
+async fn count_category_ratings_by_doc_filter(doc_filter: Document) -> mongodb::error::Result<u64> {
+    let collection: Collection<CategoryRating> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(CategoryRating::db_name())
+        .collection(CategoryRating::coll_name());
+    collection.count_documents(doc_filter, None).await
+}
+
+/// Get multiple category_rating by doc filter.
+async fn get_category_ratings_by_doc_filter(
+    doc_filter: Document,
+    options: impl Into<Option<FindOptions>>,
+) -> mongodb::error::Result<Vec<CategoryRating>> {
+    let collection = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(CategoryRating::db_name())
+        .collection(CategoryRating::coll_name());
+
+    let mut ret_category_ratings: Vec<CategoryRating> = Vec::new();
+    let mut results = collection.find(doc_filter, options).await?;
+    while let Some(result) = results.next().await {
+        let category_rating: CategoryRating = bson::from_document(result?)?;
+        ret_category_ratings.push(category_rating);
+    }
+    Ok(ret_category_ratings)
+}
+
+/// Updates the category_rating by doc filter, inserted if absent.
+async fn upsert_category_rating_by_doc_filter(
+    doc_filter: Document,
+    update_info: Document,
+) -> mongodb::error::Result<UpdateResult> {
+    let collection: Collection<CategoryRating> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(CategoryRating::db_name())
+        .collection(CategoryRating::coll_name());
+
+    let options = UpdateOptions::builder().upsert(true).build();
+    collection
+        .update_one(doc_filter, update_info, options)
+        .await
+}
+
+/// Hard delete the category_ratings by doc filter.
+async fn delete_category_ratings_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<DeleteResult> {
+    let collection: Collection<CategoryRating> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(CategoryRating::db_name())
+        .collection(CategoryRating::coll_name());
+    collection.delete_many(doc_filter, None).await
+}
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+
+use crate::dao::start_operation;
+use crate::repository::{DatabaseRepository, RepositoryResult};
+use crate::resource;
+
+/// The database repository backed by the mongo deployment.
+pub struct MongoDatabaseDao;
+
+#[async_trait]
+impl DatabaseRepository for MongoDatabaseDao {
+    async fn ping(&self) -> RepositoryResult<()> {
+        let _operation = start_operation("mongo", "database", "ping");
+        Ok(resource::check_mongo().await?)
+    }
+}
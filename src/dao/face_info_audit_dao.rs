@@ -0,0 +1,137 @@
+use futures_util::StreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::FindOptions;
+use mongodb::results::{DeleteResult, InsertManyResult};
+use mongodb::{bson, Collection};
+
+use async_trait::async_trait;
+
+use crate::dao::exclude_deleted;
+use crate::dao::start_operation;
+use crate::entity::face_info_audit::FaceInfoAudit;
+use crate::mongo;
+use crate::repository::{FaceInfoAuditRepository, RepositoryResult};
+
+/// The face_info_audit repository backed by the "face_info_audit" collection.
+pub struct MongoFaceInfoAuditDao;
+
+#[async_trait]
+impl FaceInfoAuditRepository for MongoFaceInfoAuditDao {
+    async fn add_face_info_audits(
+        &self,
+        face_info_audits: Vec<FaceInfoAudit>,
+    ) -> RepositoryResult<()> {
+        let _operation = start_operation("mongo", "face_info_audit", "add_face_info_audits");
+        if !face_info_audits.is_empty() {
+            add_face_info_audits(face_info_audits).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_face_info_audits_by_face_info_id(
+        &self,
+        face_info_id: &str,
+    ) -> RepositoryResult<Vec<FaceInfoAudit>> {
+        let _operation = start_operation(
+            "mongo",
+            "face_info_audit",
+            "get_face_info_audits_by_face_info_id",
+        );
+        Ok(get_face_info_audits_by_doc_filter(doc! {"face_info_id": face_info_id}).await?)
+    }
+
+    async fn get_all_face_info_audits(&self) -> RepositoryResult<Vec<FaceInfoAudit>> {
+        let _operation = start_operation("mongo", "face_info_audit", "get_all_face_info_audits");
+        Ok(get_face_info_audits_with_deleted_by_doc_filter(doc! {}).await?)
+    }
+
+    async fn count_all_face_info_audits(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "face_info_audit", "count_all_face_info_audits");
+        Ok(count_face_info_audits_with_deleted_by_doc_filter(doc! {}).await?)
+    }
+
+    async fn delete_face_info_audits_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let _operation =
+            start_operation("mongo", "face_info_audit", "delete_face_info_audits_by_ids");
+        let res = delete_face_info_audits_by_doc_filter(doc! {"id": {"$in": ids}}).await?;
+        Ok(res.deleted_count)
+    }
+}
+
+/// Adds new face_info_audits to the "face_info_audit" collection in the database.
+async fn add_face_info_audits(
+    face_info_audits: Vec<FaceInfoAudit>,
+) -> mongodb::error::Result<InsertManyResult> {
+    let collection: Collection<FaceInfoAudit> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceInfoAudit::db_name())
+        .collection(FaceInfoAudit::coll_name());
+    collection.insert_many(face_info_audits, None).await
+}
+
+/// Get multiple face_info_audit by doc filter, the latest comes first.
+async fn get_face_info_audits_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<Vec<FaceInfoAudit>> {
+    let collection = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceInfoAudit::db_name())
+        .collection(FaceInfoAudit::coll_name());
+
+    let options = FindOptions::builder().sort(doc! {"created_on": -1}).build();
+
+    let mut ret_face_info_audits: Vec<FaceInfoAudit> = Vec::new();
+    let mut results = collection
+        .find(exclude_deleted(doc_filter), options)
+        .await?;
+    while let Some(result) = results.next().await {
+        let face_info_audit: FaceInfoAudit = bson::from_document(result?)?;
+        ret_face_info_audits.push(face_info_audit);
+    }
+    Ok(ret_face_info_audits)
+}
+
+/// Get multiple face_info_audit by doc filter, including the soft deleted ones.
+async fn get_face_info_audits_with_deleted_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<Vec<FaceInfoAudit>> {
+    let collection = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceInfoAudit::db_name())
+        .collection(FaceInfoAudit::coll_name());
+
+    let mut ret_face_info_audits: Vec<FaceInfoAudit> = Vec::new();
+    let mut results = collection.find(doc_filter, None).await?;
+    while let Some(result) = results.next().await {
+        let face_info_audit: FaceInfoAudit = bson::from_document(result?)?;
+        ret_face_info_audits.push(face_info_audit);
+    }
+    Ok(ret_face_info_audits)
+}
+
+/// Counts the face_info_audits by doc filter, including the soft deleted ones.
+async fn count_face_info_audits_with_deleted_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<u64> {
+    let collection: Collection<FaceInfoAudit> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceInfoAudit::db_name())
+        .collection(FaceInfoAudit::coll_name());
+    collection.count_documents(doc_filter, None).await
+}
+
+/// Hard delete the face_info_audits by doc filter.
+async fn delete_face_info_audits_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<DeleteResult> {
+    let collection: Collection<FaceInfoAudit> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceInfoAudit::db_name())
+        .collection(FaceInfoAudit::coll_name());
+    collection.delete_many(doc_filter, None).await
+}
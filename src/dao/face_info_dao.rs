@@ -1,41 +1,534 @@
 use futures_util::StreamExt;
 use mongodb::bson::{doc, Document};
-use mongodb::results::{InsertOneResult, UpdateResult};
+use mongodb::options::{FindOptions, UpdateModifications};
+use mongodb::results::{DeleteResult, InsertManyResult, InsertOneResult, UpdateResult};
 use mongodb::{bson, Collection};
 
-use crate::entity::face_info::FaceInfo;
+use async_trait::async_trait;
+
+use crate::algorithm::fuzzy_match;
+use crate::dao::exclude_deleted;
+use crate::dao::start_operation;
+use crate::entity::face_info::{FaceInfo, FaceInfoOrder, FaceInfoPatch, FaceStatus};
+use crate::entity::{DELETED, NOT_DELETED};
 use crate::mongo;
+use crate::repository::{get_page_skip, FaceInfoRepository, RepositoryResult};
 use crate::resource::mongo::MONGO_CLIENT;
 
+/// The face_info repository backed by the "face_info" collection.
+pub struct MongoFaceInfoDao;
+
+#[async_trait]
+impl FaceInfoRepository for MongoFaceInfoDao {
+    async fn add_face_info(&self, face_info: &FaceInfo) -> RepositoryResult<()> {
+        let _operation = start_operation("mongo", "face_info", "add_face_info");
+        add_one_face_info(face_info).await?;
+        Ok(())
+    }
+
+    async fn add_face_infos(&self, face_infos: Vec<FaceInfo>) -> RepositoryResult<()> {
+        let _operation = start_operation("mongo", "face_info", "add_face_infos");
+        if !face_infos.is_empty() {
+            add_face_infos(face_infos).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_face_info_by_id(&self, id: &str) -> RepositoryResult<Option<FaceInfo>> {
+        let operation = start_operation("mongo", "face_info", "get_face_info_by_id");
+        operation.record("face_info.id", id.to_string());
+        Ok(get_one_face_info_by_doc_filter(doc! {"id": id}).await?)
+    }
+
+    async fn get_face_info_by_file_id(&self, file_id: &str) -> RepositoryResult<Option<FaceInfo>> {
+        let _operation = start_operation("mongo", "face_info", "get_face_info_by_file_id");
+        Ok(get_one_face_info_by_doc_filter(doc! {"file_id": file_id}).await?)
+    }
+
+    async fn get_face_infos_by_ids(
+        &self,
+        ids: &[&str],
+        status: FaceStatus,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let operation = start_operation("mongo", "face_info", "get_face_infos_by_ids");
+        operation.record("face_info.ids", ids.join(","));
+        let face_infos = get_face_infos_by_doc_filter(doc! {
+            "id": {"$in": ids},
+            "status": status.as_str(),
+        })
+        .await?;
+        operation.record("db.response.returned_rows", face_infos.len() as i64);
+        Ok(face_infos)
+    }
+
+    async fn get_face_infos_by_status(
+        &self,
+        status: FaceStatus,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("mongo", "face_info", "get_face_infos_by_status");
+        Ok(get_face_infos_page_by_doc_filter(
+            doc! {"status": status.as_str()},
+            doc! {"created_on": 1, "id": 1},
+            page,
+            page_size,
+        )
+        .await?)
+    }
+
+    async fn get_approved_face_infos(
+        &self,
+        order: FaceInfoOrder,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("mongo", "face_info", "get_approved_face_infos");
+        let sort = match order {
+            FaceInfoOrder::Score => doc! {"score": -1, "id": 1},
+            FaceInfoOrder::CreatedOn => doc! {"created_on": -1, "id": 1},
+        };
+        Ok(get_face_infos_page_by_doc_filter(
+            doc! {"status": FaceStatus::Approved.as_str()},
+            sort,
+            page,
+            page_size,
+        )
+        .await?)
+    }
+
+    async fn get_approved_face_infos_after(
+        &self,
+        after_id: &str,
+        limit: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("mongo", "face_info", "get_approved_face_infos_after");
+        Ok(get_face_infos_page_by_doc_filter(
+            doc! {"status": FaceStatus::Approved.as_str(), "id": {"$gt": after_id}},
+            doc! {"id": 1},
+            0,
+            limit,
+        )
+        .await?)
+    }
+
+    async fn search_face_infos_by_trigrams(
+        &self,
+        trigrams: &[String],
+        limit: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("mongo", "face_info", "search_face_infos_by_trigrams");
+        if trigrams.is_empty() {
+            return Ok(vec![]);
+        }
+        Ok(search_face_infos_by_trigrams(trigrams, limit).await?)
+    }
+
+    async fn search_face_infos_by_prefix(
+        &self,
+        prefix: &str,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("mongo", "face_info", "search_face_infos_by_prefix");
+        Ok(get_face_infos_page_by_doc_filter(
+            star_name_prefix_filter(prefix),
+            doc! {"score": -1, "id": 1},
+            page,
+            page_size,
+        )
+        .await?)
+    }
+
+    async fn count_face_infos_by_prefix(&self, prefix: &str) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "face_info", "count_face_infos_by_prefix");
+        Ok(
+            count_face_infos_with_deleted_by_doc_filter(exclude_deleted(star_name_prefix_filter(
+                prefix,
+            )))
+            .await?,
+        )
+    }
+
+    async fn get_face_info_sample(
+        &self,
+        size: i64,
+        category: Option<&str>,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("mongo", "face_info", "get_face_info_sample");
+        Ok(get_face_info_sample(size, category).await?)
+    }
+
+    async fn get_all_face_infos(&self) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("mongo", "face_info", "get_all_face_infos");
+        Ok(get_face_infos_with_deleted_by_doc_filter(doc! {}).await?)
+    }
+
+    async fn count_all_face_infos(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "face_info", "count_all_face_infos");
+        Ok(count_face_infos_with_deleted_by_doc_filter(doc! {}).await?)
+    }
+
+    async fn count_face_infos_by_status(&self, status: FaceStatus) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "face_info", "count_face_infos_by_status");
+        Ok(count_face_infos_with_deleted_by_doc_filter(exclude_deleted(
+            doc! {"status": status.as_str()},
+        ))
+        .await?)
+    }
+
+    async fn get_deleted_face_infos_before(
+        &self,
+        deleted_before: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("mongo", "face_info", "get_deleted_face_infos_before");
+        Ok(get_face_infos_with_deleted_by_doc_filter(
+            doc! {"is_deleted": DELETED, "deleted_on": {"$lt": deleted_before}},
+        )
+        .await?)
+    }
+
+    async fn update_face_info_rating(
+        &self,
+        id: &str,
+        score: f64,
+        upvote: bool,
+        voter: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let operation = start_operation("mongo", "face_info", "update_face_info_rating");
+        operation.record("face_info.id", id.to_string());
+        let filter_doc = doc! {"id": id};
+
+        let update_doc = if upvote {
+            doc! {
+                "$set": {"score": score, "updater": voter, "updated_on": now},
+                "$inc": {"upvote_count": 1},
+            }
+        } else {
+            doc! {
+                "$set": {"score": score, "updater": voter, "updated_on": now},
+                "$inc": {"downvote_count": 1},
+            }
+        };
+
+        let res = update_face_info_by_doc_filter(filter_doc, update_doc).await?;
+        operation.record("db.response.matched_rows", res.matched_count as i64);
+        Ok(res.matched_count > 0)
+    }
+
+    async fn soft_reset_face_info_scores(
+        &self,
+        target: f64,
+        soft_reset: f64,
+    ) -> RepositoryResult<u64> {
+        let operation = start_operation("mongo", "face_info", "soft_reset_face_info_scores");
+        // A pipeline, so the new score is computed from the stored one
+        let res = update_face_infos_by_doc_filter(
+            exclude_deleted(doc! {}),
+            vec![doc! {
+                "$set": {
+                    "score": {
+                        "$add": [
+                            "$score",
+                            {"$multiply": [{"$subtract": [target, "$score"]}, soft_reset]},
+                        ],
+                    },
+                },
+            }],
+        )
+        .await?;
+        operation.record("db.response.matched_rows", res.matched_count as i64);
+        Ok(res.matched_count)
+    }
+
+    async fn update_face_info_by_patch(
+        &self,
+        face_info: &FaceInfo,
+        patch: &FaceInfoPatch,
+        updater: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let _operation = start_operation("mongo", "face_info", "update_face_info_by_patch");
+        let filter_doc = patch_filter(face_info, patch);
+        let mut set_doc = doc! {"updater": updater, "updated_on": now};
+        if let Some(star_name) = &patch.star_name {
+            set_doc.insert("star_name", star_name);
+            set_doc.insert(STAR_NAME_TRIGRAMS, fuzzy_match::trigrams(star_name));
+            set_doc.insert(STAR_NAME_SUFFIXES, fuzzy_match::word_suffixes(star_name));
+        }
+        if let Some(file_id) = &patch.file_id {
+            set_doc.insert("file_id", file_id);
+        }
+        if let Some(categories) = &patch.categories {
+            set_doc.insert("categories", categories);
+        }
+
+        let res = update_face_info_by_doc_filter(filter_doc, doc! {"$set": set_doc}).await?;
+        Ok(res.matched_count > 0)
+    }
+
+    async fn update_face_info_status(
+        &self,
+        id: &str,
+        old_status: FaceStatus,
+        status: FaceStatus,
+        moderator: &str,
+        reason: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let _operation = start_operation("mongo", "face_info", "update_face_info_status");
+        let res = update_face_info_by_doc_filter(
+            doc! {"id": id, "is_deleted": NOT_DELETED, "status": old_status.as_str()},
+            doc! {
+                "$set": {
+                    "status": status.as_str(),
+                    "moderator": moderator,
+                    "moderation_reason": reason,
+                    "moderated_on": now,
+                    "updater": moderator,
+                    "updated_on": now,
+                },
+            },
+        )
+        .await?;
+        Ok(res.matched_count > 0)
+    }
+
+    async fn soft_delete_face_info(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let _operation = start_operation("mongo", "face_info", "soft_delete_face_info");
+        let res = update_face_info_by_doc_filter(
+            doc! {"id": id, "is_deleted": NOT_DELETED},
+            doc! {
+                "$set": {"is_deleted": DELETED, "deleted_on": now, "updater": operator, "updated_on": now},
+            },
+        )
+        .await?;
+        Ok(res.matched_count > 0)
+    }
+
+    async fn restore_face_info(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let _operation = start_operation("mongo", "face_info", "restore_face_info");
+        let res = update_face_info_by_doc_filter(
+            doc! {"id": id, "is_deleted": DELETED},
+            doc! {
+                "$set": {"is_deleted": NOT_DELETED, "deleted_on": 0_i64, "updater": operator, "updated_on": now},
+            },
+        )
+        .await?;
+        Ok(res.matched_count > 0)
+    }
+
+    async fn delete_face_infos_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "face_info", "delete_face_infos_by_ids");
+        let res = delete_face_infos_by_doc_filter(doc! {"id": {"$in": ids}}).await?;
+        Ok(res.deleted_count)
+    }
+}
+
+/// Approves the face_infos created before the moderation status was introduced,
+/// applied by a data migration.
+pub async fn init_face_info_status() -> mongodb::error::Result<UpdateResult> {
+    update_face_infos_by_doc_filter(
+        doc! {"status": {"$exists": false}},
+        doc! {"$set": {"status": FaceStatus::Approved.as_str()}},
+    )
+    .await
+}
+
+/// Sets no categories on the face_infos added before the categories,
+/// applied by a data migration.
+pub async fn init_face_info_categories() -> mongodb::error::Result<UpdateResult> {
+    update_face_infos_by_doc_filter(
+        doc! {"categories": {"$exists": false}},
+        doc! {"$set": {"categories": []}},
+    )
+    .await
+}
+
+/// Gets the filter of the face_info as it was read, on the fields the patch changes,
+/// so a concurrent update of them is not overwritten.
+///
+/// No categories also matches a document without the field, which is how the
+/// face_infos added before the categories are stored until they are migrated.
+fn patch_filter(face_info: &FaceInfo, patch: &FaceInfoPatch) -> Document {
+    let mut filter_doc = doc! {"id": &face_info.id, "is_deleted": NOT_DELETED};
+    if patch.star_name.is_some() {
+        filter_doc.insert("star_name", &face_info.star_name);
+    }
+    if patch.file_id.is_some() {
+        filter_doc.insert("file_id", &face_info.file_id);
+    }
+    if patch.categories.is_some() {
+        if face_info.categories.is_empty() {
+            filter_doc.insert("categories", doc! {"$in": [[], null]});
+        } else {
+            filter_doc.insert("categories", &face_info.categories);
+        }
+    }
+    filter_doc
+}
+
+/// Indexes the star_names of the face_infos added before the search,
+/// applied by a data migration.
+pub async fn init_star_name_trigrams() -> mongodb::error::Result<u64> {
+    let collection: Collection<Document> = MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceInfo::db_name())
+        .collection(FaceInfo::coll_name());
+
+    let mut modified_cnt = 0;
+    let mut results = collection
+        .find(doc! {STAR_NAME_TRIGRAMS: {"$exists": false}}, None)
+        .await?;
+    while let Some(result) = results.next().await {
+        let face_info: FaceInfo = bson::from_document(result?)?;
+        let res = collection
+            .update_one(
+                doc! {"id": &face_info.id},
+                doc! {"$set": {STAR_NAME_TRIGRAMS: fuzzy_match::trigrams(&face_info.star_name)}},
+                None,
+            )
+            .await?;
+        modified_cnt += res.modified_count;
+    }
+    Ok(modified_cnt)
+}
+
+/// Indexes the star_names of the face_infos added before the prefix search,
+/// applied by a data migration.
+pub async fn init_star_name_suffixes() -> mongodb::error::Result<u64> {
+    let collection: Collection<Document> = MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceInfo::db_name())
+        .collection(FaceInfo::coll_name());
+
+    let mut modified_cnt = 0;
+    let mut results = collection
+        .find(doc! {STAR_NAME_SUFFIXES: {"$exists": false}}, None)
+        .await?;
+    while let Some(result) = results.next().await {
+        let face_info: FaceInfo = bson::from_document(result?)?;
+        let res = collection
+            .update_one(
+                doc! {"id": &face_info.id},
+                doc! {"$set": {STAR_NAME_SUFFIXES: fuzzy_match::word_suffixes(&face_info.star_name)}},
+                None,
+            )
+            .await?;
+        modified_cnt += res.modified_count;
+    }
+    Ok(modified_cnt)
+}
+
+/// The field of the star_name's trigrams, the text index of the search,
+/// see `algorithm::fuzzy_match`. It's not a field of `FaceInfo`.
+pub const STAR_NAME_TRIGRAMS: &str = "star_name_trigrams";
+
+/// The field of the star_name from each of its words on, lowercased, the index of
+/// the prefix search, see `fuzzy_match::word_suffixes`. It's not a field of `FaceInfo`.
+pub const STAR_NAME_SUFFIXES: &str = "star_name_suffixes";
+
+/// Gets the face_info's document with the trigrams and the word suffixes of its star_name.
+fn to_indexed_document(face_info: &FaceInfo) -> mongodb::error::Result<Document> {
+    let mut document = bson::to_document(face_info)?;
+    document.insert(
+        STAR_NAME_TRIGRAMS,
+        fuzzy_match::trigrams(&face_info.star_name),
+    );
+    document.insert(
+        STAR_NAME_SUFFIXES,
+        fuzzy_match::word_suffixes(&face_info.star_name),
+    );
+    Ok(document)
+}
+
+/// Gets the filter of the approved face_infos whose star_name, or one of its words,
+/// starts with the prefix. The regex is anchored, so it's bounded by the index.
+fn star_name_prefix_filter(prefix: &str) -> Document {
+    let prefix = fuzzy_match::normalize_prefix(prefix);
+    doc! {
+        STAR_NAME_SUFFIXES: {"$regex": format!("^{}", regex::escape(&prefix))},
+        "status": FaceStatus::Approved.as_str(),
+    }
+}
+
 /// Adds a new face_info to the "face_info" collection in the database.
-pub async fn add_one_face_info(face_info: &FaceInfo) -> mongodb::error::Result<InsertOneResult> {
-    let collection: Collection<FaceInfo> = mongo::MONGO_CLIENT
+async fn add_one_face_info(face_info: &FaceInfo) -> mongodb::error::Result<InsertOneResult> {
+    let collection: Collection<Document> = mongo::MONGO_CLIENT
         .get()
+        .await?
+        .database(FaceInfo::db_name())
+        .collection(FaceInfo::coll_name());
+    collection
+        .insert_one(to_indexed_document(face_info)?, None)
         .await
+}
+
+/// Adds new face_infos to the "face_info" collection in the database.
+async fn add_face_infos(face_infos: Vec<FaceInfo>) -> mongodb::error::Result<InsertManyResult> {
+    let collection: Collection<Document> = mongo::MONGO_CLIENT
+        .get()
+        .await?
         .database(FaceInfo::db_name())
         .collection(FaceInfo::coll_name());
-    collection.insert_one(face_info, None).await
+    let documents = face_infos
+        .iter()
+        .map(to_indexed_document)
+        .collect::<mongodb::error::Result<Vec<_>>>()?;
+    collection.insert_many(documents, None).await
 }
 
-/// Gets the face_info by doc filter.
-pub async fn get_one_face_info_by_doc_filter(
+/// Counts the face_infos by doc filter, including the soft deleted ones.
+async fn count_face_infos_with_deleted_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<u64> {
+    let collection: Collection<FaceInfo> = MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceInfo::db_name())
+        .collection(FaceInfo::coll_name());
+    collection.count_documents(doc_filter, None).await
+}
+
+/// Gets the face_info by doc filter, soft deleted face_infos are excluded.
+async fn get_one_face_info_by_doc_filter(
     doc_filter: Document,
 ) -> mongodb::error::Result<Option<FaceInfo>> {
     let collection = MONGO_CLIENT
         .get()
-        .await
+        .await?
         .database(FaceInfo::db_name())
         .collection(FaceInfo::coll_name());
-    collection.find_one(doc_filter, None).await
+    collection.find_one(exclude_deleted(doc_filter), None).await
+}
+
+/// Get multiple face_info by doc filter, soft deleted face_infos are excluded.
+async fn get_face_infos_by_doc_filter(
+    doc_filter: Document,
+) -> Result<Vec<FaceInfo>, mongodb::error::Error> {
+    get_face_infos_with_deleted_by_doc_filter(exclude_deleted(doc_filter)).await
 }
 
-/// Get multiple face_info by doc filter.
-pub async fn get_face_infos_by_doc_filter(
+/// Get multiple face_info by doc filter, including the soft deleted ones.
+async fn get_face_infos_with_deleted_by_doc_filter(
     doc_filter: Document,
 ) -> Result<Vec<FaceInfo>, mongodb::error::Error> {
     let collection = MONGO_CLIENT
         .get()
-        .await
+        .await?
         .database(FaceInfo::db_name())
         .collection(FaceInfo::coll_name());
 
@@ -50,29 +543,93 @@ pub async fn get_face_infos_by_doc_filter(
     Ok(ret_face_infos)
 }
 
+/// Get a page of face_info by doc filter, soft deleted face_infos are excluded.
+async fn get_face_infos_page_by_doc_filter(
+    doc_filter: Document,
+    sort: Document,
+    page: u64,
+    page_size: i64,
+) -> Result<Vec<FaceInfo>, mongodb::error::Error> {
+    let collection = MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceInfo::db_name())
+        .collection(FaceInfo::coll_name());
+
+    let options = FindOptions::builder()
+        .sort(sort)
+        .skip(get_page_skip(page, page_size))
+        .limit(page_size)
+        .build();
+
+    let mut ret_face_infos: Vec<FaceInfo> = Vec::new();
+    let mut results = collection
+        .find(exclude_deleted(doc_filter), options)
+        .await?;
+    while let Some(result) = results.next().await {
+        let face_info: FaceInfo = bson::from_document(result?)?;
+        ret_face_infos.push(face_info);
+    }
+    Ok(ret_face_infos)
+}
+
 /// Update the face_info by id.
-pub async fn update_face_info_by_doc_filter(
+async fn update_face_info_by_doc_filter(
     doc_filter: Document,
     update_info: Document,
 ) -> mongodb::error::Result<UpdateResult> {
     let collection: Collection<FaceInfo> = MONGO_CLIENT
         .get()
-        .await
+        .await?
         .database(FaceInfo::db_name())
         .collection(FaceInfo::coll_name());
 
     collection.update_one(doc_filter, update_info, None).await
 }
 
-/// Get face_info randomly
-pub async fn get_face_info_sample(size: i64) -> Result<Vec<FaceInfo>, mongodb::error::Error> {
+/// Update all the face_infos matching the doc filter, by an update document or pipeline.
+async fn update_face_infos_by_doc_filter(
+    doc_filter: Document,
+    update_info: impl Into<UpdateModifications>,
+) -> mongodb::error::Result<UpdateResult> {
     let collection: Collection<FaceInfo> = MONGO_CLIENT
         .get()
-        .await
+        .await?
+        .database(FaceInfo::db_name())
+        .collection(FaceInfo::coll_name());
+
+    collection.update_many(doc_filter, update_info, None).await
+}
+
+/// Hard delete the face_infos by doc filter.
+async fn delete_face_infos_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<DeleteResult> {
+    let collection: Collection<FaceInfo> = MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceInfo::db_name())
+        .collection(FaceInfo::coll_name());
+
+    collection.delete_many(doc_filter, None).await
+}
+
+/// Get approved face_info randomly, in the category if any, soft deleted face_infos are excluded.
+async fn get_face_info_sample(
+    size: i64,
+    category: Option<&str>,
+) -> Result<Vec<FaceInfo>, mongodb::error::Error> {
+    let collection: Collection<FaceInfo> = MONGO_CLIENT
+        .get()
+        .await?
         .database(FaceInfo::db_name())
         .collection(FaceInfo::coll_name());
 
-    let pipeline = vec![doc! {"$sample": {"size": size}}];
+    let mut match_doc = doc! {"is_deleted": NOT_DELETED, "status": FaceStatus::Approved.as_str()};
+    if let Some(category) = category {
+        match_doc.insert("categories", category);
+    }
+    let pipeline = vec![doc! {"$match": match_doc}, doc! {"$sample": {"size": size}}];
 
     let mut ret_face_infos: Vec<FaceInfo> = Vec::new();
     let mut results = collection.aggregate(pipeline, None).await?;
@@ -83,3 +640,72 @@ pub async fn get_face_info_sample(size: i64) -> Result<Vec<FaceInfo>, mongodb::e
     }
     Ok(ret_face_infos)
 }
+
+/// Get the approved face_infos sharing the most trigrams of their star_name,
+/// soft deleted face_infos are excluded.
+async fn search_face_infos_by_trigrams(
+    trigrams: &[String],
+    limit: i64,
+) -> Result<Vec<FaceInfo>, mongodb::error::Error> {
+    let collection: Collection<FaceInfo> = MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceInfo::db_name())
+        .collection(FaceInfo::coll_name());
+
+    let pipeline = vec![
+        doc! {"$match": {
+            STAR_NAME_TRIGRAMS: {"$in": trigrams},
+            "is_deleted": NOT_DELETED,
+            "status": FaceStatus::Approved.as_str(),
+        }},
+        doc! {"$addFields": {"matched_cnt": {"$size": {
+            "$setIntersection": [format!("${}", STAR_NAME_TRIGRAMS), trigrams],
+        }}}},
+        doc! {"$sort": {"matched_cnt": -1, "id": 1}},
+        doc! {"$limit": limit},
+    ];
+
+    let mut ret_face_infos: Vec<FaceInfo> = Vec::new();
+    let mut results = collection.aggregate(pipeline, None).await?;
+    while let Some(result) = results.next().await {
+        let face_info: FaceInfo = bson::from_document(result?)?;
+        ret_face_infos.push(face_info);
+    }
+    Ok(ret_face_infos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patch_filter_of_legacy_face_info() {
+        // Step 1: A face_info added before the categories has no such field
+        let legacy_doc = doc! {"id": "1", "star_name": "Jane Doe", "is_deleted": NOT_DELETED};
+        let face_info: FaceInfo = bson::from_document(legacy_doc).unwrap();
+        assert!(face_info.categories.is_empty());
+
+        // Step 2: Its categories update still matches the document
+        let patch = FaceInfoPatch {
+            categories: Some(vec!["actors".to_string()]),
+            ..FaceInfoPatch::default()
+        };
+        let filter_doc = patch_filter(&face_info, &patch);
+        assert_eq!(
+            filter_doc.get_document("categories").unwrap(),
+            &doc! {"$in": [[], null]}
+        );
+
+        // Step 3: Known categories are matched exactly
+        let face_info = FaceInfo {
+            categories: vec!["actors".to_string()],
+            ..face_info
+        };
+        let filter_doc = patch_filter(&face_info, &patch);
+        assert_eq!(
+            filter_doc.get_array("categories").unwrap(),
+            &vec![bson::Bson::from("actors")]
+        );
+    }
+}
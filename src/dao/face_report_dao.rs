@@ -0,0 +1,243 @@
+use futures_util::StreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::options::UpdateOptions;
+use mongodb::results::{DeleteResult, InsertManyResult, UpdateResult};
+use mongodb::{bson, Collection};
+
+use async_trait::async_trait;
+
+use crate::dao::start_operation;
+use crate::entity::face_report::{FaceReport, FaceReportSummary};
+use crate::entity::NOT_DELETED;
+use crate::mongo;
+use crate::repository::{FaceReportRepository, RepositoryResult};
+
+/// The face_report repository backed by the "face_report" collection.
+pub struct MongoFaceReportDao;
+
+#[async_trait]
+impl FaceReportRepository for MongoFaceReportDao {
+    async fn add_face_report_if_absent(&self, face_report: &FaceReport) -> RepositoryResult<bool> {
+        let _operation = start_operation("mongo", "face_report", "add_face_report_if_absent");
+        let res = add_face_report_if_absent(
+            doc! {
+                "face_info_id": &face_report.face_info_id,
+                "reporter": &face_report.reporter,
+                "is_resolved": 0_i64,
+                "is_deleted": NOT_DELETED,
+            },
+            face_report,
+        )
+        .await;
+        match res {
+            Ok(res) => Ok(res.upserted_id.is_some()),
+            // A concurrent report won the unique index
+            Err(e) if is_duplicate_key(&e) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn add_face_reports(&self, face_reports: Vec<FaceReport>) -> RepositoryResult<()> {
+        let operation = start_operation("mongo", "face_report", "add_face_reports");
+        operation.record("face_report.count", face_reports.len() as i64);
+        if !face_reports.is_empty() {
+            add_face_reports(face_reports).await?;
+        }
+        Ok(())
+    }
+
+    async fn count_unresolved_face_reports(&self, face_info_id: &str) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "face_report", "count_unresolved_face_reports");
+        Ok(count_face_reports_by_doc_filter(doc! {
+            "face_info_id": face_info_id,
+            "is_resolved": 0_i64,
+            "is_deleted": NOT_DELETED,
+        })
+        .await?)
+    }
+
+    async fn resolve_face_reports(
+        &self,
+        face_info_id: &str,
+        moderator: &str,
+        now: i64,
+    ) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "face_report", "resolve_face_reports");
+        let res = update_face_reports_by_doc_filter(
+            doc! {"face_info_id": face_info_id, "is_resolved": 0_i64},
+            doc! {"$set": {"is_resolved": 1_i64, "resolved_on": now, "updater": moderator, "updated_on": now}},
+        )
+        .await?;
+        Ok(res.modified_count)
+    }
+
+    async fn get_unresolved_face_report_summaries(
+        &self,
+        skip: u64,
+        limit: i64,
+    ) -> RepositoryResult<Vec<FaceReportSummary>> {
+        let _operation = start_operation(
+            "mongo",
+            "face_report",
+            "get_unresolved_face_report_summaries",
+        );
+        Ok(get_unresolved_face_report_summaries(skip, limit).await?)
+    }
+
+    async fn get_all_face_reports(&self) -> RepositoryResult<Vec<FaceReport>> {
+        let _operation = start_operation("mongo", "face_report", "get_all_face_reports");
+        Ok(get_face_reports_by_doc_filter(doc! {}).await?)
+    }
+
+    async fn count_all_face_reports(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "face_report", "count_all_face_reports");
+        Ok(count_face_reports_by_doc_filter(doc! {}).await?)
+    }
+
+    async fn delete_face_reports_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "face_report", "delete_face_reports_by_ids");
+        let res = delete_face_reports_by_doc_filter(doc! {"id": {"$in": ids}}).await?;
+        Ok(res.deleted_count)
+    }
+}
+
+/// Adds the face_report to the "face_report" collection unless one matches the doc filter.
+async fn add_face_report_if_absent(
+    doc_filter: Document,
+    face_report: &FaceReport,
+) -> mongodb::error::Result<UpdateResult> {
+    let collection: Collection<FaceReport> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceReport::db_name())
+        .collection(FaceReport::coll_name());
+
+    let options = UpdateOptions::builder().upsert(true).build();
+    collection
+        .update_one(
+            doc_filter,
+            doc! {"$setOnInsert": bson::to_document(face_report)?},
+            options,
+        )
+        .await
+}
+
+/// Adds new face_reports to the "face_report" collection in the database.
+async fn add_face_reports(
+    face_reports: Vec<FaceReport>,
+) -> mongodb::error::Result<InsertManyResult> {
+    let collection: Collection<FaceReport> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceReport::db_name())
+        .collection(FaceReport::coll_name());
+    collection.insert_many(face_reports, None).await
+}
+
+/// Get multiple face_report by doc filter, including the resolved and the soft deleted ones.
+async fn get_face_reports_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<Vec<FaceReport>> {
+    let collection = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceReport::db_name())
+        .collection(FaceReport::coll_name());
+
+    let mut ret_face_reports: Vec<FaceReport> = Vec::new();
+    let mut results = collection.find(doc_filter, None).await?;
+    while let Some(result) = results.next().await {
+        let face_report: FaceReport = bson::from_document(result?)?;
+        ret_face_reports.push(face_report);
+    }
+    Ok(ret_face_reports)
+}
+
+/// Hard delete the face_reports by doc filter.
+async fn delete_face_reports_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<DeleteResult> {
+    let collection: Collection<FaceReport> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceReport::db_name())
+        .collection(FaceReport::coll_name());
+    collection.delete_many(doc_filter, None).await
+}
+
+/// Tells whether the write was refused by a unique index.
+fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(e)) if e.code == 11000
+    )
+}
+
+/// Counts the face_reports by doc filter.
+async fn count_face_reports_by_doc_filter(doc_filter: Document) -> mongodb::error::Result<u64> {
+    let collection: Collection<FaceReport> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceReport::db_name())
+        .collection(FaceReport::coll_name());
+    collection.count_documents(doc_filter, None).await
+}
+
+/// Update all the face_reports matching the doc filter.
+async fn update_face_reports_by_doc_filter(
+    doc_filter: Document,
+    update_info: Document,
+) -> mongodb::error::Result<UpdateResult> {
+    let collection: Collection<FaceReport> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceReport::db_name())
+        .collection(FaceReport::coll_name());
+    collection.update_many(doc_filter, update_info, None).await
+}
+
+/// Aggregates the unresolved face_reports per face_info, the most reported comes first.
+async fn get_unresolved_face_report_summaries(
+    skip: u64,
+    limit: i64,
+) -> mongodb::error::Result<Vec<FaceReportSummary>> {
+    let collection: Collection<FaceReport> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FaceReport::db_name())
+        .collection(FaceReport::coll_name());
+
+    let pipeline = vec![
+        doc! {"$match": {"is_resolved": 0_i64, "is_deleted": NOT_DELETED}},
+        doc! {"$group": {
+            "_id": {"face_info_id": "$face_info_id", "reason": "$reason"},
+            "cnt": {"$sum": 1_i64},
+            "last_reported_on": {"$max": "$created_on"},
+        }},
+        doc! {"$group": {
+            "_id": "$_id.face_info_id",
+            "report_cnt": {"$sum": "$cnt"},
+            "reason_cnts": {"$push": {"reason": "$_id.reason", "cnt": "$cnt"}},
+            "last_reported_on": {"$max": "$last_reported_on"},
+        }},
+        doc! {"$sort": {"report_cnt": -1, "_id": 1}},
+        doc! {"$skip": skip as i64},
+        doc! {"$limit": limit},
+        doc! {"$project": {
+            "_id": 0,
+            "face_info_id": "$_id",
+            "report_cnt": 1,
+            "reason_cnts": 1,
+            "last_reported_on": 1,
+        }},
+    ];
+
+    let mut ret_summaries: Vec<FaceReportSummary> = Vec::new();
+    let mut results = collection.aggregate(pipeline, None).await?;
+    while let Some(result) = results.next().await {
+        let summary: FaceReportSummary = bson::from_document(result?)?;
+        ret_summaries.push(summary);
+    }
+    Ok(ret_summaries)
+}
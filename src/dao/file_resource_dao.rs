@@ -1,31 +1,202 @@
+use futures_util::StreamExt;
 use mongodb::bson::Document;
-use mongodb::results::InsertOneResult;
-use mongodb::Collection;
+use mongodb::results::{DeleteResult, InsertManyResult, InsertOneResult, UpdateResult};
+use mongodb::{bson, Collection};
 
+use async_trait::async_trait;
+use mongodb::bson::doc;
+
+use crate::dao::exclude_deleted;
+use crate::dao::start_operation;
 use crate::entity::file_resource::FileResource;
+use crate::entity::{DELETED, NOT_DELETED};
 use crate::mongo;
+use crate::repository::{FileResourceRepository, RepositoryResult};
 use crate::resource::mongo::MONGO_CLIENT;
 
+/// The file_resource repository backed by the "file_resource" collection.
+pub struct MongoFileResourceDao;
+
+#[async_trait]
+impl FileResourceRepository for MongoFileResourceDao {
+    async fn add_file_resource(&self, file_resource: &FileResource) -> RepositoryResult<()> {
+        let _operation = start_operation("mongo", "file_resource", "add_file_resource");
+        add_one_file_resource(file_resource).await?;
+        Ok(())
+    }
+
+    async fn add_file_resources(&self, file_resources: Vec<FileResource>) -> RepositoryResult<()> {
+        let _operation = start_operation("mongo", "file_resource", "add_file_resources");
+        if !file_resources.is_empty() {
+            add_file_resources(file_resources).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_file_resource_by_id(&self, id: &str) -> RepositoryResult<Option<FileResource>> {
+        let _operation = start_operation("mongo", "file_resource", "get_file_resource_by_id");
+        Ok(get_one_file_resource_by_doc_filter(doc! {"id": id}).await?)
+    }
+
+    async fn get_file_resource_by_md5(&self, md5: &str) -> RepositoryResult<Option<FileResource>> {
+        let _operation = start_operation("mongo", "file_resource", "get_file_resource_by_md5");
+        Ok(get_one_file_resource_by_doc_filter(doc! {"md5": md5}).await?)
+    }
+
+    async fn get_all_file_resources(&self) -> RepositoryResult<Vec<FileResource>> {
+        let _operation = start_operation("mongo", "file_resource", "get_all_file_resources");
+        Ok(get_file_resources_with_deleted_by_doc_filter(doc! {}).await?)
+    }
+
+    async fn count_all_file_resources(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "file_resource", "count_all_file_resources");
+        Ok(count_file_resources_with_deleted_by_doc_filter(doc! {}).await?)
+    }
+
+    async fn get_deleted_file_resources_before(
+        &self,
+        deleted_before: i64,
+    ) -> RepositoryResult<Vec<FileResource>> {
+        let _operation = start_operation(
+            "mongo",
+            "file_resource",
+            "get_deleted_file_resources_before",
+        );
+        Ok(get_file_resources_with_deleted_by_doc_filter(
+            doc! {"is_deleted": DELETED, "deleted_on": {"$lt": deleted_before}},
+        )
+        .await?)
+    }
+
+    async fn soft_delete_file_resource(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let _operation = start_operation("mongo", "file_resource", "soft_delete_file_resource");
+        let res = update_file_resource_by_doc_filter(
+            doc! {"id": id, "is_deleted": NOT_DELETED},
+            doc! {
+                "$set": {"is_deleted": DELETED, "deleted_on": now, "updater": operator, "updated_on": now},
+            },
+        )
+        .await?;
+        Ok(res.matched_count > 0)
+    }
+
+    async fn restore_file_resource(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let _operation = start_operation("mongo", "file_resource", "restore_file_resource");
+        let res = update_file_resource_by_doc_filter(
+            doc! {"id": id, "is_deleted": DELETED},
+            doc! {
+                "$set": {"is_deleted": NOT_DELETED, "deleted_on": 0_i64, "updater": operator, "updated_on": now},
+            },
+        )
+        .await?;
+        Ok(res.matched_count > 0)
+    }
+
+    async fn delete_file_resources_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "file_resource", "delete_file_resources_by_ids");
+        let res = delete_file_resources_by_doc_filter(doc! {"id": {"$in": ids}}).await?;
+        Ok(res.deleted_count)
+    }
+}
+
 /// Adds a new file_resource to the "file_resource" collection in the database.
-pub async fn add_one_file_resource(
+async fn add_one_file_resource(
     file_resource: &FileResource,
 ) -> mongodb::error::Result<InsertOneResult> {
     let collection: Collection<FileResource> = mongo::MONGO_CLIENT
         .get()
-        .await
+        .await?
         .database(FileResource::db_name())
         .collection(FileResource::coll_name());
     collection.insert_one(file_resource, None).await
 }
 
-/// Gets the file_resource by doc filter.
-pub async fn get_one_file_resource_by_doc_filter(
+/// Adds new file_resources to the "file_resource" collection in the database.
+async fn add_file_resources(
+    file_resources: Vec<FileResource>,
+) -> mongodb::error::Result<InsertManyResult> {
+    let collection: Collection<FileResource> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(FileResource::db_name())
+        .collection(FileResource::coll_name());
+    collection.insert_many(file_resources, None).await
+}
+
+/// Counts the file_resources by doc filter, including the soft deleted ones.
+async fn count_file_resources_with_deleted_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<u64> {
+    let collection: Collection<FileResource> = MONGO_CLIENT
+        .get()
+        .await?
+        .database(FileResource::db_name())
+        .collection(FileResource::coll_name());
+    collection.count_documents(doc_filter, None).await
+}
+
+/// Gets the file_resource by doc filter, soft deleted file_resources are excluded.
+async fn get_one_file_resource_by_doc_filter(
     doc_filter: Document,
 ) -> mongodb::error::Result<Option<FileResource>> {
     let collection = MONGO_CLIENT
         .get()
-        .await
+        .await?
+        .database(FileResource::db_name())
+        .collection(FileResource::coll_name());
+    collection.find_one(exclude_deleted(doc_filter), None).await
+}
+
+/// Get multiple file_resource by doc filter, including the soft deleted ones.
+async fn get_file_resources_with_deleted_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<Vec<FileResource>> {
+    let collection = MONGO_CLIENT
+        .get()
+        .await?
+        .database(FileResource::db_name())
+        .collection(FileResource::coll_name());
+
+    let mut ret_file_resources: Vec<FileResource> = Vec::new();
+    let mut results = collection.find(doc_filter, None).await?;
+    while let Some(result) = results.next().await {
+        let file_resource: FileResource = bson::from_document(result?)?;
+        ret_file_resources.push(file_resource);
+    }
+    Ok(ret_file_resources)
+}
+
+/// Update the file_resource by doc filter.
+async fn update_file_resource_by_doc_filter(
+    doc_filter: Document,
+    update_info: Document,
+) -> mongodb::error::Result<UpdateResult> {
+    let collection: Collection<FileResource> = MONGO_CLIENT
+        .get()
+        .await?
+        .database(FileResource::db_name())
+        .collection(FileResource::coll_name());
+    collection.update_one(doc_filter, update_info, None).await
+}
+
+/// Hard delete the file_resources by doc filter.
+async fn delete_file_resources_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<DeleteResult> {
+    let collection: Collection<FileResource> = MONGO_CLIENT
+        .get()
+        .await?
         .database(FileResource::db_name())
         .collection(FileResource::coll_name());
-    collection.find_one(doc_filter, None).await
+    collection.delete_many(doc_filter, None).await
 }
@@ -0,0 +1,244 @@
+//! The indexes and data migrations of the mongo repositories, applied at startup.
+//!
+//! The indexes are (re)created on every boot, which is a no-op once they exist.
+//! The data migrations are applied once each, in version order, and recorded
+//! in the "migration" collection.
+
+use std::collections::HashSet;
+
+use futures_util::future::BoxFuture;
+use futures_util::StreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::IndexOptions;
+use mongodb::{bson, IndexModel};
+use serde::{Deserialize, Serialize};
+
+use crate::dao::face_info_dao;
+use crate::entity::category_rating::CategoryRating;
+use crate::entity::face_info::FaceInfo;
+use crate::entity::face_info_audit::FaceInfoAudit;
+use crate::entity::face_report::FaceReport;
+use crate::entity::file_resource::FileResource;
+use crate::entity::rating_log::RatingLog;
+use crate::entity::season::Season;
+use crate::entity::season_snapshot::SeasonSnapshot;
+use crate::entity::NOT_DELETED;
+use crate::mongo;
+
+/// An applied data migration, stored in the "migration" collection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationRecord {
+    pub version: i64,
+    pub name: String,
+    /// The number of documents the migration changed
+    pub modified_cnt: u64,
+    pub applied_on: i64,
+}
+
+impl MigrationRecord {
+    pub fn db_name() -> &'static str {
+        "facemash"
+    }
+
+    pub fn coll_name() -> &'static str {
+        "migration"
+    }
+}
+
+/// A data migration, returns the number of documents changed.
+struct DataMigration {
+    version: i64,
+    name: &'static str,
+    apply: fn() -> BoxFuture<'static, mongodb::error::Result<u64>>,
+}
+
+/// The data migrations, append only, in version order.
+const DATA_MIGRATIONS: &[DataMigration] = &[
+    DataMigration {
+        version: 1,
+        name: "approve face_infos without status",
+        apply: || {
+            Box::pin(async {
+                let res = face_info_dao::init_face_info_status().await?;
+                Ok(res.modified_count)
+            })
+        },
+    },
+    DataMigration {
+        version: 2,
+        name: "index the star_names of face_infos",
+        apply: || Box::pin(face_info_dao::init_star_name_trigrams()),
+    },
+    DataMigration {
+        version: 3,
+        name: "index the star_name prefixes of face_infos",
+        apply: || Box::pin(face_info_dao::init_star_name_suffixes()),
+    },
+    DataMigration {
+        version: 4,
+        name: "set no categories on face_infos without categories",
+        apply: || {
+            Box::pin(async {
+                let res = face_info_dao::init_face_info_categories().await?;
+                Ok(res.modified_count)
+            })
+        },
+    },
+];
+
+/// The indexes of each collection.
+fn get_index_models() -> Vec<(&'static str, Vec<IndexModel>)> {
+    vec![
+        (
+            FaceInfo::coll_name(),
+            vec![
+                unique_index(doc! {"id": 1}),
+                index(doc! {"score": -1}),
+                index(doc! {"file_id": 1}),
+                index(doc! {"status": 1, "created_on": 1, "id": 1}),
+                index(doc! {"status": 1, "score": -1, "id": 1}),
+                index(doc! {face_info_dao::STAR_NAME_TRIGRAMS: 1, "status": 1}),
+                index(doc! {face_info_dao::STAR_NAME_SUFFIXES: 1, "status": 1}),
+                index(doc! {"categories": 1, "status": 1}),
+            ],
+        ),
+        (
+            FileResource::coll_name(),
+            vec![unique_index(doc! {"id": 1}), index(doc! {"md5": 1})],
+        ),
+        (
+            RatingLog::coll_name(),
+            vec![
+                unique_index(doc! {"id": 1}),
+                index(doc! {"win_face_id": 1, "created_on": -1}),
+                index(doc! {"loss_face_id": 1, "created_on": -1}),
+                index(doc! {"season_id": 1}),
+            ],
+        ),
+        (
+            CategoryRating::coll_name(),
+            vec![
+                unique_index(doc! {"category": 1, "face_info_id": 1}),
+                index(doc! {"category": 1, "score": -1, "face_info_id": 1}),
+                index(doc! {"face_info_id": 1}),
+            ],
+        ),
+        (
+            Season::coll_name(),
+            vec![
+                unique_index(doc! {"id": 1}),
+                index(doc! {"ended_on": 1}),
+                index(doc! {"started_on": -1, "ended_on": 1}),
+            ],
+        ),
+        (
+            SeasonSnapshot::coll_name(),
+            vec![
+                unique_index(doc! {"season_id": 1, "face_info_id": 1}),
+                index(doc! {"season_id": 1, "score": -1, "face_info_id": 1}),
+            ],
+        ),
+        (
+            FaceInfoAudit::coll_name(),
+            vec![
+                unique_index(doc! {"id": 1}),
+                index(doc! {"face_info_id": 1, "created_on": -1}),
+            ],
+        ),
+        (
+            FaceReport::coll_name(),
+            vec![
+                unique_index(doc! {"id": 1}),
+                // One unresolved report per reporter and face_info
+                unique_partial_index(
+                    doc! {"face_info_id": 1, "reporter": 1},
+                    doc! {"is_resolved": 0_i64, "is_deleted": NOT_DELETED},
+                ),
+            ],
+        ),
+        (
+            MigrationRecord::coll_name(),
+            vec![unique_index(doc! {"version": 1})],
+        ),
+    ]
+}
+
+fn index(keys: Document) -> IndexModel {
+    IndexModel::builder().keys(keys).build()
+}
+
+fn unique_index(keys: Document) -> IndexModel {
+    IndexModel::builder()
+        .keys(keys)
+        .options(IndexOptions::builder().unique(true).build())
+        .build()
+}
+
+/// A unique index over the documents matching the filter only.
+fn unique_partial_index(keys: Document, filter: Document) -> IndexModel {
+    IndexModel::builder()
+        .keys(keys)
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(filter)
+                .build(),
+        )
+        .build()
+}
+
+/// Creates the indexes, then applies the data migrations not recorded yet,
+/// returns the number of data migrations applied.
+pub async fn migrate() -> mongodb::error::Result<usize> {
+    let database = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(MigrationRecord::db_name());
+
+    // Step 1: Create the indexes
+    for (coll_name, index_models) in get_index_models() {
+        database
+            .collection::<Document>(coll_name)
+            .create_indexes(index_models, None)
+            .await?;
+    }
+
+    // Step 2: Apply the data migrations
+    let collection = database.collection::<MigrationRecord>(MigrationRecord::coll_name());
+    let mut applied_versions = HashSet::new();
+    let mut results = collection.find(doc! {}, None).await?;
+    while let Some(result) = results.next().await {
+        applied_versions.insert(result?.version);
+    }
+
+    let mut applied_cnt = 0;
+    for migration in DATA_MIGRATIONS {
+        if applied_versions.contains(&migration.version) {
+            continue;
+        }
+
+        let modified_cnt = (migration.apply)().await?;
+        let record = MigrationRecord {
+            version: migration.version,
+            name: migration.name.to_string(),
+            modified_cnt,
+            applied_on: chrono::Utc::now().timestamp(),
+        };
+        collection.insert_one(&record, None).await?;
+        info!("Applied mongo migration: {}", bson::to_document(&record)?);
+        applied_cnt += 1;
+    }
+    Ok(applied_cnt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_migration_versions() {
+        let versions: Vec<i64> = DATA_MIGRATIONS.iter().map(|m| m.version).collect();
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(versions.iter().all(|version| *version > 0));
+    }
+}
@@ -1,3 +1,68 @@
+use mongodb::bson::Document;
+use prometheus::HistogramTimer;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::entity::NOT_DELETED;
+use crate::metrics;
+
+pub mod category_rating_dao;
+pub mod database_dao;
+pub mod face_info_audit_dao;
 pub mod face_info_dao;
+pub mod face_report_dao;
 pub mod file_resource_dao;
+pub mod migration;
 pub mod rating_log_dao;
+pub mod season_dao;
+pub mod season_snapshot_dao;
+pub mod sql;
+
+/// Prepares the collections for the mongo repositories.
+pub async fn init_mongo_dao() -> mongodb::error::Result<()> {
+    let applied_cnt = migration::migrate().await?;
+    info!(
+        "Mongo migrated successfully, {} migrations applied.",
+        applied_cnt
+    );
+    Ok(())
+}
+
+/// Restricts the doc filter to the documents which are not soft deleted.
+pub fn exclude_deleted(mut doc_filter: Document) -> Document {
+    doc_filter.insert("is_deleted", NOT_DELETED);
+    doc_filter
+}
+
+/// A repository operation, timed by the metrics and traced by a span until dropped.
+pub struct Operation {
+    span: tracing::Span,
+    _timer: HistogramTimer,
+}
+
+impl Operation {
+    /// Adds an attribute to the span, e.g. the ids queried or the result count.
+    pub fn record(&self, key: &'static str, value: impl Into<opentelemetry::Value>) {
+        self.span.set_attribute(key, value);
+    }
+}
+
+/// Starts an operation of the repository backed by the `mongo` or `sql` backend,
+/// the span is a child of the span of the calling service.
+pub fn start_operation(
+    backend: &'static str,
+    repository: &'static str,
+    operation: &'static str,
+) -> Operation {
+    let span = tracing::info_span!(
+        "repository_operation",
+        otel.name = %format!("{} {}", operation, repository),
+        otel.kind = "client",
+        db.system.name = if backend == "mongo" { "mongodb" } else { backend },
+        db.collection.name = repository,
+        db.operation.name = operation,
+    );
+    Operation {
+        span,
+        _timer: metrics::start_repository_timer(backend, repository, operation),
+    }
+}
@@ -1,17 +1,112 @@
-use mongodb::results::InsertManyResult;
-use mongodb::Collection;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::results::{DeleteResult, InsertManyResult};
+use mongodb::{bson, Collection};
 
+use crate::dao::start_operation;
 use crate::entity::rating_log::RatingLog;
+use crate::entity::DELETED;
 use crate::mongo;
+use crate::repository::{RatingLogRepository, RepositoryResult};
+
+/// The rating_log repository backed by the "rating_log" collection.
+pub struct MongoRatingLogDao;
+
+#[async_trait]
+impl RatingLogRepository for MongoRatingLogDao {
+    async fn add_rating_logs(&self, rating_logs: Vec<RatingLog>) -> RepositoryResult<()> {
+        let operation = start_operation("mongo", "rating_log", "add_rating_logs");
+        operation.record("rating_log.count", rating_logs.len() as i64);
+        if !rating_logs.is_empty() {
+            add_rating_logs(rating_logs).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_all_rating_logs(&self) -> RepositoryResult<Vec<RatingLog>> {
+        let _operation = start_operation("mongo", "rating_log", "get_all_rating_logs");
+        Ok(get_rating_logs_with_deleted_by_doc_filter(doc! {}).await?)
+    }
+
+    async fn count_all_rating_logs(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "rating_log", "count_all_rating_logs");
+        Ok(count_rating_logs_with_deleted_by_doc_filter(doc! {}).await?)
+    }
+
+    async fn purge_rating_logs(
+        &self,
+        face_info_ids: &[String],
+        deleted_before: i64,
+    ) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "rating_log", "purge_rating_logs");
+        let res = delete_rating_logs_by_doc_filter(doc! {
+            "$or": [
+                {"win_face_id": {"$in": face_info_ids}},
+                {"loss_face_id": {"$in": face_info_ids}},
+                {"is_deleted": DELETED, "deleted_on": {"$lt": deleted_before}},
+            ]
+        })
+        .await?;
+        Ok(res.deleted_count)
+    }
+
+    async fn delete_rating_logs_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "rating_log", "delete_rating_logs_by_ids");
+        let res = delete_rating_logs_by_doc_filter(doc! {"id": {"$in": ids}}).await?;
+        Ok(res.deleted_count)
+    }
+}
 
 /// Adds new rating_logs to the "rating_log" collection in the database.
-pub async fn add_rating_logs(
-    rating_log: Vec<RatingLog>,
-) -> mongodb::error::Result<InsertManyResult> {
+async fn add_rating_logs(rating_log: Vec<RatingLog>) -> mongodb::error::Result<InsertManyResult> {
     let collection: Collection<RatingLog> = mongo::MONGO_CLIENT
         .get()
-        .await
+        .await?
         .database(RatingLog::db_name())
         .collection(RatingLog::coll_name());
     collection.insert_many(rating_log, None).await
 }
+
+/// Get multiple rating_log by doc filter, including the soft deleted ones.
+async fn get_rating_logs_with_deleted_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<Vec<RatingLog>> {
+    let collection = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(RatingLog::db_name())
+        .collection(RatingLog::coll_name());
+
+    let mut ret_rating_logs: Vec<RatingLog> = Vec::new();
+    let mut results = collection.find(doc_filter, None).await?;
+    while let Some(result) = results.next().await {
+        let rating_log: RatingLog = bson::from_document(result?)?;
+        ret_rating_logs.push(rating_log);
+    }
+    Ok(ret_rating_logs)
+}
+
+/// Counts the rating_logs by doc filter, including the soft deleted ones.
+async fn count_rating_logs_with_deleted_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<u64> {
+    let collection: Collection<RatingLog> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(RatingLog::db_name())
+        .collection(RatingLog::coll_name());
+    collection.count_documents(doc_filter, None).await
+}
+
+/// Hard delete the rating_logs by doc filter.
+async fn delete_rating_logs_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<DeleteResult> {
+    let collection: Collection<RatingLog> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(RatingLog::db_name())
+        .collection(RatingLog::coll_name());
+    collection.delete_many(doc_filter, None).await
+}
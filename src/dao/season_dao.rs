@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::FindOptions;
+use mongodb::results::{DeleteResult, InsertOneResult, UpdateResult};
+use mongodb::{bson, Collection};
+
+use crate::dao::start_operation;
+use crate::entity::season::Season;
+use crate::mongo;
+use crate::repository::{RepositoryResult, SeasonRepository};
+
+/// The season repository backed by the "season" collection.
+pub struct MongoSeasonDao;
+
+#[async_trait]
+impl SeasonRepository for MongoSeasonDao {
+    async fn add_season(&self, season: &Season) -> RepositoryResult<()> {
+        let operation = start_operation("mongo", "season", "add_season");
+        operation.record("season.id", season.id.clone());
+        add_one_season(season).await?;
+        Ok(())
+    }
+
+    async fn get_season_by_id(&self, id: &str) -> RepositoryResult<Option<Season>> {
+        let operation = start_operation("mongo", "season", "get_season_by_id");
+        operation.record("season.id", id.to_string());
+        Ok(get_seasons_by_doc_filter(doc! {"id": id}, None)
+            .await?
+            .pop())
+    }
+
+    async fn get_current_season(&self) -> RepositoryResult<Option<Season>> {
+        let _operation = start_operation("mongo", "season", "get_current_season");
+        Ok(get_seasons_by_doc_filter(doc! {"ended_on": 0_i64}, None)
+            .await?
+            .pop())
+    }
+
+    async fn get_seasons(&self) -> RepositoryResult<Vec<Season>> {
+        let _operation = start_operation("mongo", "season", "get_seasons");
+        let options = FindOptions::builder()
+            .sort(doc! {"started_on": -1, "ended_on": 1})
+            .build();
+        Ok(get_seasons_by_doc_filter(doc! {}, options).await?)
+    }
+
+    async fn end_season(&self, id: &str, now: i64) -> RepositoryResult<bool> {
+        let operation = start_operation("mongo", "season", "end_season");
+        operation.record("season.id", id.to_string());
+        let res = update_season_by_doc_filter(
+            doc! {"id": id, "ended_on": 0_i64},
+            doc! {"$set": {"ended_on": now}},
+        )
+        .await?;
+        Ok(res.matched_count > 0)
+    }
+
+    async fn finish_season_reset(&self, id: &str) -> RepositoryResult<bool> {
+        let operation = start_operation("mongo", "season", "finish_season_reset");
+        operation.record("season.id", id.to_string());
+        let res = update_season_by_doc_filter(
+            doc! {"id": id, "reset_pending": true},
+            doc! {"$set": {"reset_pending": false}},
+        )
+        .await?;
+        Ok(res.matched_count > 0)
+    }
+
+    async fn delete_seasons_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "season", "delete_seasons_by_ids");
+        let res = delete_seasons_by_doc_filter(doc! {"id": {"$in": ids}}).await?;
+        Ok(res.deleted_count)
+    }
+}
+
+/// Adds a new season to the "season" collection in the database.
+async fn add_one_season(season: &Season) -> mongodb::error::Result<InsertOneResult> {
+    let collection: Collection<Season> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(Season::db_name())
+        .collection(Season::coll_name());
+    collection.insert_one(season, None).await
+}
+
+/// Get multiple season by doc filter.
+async fn get_seasons_by_doc_filter(
+    doc_filter: Document,
+    options: impl Into<Option<FindOptions>>,
+) -> mongodb::error::Result<Vec<Season>> {
+    let collection = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(Season::db_name())
+        .collection(Season::coll_name());
+
+    let mut ret_seasons: Vec<Season> = Vec::new();
+    let mut results = collection.find(doc_filter, options).await?;
+    while let Some(result) = results.next().await {
+        let season: Season = bson::from_document(result?)?;
+        ret_seasons.push(season);
+    }
+    Ok(ret_seasons)
+}
+
+/// Update the season by doc filter.
+async fn update_season_by_doc_filter(
+    doc_filter: Document,
+    update_info: Document,
+) -> mongodb::error::Result<UpdateResult> {
+    let collection: Collection<Season> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(Season::db_name())
+        .collection(Season::coll_name());
+    collection.update_one(doc_filter, update_info, None).await
+}
+
+/// Hard delete the seasons by doc filter.
+async fn delete_seasons_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<DeleteResult> {
+    let collection: Collection<Season> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(Season::db_name())
+        .collection(Season::coll_name());
+    collection.delete_many(doc_filter, None).await
+}
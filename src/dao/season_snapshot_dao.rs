@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::FindOptions;
+use mongodb::results::{DeleteResult, InsertManyResult};
+use mongodb::{bson, Collection};
+
+use crate::dao::start_operation;
+use crate::entity::season_snapshot::SeasonSnapshot;
+use crate::mongo;
+use crate::repository::{get_page_skip, RepositoryResult, SeasonSnapshotRepository};
+
+/// The season_snapshot repository backed by the "season_snapshot" collection.
+pub struct MongoSeasonSnapshotDao;
+
+#[async_trait]
+impl SeasonSnapshotRepository for MongoSeasonSnapshotDao {
+    async fn save_season_snapshots(
+        &self,
+        season_snapshots: Vec<SeasonSnapshot>,
+    ) -> RepositoryResult<()> {
+        let operation = start_operation("mongo", "season_snapshot", "save_season_snapshots");
+        operation.record("season_snapshot.count", season_snapshots.len() as i64);
+        if !season_snapshots.is_empty() {
+            save_season_snapshots(season_snapshots).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_season_snapshots_page(
+        &self,
+        season_id: &str,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<SeasonSnapshot>> {
+        let operation = start_operation("mongo", "season_snapshot", "get_season_snapshots_page");
+        operation.record("season.id", season_id.to_string());
+        let options = FindOptions::builder()
+            .sort(doc! {"score": -1, "face_info_id": 1})
+            .skip(get_page_skip(page, page_size))
+            .limit(page_size)
+            .build();
+        Ok(get_season_snapshots_by_doc_filter(doc! {"season_id": season_id}, options).await?)
+    }
+
+    async fn get_all_season_snapshots(&self) -> RepositoryResult<Vec<SeasonSnapshot>> {
+        let _operation = start_operation("mongo", "season_snapshot", "get_all_season_snapshots");
+        Ok(get_season_snapshots_by_doc_filter(doc! {}, None).await?)
+    }
+
+    async fn count_all_season_snapshots(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("mongo", "season_snapshot", "count_all_season_snapshots");
+        Ok(count_season_snapshots_by_doc_filter(doc! {}).await?)
+    }
+
+    async fn delete_season_snapshots_by_season_ids(
+        &self,
+        season_ids: &[String],
+    ) -> RepositoryResult<u64> {
+        let _operation = start_operation(
+            "mongo",
+            "season_snapshot",
+            "delete_season_snapshots_by_season_ids",
+        );
+        let res =
+            delete_season_snapshots_by_doc_filter(doc! {"season_id": {"$in": season_ids}}).await?;
+        Ok(res.deleted_count)
+    }
+}
+
+/// Adds new season_snapshots to the "season_snapshot" collection in the database,
+/// after deleting the ones of the same season and face_info.
+async fn save_season_snapshots(
+    season_snapshots: Vec<SeasonSnapshot>,
+) -> mongodb::error::Result<InsertManyResult> {
+    let collection: Collection<SeasonSnapshot> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(SeasonSnapshot::db_name())
+        .collection(SeasonSnapshot::coll_name());
+    let keys: Vec<Document> = season_snapshots
+        .iter()
+        .map(|snapshot| {
+            doc! {"season_id": &snapshot.season_id, "face_info_id": &snapshot.face_info_id}
+        })
+        .collect();
+    collection.delete_many(doc! {"$or": keys}, None).await?;
+    collection.insert_many(season_snapshots, None).await
+}
+
+/// Get multiple season_snapshot by doc filter.
+async fn get_season_snapshots_by_doc_filter(
+    doc_filter: Document,
+    options: impl Into<Option<FindOptions>>,
+) -> mongodb::error::Result<Vec<SeasonSnapshot>> {
+    let collection = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(SeasonSnapshot::db_name())
+        .collection(SeasonSnapshot::coll_name());
+
+    let mut ret_season_snapshots: Vec<SeasonSnapshot> = Vec::new();
+    let mut results = collection.find(doc_filter, options).await?;
+    while let Some(result) = results.next().await {
+        let season_snapshot: SeasonSnapshot = bson::from_document(result?)?;
+        ret_season_snapshots.push(season_snapshot);
+    }
+    Ok(ret_season_snapshots)
+}
+
+/// Counts the season_snapshots by doc filter.
+async fn count_season_snapshots_by_doc_filter(doc_filter: Document) -> mongodb::error::Result<u64> {
+    let collection: Collection<SeasonSnapshot> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(SeasonSnapshot::db_name())
+        .collection(SeasonSnapshot::coll_name());
+    collection.count_documents(doc_filter, None).await
+}
+
+/// Hard delete the season_snapshots by doc filter.
+async fn delete_season_snapshots_by_doc_filter(
+    doc_filter: Document,
+) -> mongodb::error::Result<DeleteResult> {
+    let collection: Collection<SeasonSnapshot> = mongo::MONGO_CLIENT
+        .get()
+        .await?
+        .database(SeasonSnapshot::db_name())
+        .collection(SeasonSnapshot::coll_name());
+    collection.delete_many(doc_filter, None).await
+}
@@ -0,0 +1,187 @@
+use async_trait::async_trait;
+use sqlx::any::AnyRow;
+use sqlx::{AnyPool, Row};
+
+use crate::dao::sql::{page_offset, placeholders};
+use crate::dao::start_operation;
+use crate::entity::category_rating::CategoryRating;
+use crate::repository::{CategoryRatingRepository, RepositoryResult};
+
+const COLUMNS: &str =
+    "category, face_info_id, score, upvote_count, downvote_count, created_on, updated_on";
+
+/// The category_rating repository backed by the "category_rating" table.
+pub struct SqlCategoryRatingDao {
+    pool: AnyPool,
+}
+
+impl SqlCategoryRatingDao {
+    pub fn new(pool: AnyPool) -> Self {
+        SqlCategoryRatingDao { pool }
+    }
+}
+
+fn category_rating_from_row(row: &AnyRow) -> Result<CategoryRating, sqlx::Error> {
+    Ok(CategoryRating {
+        category: row.try_get("category")?,
+        face_info_id: row.try_get("face_info_id")?,
+        score: row.try_get("score")?,
+        upvote_count: row.try_get::<i64, _>("upvote_count")? as u64,
+        downvote_count: row.try_get::<i64, _>("downvote_count")? as u64,
+        created_on: row.try_get("created_on")?,
+        updated_on: row.try_get("updated_on")?,
+    })
+}
+
+#[async_trait]
+impl CategoryRatingRepository for SqlCategoryRatingDao {
+    async fn add_category_ratings(
+        &self,
+        category_ratings: Vec<CategoryRating>,
+    ) -> RepositoryResult<()> {
+        let operation = start_operation("sql", "category_rating", "add_category_ratings");
+        operation.record("category_rating.count", category_ratings.len() as i64);
+        let sql = format!(
+            "INSERT INTO category_rating ({}) VALUES ({})",
+            COLUMNS,
+            placeholders(1, 7)
+        );
+        let mut tx = self.pool.begin().await?;
+        for category_rating in &category_ratings {
+            sqlx::query(&sql)
+                .bind(&category_rating.category)
+                .bind(&category_rating.face_info_id)
+                .bind(category_rating.score)
+                .bind(category_rating.upvote_count as i64)
+                .bind(category_rating.downvote_count as i64)
+                .bind(category_rating.created_on)
+                .bind(category_rating.updated_on)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_all_category_ratings(&self) -> RepositoryResult<Vec<CategoryRating>> {
+        let _operation = start_operation("sql", "category_rating", "get_all_category_ratings");
+        let rows = sqlx::query(&format!("SELECT {} FROM category_rating", COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(category_rating_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn count_all_category_ratings(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "category_rating", "count_all_category_ratings");
+        let cnt: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM category_rating")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(cnt as u64)
+    }
+
+    async fn get_category_ratings(
+        &self,
+        category: &str,
+        face_info_ids: &[&str],
+    ) -> RepositoryResult<Vec<CategoryRating>> {
+        let operation = start_operation("sql", "category_rating", "get_category_ratings");
+        operation.record("face_info.ids", face_info_ids.join(","));
+        if face_info_ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let sql = format!(
+            "SELECT {} FROM category_rating WHERE category = $1 AND face_info_id IN ({})",
+            COLUMNS,
+            placeholders(2, face_info_ids.len())
+        );
+        let mut query = sqlx::query(&sql).bind(category);
+        for face_info_id in face_info_ids {
+            query = query.bind(*face_info_id);
+        }
+        let rows = query.fetch_all(&self.pool).await?;
+        Ok(rows
+            .iter()
+            .map(category_rating_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn update_category_rating(
+        &self,
+        category: &str,
+        face_info_id: &str,
+        score: f64,
+        upvote: bool,
+        now: i64,
+    ) -> RepositoryResult<()> {
+        let operation = start_operation("sql", "category_rating", "update_category_rating");
+        operation.record("face_info.id", face_info_id.to_string());
+        let (upvote_cnt, downvote_cnt) = if upvote { (1_i64, 0_i64) } else { (0, 1) };
+        sqlx::query(&format!(
+            "INSERT INTO category_rating ({}) VALUES ($1, $2, $3, $4, $5, $6, $6) \
+             ON CONFLICT (category, face_info_id) DO UPDATE SET score = $3, \
+             upvote_count = category_rating.upvote_count + $4, \
+             downvote_count = category_rating.downvote_count + $5, updated_on = $6",
+            COLUMNS
+        ))
+        .bind(category)
+        .bind(face_info_id)
+        .bind(score)
+        .bind(upvote_cnt)
+        .bind(downvote_cnt)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_category_ratings_page(
+        &self,
+        category: &str,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<CategoryRating>> {
+        let _operation = start_operation("sql", "category_rating", "get_category_ratings_page");
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM category_rating WHERE category = $1 \
+             ORDER BY score DESC, face_info_id LIMIT $2 OFFSET $3",
+            COLUMNS
+        ))
+        .bind(category)
+        .bind(page_size)
+        .bind(page_offset(page, page_size))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .iter()
+            .map(category_rating_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn delete_category_ratings_by_face_info_ids(
+        &self,
+        face_info_ids: &[String],
+    ) -> RepositoryResult<u64> {
+        let _operation = start_operation(
+            "sql",
+            "category_rating",
+            "delete_category_ratings_by_face_info_ids",
+        );
+        if face_info_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let sql = format!(
+            "DELETE FROM category_rating WHERE face_info_id IN ({})",
+            placeholders(1, face_info_ids.len())
+        );
+        let mut query = sqlx::query(&sql);
+        for face_info_id in face_info_ids {
+            query = query.bind(face_info_id);
+        }
+        Ok(query.execute(&self.pool).await?.rows_affected())
+    }
+}
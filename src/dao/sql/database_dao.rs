@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use sqlx::AnyPool;
+
+use crate::dao::start_operation;
+use crate::repository::{DatabaseRepository, RepositoryResult};
+
+/// The database repository backed by the relational database of the pool.
+pub struct SqlDatabaseDao {
+    pool: AnyPool,
+}
+
+impl SqlDatabaseDao {
+    pub fn new(pool: AnyPool) -> Self {
+        SqlDatabaseDao { pool }
+    }
+}
+
+#[async_trait]
+impl DatabaseRepository for SqlDatabaseDao {
+    async fn ping(&self) -> RepositoryResult<()> {
+        let _operation = start_operation("sql", "database", "ping");
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use async_trait::async_trait;
+use sqlx::any::AnyRow;
+use sqlx::{AnyPool, Row};
+
+use crate::dao::sql::placeholders;
+use crate::dao::start_operation;
+use crate::entity::face_info_audit::FaceInfoAudit;
+use crate::entity::NOT_DELETED;
+use crate::repository::{FaceInfoAuditRepository, RepositoryResult};
+
+const COLUMNS: &str = "id, face_info_id, field, old_value, new_value, creator, updater, \
+    created_on, updated_on, deleted_on, is_deleted";
+
+/// The face_info_audit repository backed by the "face_info_audit" table.
+pub struct SqlFaceInfoAuditDao {
+    pool: AnyPool,
+}
+
+impl SqlFaceInfoAuditDao {
+    pub fn new(pool: AnyPool) -> Self {
+        SqlFaceInfoAuditDao { pool }
+    }
+}
+
+fn face_info_audit_from_row(row: &AnyRow) -> Result<FaceInfoAudit, sqlx::Error> {
+    Ok(FaceInfoAudit {
+        id: row.try_get("id")?,
+        face_info_id: row.try_get("face_info_id")?,
+        field: row.try_get("field")?,
+        old_value: row.try_get("old_value")?,
+        new_value: row.try_get("new_value")?,
+        creator: row.try_get("creator")?,
+        updater: row.try_get("updater")?,
+        created_on: row.try_get("created_on")?,
+        updated_on: row.try_get("updated_on")?,
+        deleted_on: row.try_get("deleted_on")?,
+        is_deleted: row.try_get("is_deleted")?,
+    })
+}
+
+#[async_trait]
+impl FaceInfoAuditRepository for SqlFaceInfoAuditDao {
+    async fn add_face_info_audits(
+        &self,
+        face_info_audits: Vec<FaceInfoAudit>,
+    ) -> RepositoryResult<()> {
+        let _operation = start_operation("sql", "face_info_audit", "add_face_info_audits");
+        let sql = format!(
+            "INSERT INTO face_info_audit ({}) VALUES ({})",
+            COLUMNS,
+            placeholders(1, 11)
+        );
+        let mut tx = self.pool.begin().await?;
+        for face_info_audit in &face_info_audits {
+            sqlx::query(&sql)
+                .bind(&face_info_audit.id)
+                .bind(&face_info_audit.face_info_id)
+                .bind(&face_info_audit.field)
+                .bind(&face_info_audit.old_value)
+                .bind(&face_info_audit.new_value)
+                .bind(&face_info_audit.creator)
+                .bind(&face_info_audit.updater)
+                .bind(face_info_audit.created_on)
+                .bind(face_info_audit.updated_on)
+                .bind(face_info_audit.deleted_on)
+                .bind(face_info_audit.is_deleted)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_face_info_audits_by_face_info_id(
+        &self,
+        face_info_id: &str,
+    ) -> RepositoryResult<Vec<FaceInfoAudit>> {
+        let _operation = start_operation(
+            "sql",
+            "face_info_audit",
+            "get_face_info_audits_by_face_info_id",
+        );
+        // The snowflake ids keep the insertion order of the audits written at once
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM face_info_audit WHERE face_info_id = $1 AND is_deleted = $2 \
+             ORDER BY created_on DESC, id",
+            COLUMNS
+        ))
+        .bind(face_info_id)
+        .bind(NOT_DELETED)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .iter()
+            .map(face_info_audit_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn get_all_face_info_audits(&self) -> RepositoryResult<Vec<FaceInfoAudit>> {
+        let _operation = start_operation("sql", "face_info_audit", "get_all_face_info_audits");
+        let rows = sqlx::query(&format!("SELECT {} FROM face_info_audit", COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(face_info_audit_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn count_all_face_info_audits(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "face_info_audit", "count_all_face_info_audits");
+        let cnt: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM face_info_audit")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(cnt as u64)
+    }
+
+    async fn delete_face_info_audits_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let _operation =
+            start_operation("sql", "face_info_audit", "delete_face_info_audits_by_ids");
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let sql = format!(
+            "DELETE FROM face_info_audit WHERE id IN ({})",
+            placeholders(1, ids.len())
+        );
+        let mut query = sqlx::query(&sql);
+        for id in ids {
+            query = query.bind(id);
+        }
+        Ok(query.execute(&self.pool).await?.rows_affected())
+    }
+}
@@ -0,0 +1,671 @@
+use async_trait::async_trait;
+use futures_util::future::BoxFuture;
+use sqlx::any::{AnyArguments, AnyRow};
+use sqlx::{Any, AnyConnection, AnyPool, Row};
+
+use crate::algorithm::fuzzy_match;
+use crate::dao::sql::{from_text, page_offset, placeholders, to_text};
+use crate::dao::start_operation;
+use crate::entity::face_info::{FaceInfo, FaceInfoOrder, FaceInfoPatch, FaceStatus};
+use crate::entity::{DELETED, NOT_DELETED};
+use crate::repository::{FaceInfoRepository, RepositoryResult};
+
+const COLUMNS: &str = "id, star_name, categories, file_id, upvote_count, downvote_count, score, \
+    status, moderator, moderation_reason, moderated_on, creator, updater, created_on, updated_on, \
+    deleted_on, is_deleted";
+
+/// The categories are stored comma separated, the slugs have no commas.
+fn categories_to_text(categories: &[String]) -> String {
+    categories.join(",")
+}
+
+fn categories_from_text(text: String) -> Vec<String> {
+    text.split(',')
+        .filter(|category| !category.is_empty())
+        .map(|category| category.to_string())
+        .collect()
+}
+
+/// Gets the LIKE pattern of the comma wrapped categories containing the category,
+/// the `_` of the slugs is escaped by `\`.
+fn category_pattern(category: &str) -> String {
+    format!("%,{},%", category.replace('_', "\\_"))
+}
+
+/// The face_info repository backed by the "face_info" table.
+pub struct SqlFaceInfoDao {
+    pool: AnyPool,
+}
+
+impl SqlFaceInfoDao {
+    pub fn new(pool: AnyPool) -> Self {
+        SqlFaceInfoDao { pool }
+    }
+
+    async fn fetch_face_infos<'q>(
+        &self,
+        query: sqlx::query::Query<'q, Any, AnyArguments<'q>>,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let rows = query.fetch_all(&self.pool).await?;
+        Ok(rows
+            .iter()
+            .map(face_info_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+}
+
+fn face_info_from_row(row: &AnyRow) -> Result<FaceInfo, sqlx::Error> {
+    Ok(FaceInfo {
+        id: row.try_get("id")?,
+        star_name: row.try_get("star_name")?,
+        categories: categories_from_text(row.try_get("categories")?),
+        file_id: row.try_get("file_id")?,
+        upvote_count: row.try_get::<i64, _>("upvote_count")? as u64,
+        downvote_count: row.try_get::<i64, _>("downvote_count")? as u64,
+        score: row.try_get("score")?,
+        status: from_text(row.try_get("status")?)?,
+        moderator: row.try_get("moderator")?,
+        moderation_reason: row.try_get("moderation_reason")?,
+        moderated_on: row.try_get("moderated_on")?,
+        creator: row.try_get("creator")?,
+        updater: row.try_get("updater")?,
+        created_on: row.try_get("created_on")?,
+        updated_on: row.try_get("updated_on")?,
+        deleted_on: row.try_get("deleted_on")?,
+        is_deleted: row.try_get("is_deleted")?,
+    })
+}
+
+/// Inserts the face_info in the transaction.
+async fn insert_face_info(
+    tx: &mut sqlx::Transaction<'_, Any>,
+    face_info: &FaceInfo,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!(
+        "INSERT INTO face_info ({}) VALUES ({})",
+        COLUMNS,
+        placeholders(1, 17)
+    ))
+    .bind(&face_info.id)
+    .bind(&face_info.star_name)
+    .bind(categories_to_text(&face_info.categories))
+    .bind(&face_info.file_id)
+    .bind(face_info.upvote_count as i64)
+    .bind(face_info.downvote_count as i64)
+    .bind(face_info.score)
+    .bind(to_text(&face_info.status))
+    .bind(&face_info.moderator)
+    .bind(&face_info.moderation_reason)
+    .bind(face_info.moderated_on)
+    .bind(&face_info.creator)
+    .bind(&face_info.updater)
+    .bind(face_info.created_on)
+    .bind(face_info.updated_on)
+    .bind(face_info.deleted_on)
+    .bind(face_info.is_deleted)
+    .execute(&mut **tx)
+    .await?;
+    index_star_name(tx, &face_info.id, &face_info.star_name).await
+}
+
+/// Gets the range of the word suffixes starting with the prefix, scanned on the index.
+/// The normalized suffixes are only letters, digits and spaces, which sort below the
+/// last code point.
+fn get_suffix_range(prefix: &str) -> (String, String) {
+    let lower = fuzzy_match::normalize_prefix(prefix);
+    let upper = format!("{}{}", lower, char::MAX);
+    (lower, upper)
+}
+
+/// Indexes the face_info's star_name for the search, see `algorithm::fuzzy_match`.
+async fn index_star_name(
+    conn: &mut AnyConnection,
+    face_info_id: &str,
+    star_name: &str,
+) -> Result<(), sqlx::Error> {
+    index_star_name_keys(
+        conn,
+        "face_info_trigram",
+        "trigram",
+        face_info_id,
+        &fuzzy_match::trigrams(star_name),
+    )
+    .await?;
+    index_star_name_keys(
+        conn,
+        "face_info_star_name_suffix",
+        "suffix",
+        face_info_id,
+        &fuzzy_match::word_suffixes(star_name),
+    )
+    .await
+}
+
+/// Replaces the keys of the face_info's star_name in the index table, the trigrams in
+/// "face_info_trigram" for the fuzzy search and the word suffixes in
+/// "face_info_star_name_suffix" for the prefix search.
+async fn index_star_name_keys(
+    conn: &mut AnyConnection,
+    table: &str,
+    column: &str,
+    face_info_id: &str,
+    keys: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!("DELETE FROM {} WHERE face_info_id = $1", table))
+        .bind(face_info_id)
+        .execute(&mut *conn)
+        .await?;
+
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let values = (0..keys.len())
+        .map(|idx| format!("(${}, ${})", idx * 2 + 1, idx * 2 + 2))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!(
+        "INSERT INTO {} (face_info_id, {}) VALUES {}",
+        table, column, values
+    );
+    let mut query = sqlx::query(&sql);
+    for key in keys {
+        query = query.bind(face_info_id).bind(key);
+    }
+    query.execute(conn).await?;
+    Ok(())
+}
+
+/// Indexes the star_names of the face_infos added before the search, applied by a migration.
+pub fn index_all_star_names(conn: &mut AnyConnection) -> BoxFuture<'_, Result<(), sqlx::Error>> {
+    Box::pin(async move {
+        let rows = sqlx::query("SELECT id, star_name FROM face_info")
+            .fetch_all(&mut *conn)
+            .await?;
+        for row in rows {
+            let id: String = row.try_get("id")?;
+            let star_name: String = row.try_get("star_name")?;
+            let trigrams = fuzzy_match::trigrams(&star_name);
+            index_star_name_keys(conn, "face_info_trigram", "trigram", &id, &trigrams).await?;
+        }
+        Ok(())
+    })
+}
+
+/// Indexes the star_names of the face_infos added before the prefix search,
+/// applied by a migration.
+pub fn index_all_star_name_suffixes(
+    conn: &mut AnyConnection,
+) -> BoxFuture<'_, Result<(), sqlx::Error>> {
+    Box::pin(async move {
+        let rows = sqlx::query("SELECT id, star_name FROM face_info")
+            .fetch_all(&mut *conn)
+            .await?;
+        for row in rows {
+            let id: String = row.try_get("id")?;
+            let star_name: String = row.try_get("star_name")?;
+            let suffixes = fuzzy_match::word_suffixes(&star_name);
+            index_star_name_keys(conn, "face_info_star_name_suffix", "suffix", &id, &suffixes)
+                .await?;
+        }
+        Ok(())
+    })
+}
+
+#[async_trait]
+impl FaceInfoRepository for SqlFaceInfoDao {
+    async fn add_face_info(&self, face_info: &FaceInfo) -> RepositoryResult<()> {
+        let _operation = start_operation("sql", "face_info", "add_face_info");
+        let mut tx = self.pool.begin().await?;
+        insert_face_info(&mut tx, face_info).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn add_face_infos(&self, face_infos: Vec<FaceInfo>) -> RepositoryResult<()> {
+        let _operation = start_operation("sql", "face_info", "add_face_infos");
+        let mut tx = self.pool.begin().await?;
+        for face_info in &face_infos {
+            insert_face_info(&mut tx, face_info).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_face_info_by_id(&self, id: &str) -> RepositoryResult<Option<FaceInfo>> {
+        let operation = start_operation("sql", "face_info", "get_face_info_by_id");
+        operation.record("face_info.id", id.to_string());
+        let sql = format!(
+            "SELECT {} FROM face_info WHERE id = $1 AND is_deleted = $2",
+            COLUMNS
+        );
+        let query = sqlx::query(&sql).bind(id).bind(NOT_DELETED);
+        Ok(self.fetch_face_infos(query).await?.pop())
+    }
+
+    async fn get_face_info_by_file_id(&self, file_id: &str) -> RepositoryResult<Option<FaceInfo>> {
+        let _operation = start_operation("sql", "face_info", "get_face_info_by_file_id");
+        let sql = format!(
+            "SELECT {} FROM face_info WHERE file_id = $1 AND is_deleted = $2 LIMIT 1",
+            COLUMNS
+        );
+        let query = sqlx::query(&sql).bind(file_id).bind(NOT_DELETED);
+        Ok(self.fetch_face_infos(query).await?.pop())
+    }
+
+    async fn get_face_infos_by_ids(
+        &self,
+        ids: &[&str],
+        status: FaceStatus,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let operation = start_operation("sql", "face_info", "get_face_infos_by_ids");
+        operation.record("face_info.ids", ids.join(","));
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let sql = format!(
+            "SELECT {} FROM face_info WHERE status = $1 AND is_deleted = $2 AND id IN ({})",
+            COLUMNS,
+            placeholders(3, ids.len())
+        );
+        let mut query = sqlx::query(&sql).bind(to_text(&status)).bind(NOT_DELETED);
+        for id in ids {
+            query = query.bind(*id);
+        }
+        let face_infos = self.fetch_face_infos(query).await?;
+        operation.record("db.response.returned_rows", face_infos.len() as i64);
+        Ok(face_infos)
+    }
+
+    async fn get_face_infos_by_status(
+        &self,
+        status: FaceStatus,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("sql", "face_info", "get_face_infos_by_status");
+        let sql = format!(
+            "SELECT {} FROM face_info WHERE status = $1 AND is_deleted = $2 \
+             ORDER BY created_on, id LIMIT $3 OFFSET $4",
+            COLUMNS
+        );
+        let query = sqlx::query(&sql)
+            .bind(to_text(&status))
+            .bind(NOT_DELETED)
+            .bind(page_size)
+            .bind(page_offset(page, page_size));
+        self.fetch_face_infos(query).await
+    }
+
+    async fn get_approved_face_infos(
+        &self,
+        order: FaceInfoOrder,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("sql", "face_info", "get_approved_face_infos");
+        let order_by = match order {
+            FaceInfoOrder::Score => "score DESC, id",
+            FaceInfoOrder::CreatedOn => "created_on DESC, id",
+        };
+        let sql = format!(
+            "SELECT {} FROM face_info WHERE status = $1 AND is_deleted = $2 \
+             ORDER BY {} LIMIT $3 OFFSET $4",
+            COLUMNS, order_by
+        );
+        let query = sqlx::query(&sql)
+            .bind(to_text(&FaceStatus::Approved))
+            .bind(NOT_DELETED)
+            .bind(page_size)
+            .bind(page_offset(page, page_size));
+        self.fetch_face_infos(query).await
+    }
+
+    async fn get_approved_face_infos_after(
+        &self,
+        after_id: &str,
+        limit: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("sql", "face_info", "get_approved_face_infos_after");
+        let sql = format!(
+            "SELECT {} FROM face_info WHERE status = $1 AND is_deleted = $2 AND id > $3 \
+             ORDER BY id LIMIT $4",
+            COLUMNS
+        );
+        let query = sqlx::query(&sql)
+            .bind(to_text(&FaceStatus::Approved))
+            .bind(NOT_DELETED)
+            .bind(after_id)
+            .bind(limit);
+        self.fetch_face_infos(query).await
+    }
+
+    async fn search_face_infos_by_trigrams(
+        &self,
+        trigrams: &[String],
+        limit: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("sql", "face_info", "search_face_infos_by_trigrams");
+        if trigrams.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let sql = format!(
+            "SELECT {} FROM face_info JOIN ( \
+                 SELECT face_info_id, COUNT(*) AS matched_cnt FROM face_info_trigram \
+                 WHERE trigram IN ({}) GROUP BY face_info_id \
+             ) matched ON matched.face_info_id = face_info.id \
+             WHERE status = $1 AND is_deleted = $2 ORDER BY matched_cnt DESC, id LIMIT $3",
+            COLUMNS,
+            placeholders(4, trigrams.len())
+        );
+        let mut query = sqlx::query(&sql)
+            .bind(to_text(&FaceStatus::Approved))
+            .bind(NOT_DELETED)
+            .bind(limit);
+        for trigram in trigrams {
+            query = query.bind(trigram);
+        }
+        self.fetch_face_infos(query).await
+    }
+
+    async fn search_face_infos_by_prefix(
+        &self,
+        prefix: &str,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("sql", "face_info", "search_face_infos_by_prefix");
+        let (lower, upper) = get_suffix_range(prefix);
+        let sql = format!(
+            "SELECT {} FROM face_info WHERE status = $1 AND is_deleted = $2 AND id IN ( \
+                 SELECT face_info_id FROM face_info_star_name_suffix \
+                 WHERE suffix >= $3 AND suffix < $4 \
+             ) ORDER BY score DESC, id LIMIT $5 OFFSET $6",
+            COLUMNS
+        );
+        let query = sqlx::query(&sql)
+            .bind(to_text(&FaceStatus::Approved))
+            .bind(NOT_DELETED)
+            .bind(lower)
+            .bind(upper)
+            .bind(page_size)
+            .bind(page_offset(page, page_size));
+        self.fetch_face_infos(query).await
+    }
+
+    async fn count_face_infos_by_prefix(&self, prefix: &str) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "face_info", "count_face_infos_by_prefix");
+        let (lower, upper) = get_suffix_range(prefix);
+        let cnt: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM face_info WHERE status = $1 AND is_deleted = $2 AND id IN ( \
+                 SELECT face_info_id FROM face_info_star_name_suffix \
+                 WHERE suffix >= $3 AND suffix < $4 \
+             )",
+        )
+        .bind(to_text(&FaceStatus::Approved))
+        .bind(NOT_DELETED)
+        .bind(lower)
+        .bind(upper)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(cnt as u64)
+    }
+
+    async fn get_face_info_sample(
+        &self,
+        size: i64,
+        category: Option<&str>,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("sql", "face_info", "get_face_info_sample");
+        let sql = format!(
+            "SELECT {} FROM face_info WHERE status = $1 AND is_deleted = $2 \
+             AND ($3 = 0 OR ',' || categories || ',' LIKE $4 ESCAPE '\\') \
+             ORDER BY RANDOM() LIMIT $5",
+            COLUMNS
+        );
+        let query = sqlx::query(&sql)
+            .bind(to_text(&FaceStatus::Approved))
+            .bind(NOT_DELETED)
+            .bind(category.is_some() as i64)
+            .bind(category.map(category_pattern).unwrap_or_default())
+            .bind(size);
+        self.fetch_face_infos(query).await
+    }
+
+    async fn get_all_face_infos(&self) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("sql", "face_info", "get_all_face_infos");
+        let sql = format!("SELECT {} FROM face_info", COLUMNS);
+        self.fetch_face_infos(sqlx::query(&sql)).await
+    }
+
+    async fn count_all_face_infos(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "face_info", "count_all_face_infos");
+        let cnt: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM face_info")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(cnt as u64)
+    }
+
+    async fn count_face_infos_by_status(&self, status: FaceStatus) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "face_info", "count_face_infos_by_status");
+        let cnt: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM face_info WHERE status = $1 AND is_deleted = $2",
+        )
+        .bind(to_text(&status))
+        .bind(NOT_DELETED)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(cnt as u64)
+    }
+
+    async fn get_deleted_face_infos_before(
+        &self,
+        deleted_before: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let _operation = start_operation("sql", "face_info", "get_deleted_face_infos_before");
+        let sql = format!(
+            "SELECT {} FROM face_info WHERE is_deleted = $1 AND deleted_on < $2",
+            COLUMNS
+        );
+        let query = sqlx::query(&sql).bind(DELETED).bind(deleted_before);
+        self.fetch_face_infos(query).await
+    }
+
+    async fn update_face_info_rating(
+        &self,
+        id: &str,
+        score: f64,
+        upvote: bool,
+        voter: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let operation = start_operation("sql", "face_info", "update_face_info_rating");
+        operation.record("face_info.id", id.to_string());
+        let vote_column = if upvote {
+            "upvote_count"
+        } else {
+            "downvote_count"
+        };
+        let res = sqlx::query(&format!(
+            "UPDATE face_info SET score = $1, {0} = {0} + 1, updater = $2, updated_on = $3 \
+             WHERE id = $4",
+            vote_column
+        ))
+        .bind(score)
+        .bind(voter)
+        .bind(now)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        operation.record("db.response.matched_rows", res.rows_affected() as i64);
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn soft_reset_face_info_scores(
+        &self,
+        target: f64,
+        soft_reset: f64,
+    ) -> RepositoryResult<u64> {
+        let operation = start_operation("sql", "face_info", "soft_reset_face_info_scores");
+        let res = sqlx::query(
+            "UPDATE face_info SET score = score + ($1 - score) * $2 WHERE is_deleted = $3",
+        )
+        .bind(target)
+        .bind(soft_reset)
+        .bind(NOT_DELETED)
+        .execute(&self.pool)
+        .await?;
+        operation.record("db.response.matched_rows", res.rows_affected() as i64);
+        Ok(res.rows_affected())
+    }
+
+    async fn update_face_info_by_patch(
+        &self,
+        face_info: &FaceInfo,
+        patch: &FaceInfoPatch,
+        updater: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let _operation = start_operation("sql", "face_info", "update_face_info_by_patch");
+        // The patched fields are matched against their old values, so a
+        // concurrent update of the same field doesn't match
+        let star_name = patch.star_name.as_ref().unwrap_or(&face_info.star_name);
+        let file_id = patch.file_id.as_ref().unwrap_or(&face_info.file_id);
+        let categories = patch.categories.as_ref().unwrap_or(&face_info.categories);
+        let mut tx = self.pool.begin().await?;
+        let res = sqlx::query(
+            "UPDATE face_info SET star_name = $1, file_id = $2, categories = $11, \
+             updater = $3, updated_on = $4 \
+             WHERE id = $5 AND is_deleted = $6 \
+             AND ($7 = 0 OR star_name = $8) AND ($9 = 0 OR file_id = $10) \
+             AND ($12 = 0 OR categories = $13)",
+        )
+        .bind(star_name)
+        .bind(file_id)
+        .bind(updater)
+        .bind(now)
+        .bind(&face_info.id)
+        .bind(NOT_DELETED)
+        .bind(patch.star_name.is_some() as i64)
+        .bind(&face_info.star_name)
+        .bind(patch.file_id.is_some() as i64)
+        .bind(&face_info.file_id)
+        .bind(categories_to_text(categories))
+        .bind(patch.categories.is_some() as i64)
+        .bind(categories_to_text(&face_info.categories))
+        .execute(&mut *tx)
+        .await?;
+        if res.rows_affected() > 0 && patch.star_name.is_some() {
+            index_star_name(&mut tx, &face_info.id, star_name).await?;
+        }
+        tx.commit().await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn update_face_info_status(
+        &self,
+        id: &str,
+        old_status: FaceStatus,
+        status: FaceStatus,
+        moderator: &str,
+        reason: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let _operation = start_operation("sql", "face_info", "update_face_info_status");
+        let res = sqlx::query(
+            "UPDATE face_info SET status = $1, moderator = $2, moderation_reason = $3, \
+             moderated_on = $4, updater = $5, updated_on = $6 \
+             WHERE id = $7 AND is_deleted = $8 AND status = $9",
+        )
+        .bind(to_text(&status))
+        .bind(moderator)
+        .bind(reason)
+        .bind(now)
+        .bind(moderator)
+        .bind(now)
+        .bind(id)
+        .bind(NOT_DELETED)
+        .bind(to_text(&old_status))
+        .execute(&self.pool)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn soft_delete_face_info(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let _operation = start_operation("sql", "face_info", "soft_delete_face_info");
+        let res = sqlx::query(
+            "UPDATE face_info SET is_deleted = $1, deleted_on = $2, updater = $3, updated_on = $4 \
+             WHERE id = $5 AND is_deleted = $6",
+        )
+        .bind(DELETED)
+        .bind(now)
+        .bind(operator)
+        .bind(now)
+        .bind(id)
+        .bind(NOT_DELETED)
+        .execute(&self.pool)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn restore_face_info(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let _operation = start_operation("sql", "face_info", "restore_face_info");
+        let res = sqlx::query(
+            "UPDATE face_info SET is_deleted = $1, deleted_on = $2, updater = $3, updated_on = $4 \
+             WHERE id = $5 AND is_deleted = $6",
+        )
+        .bind(NOT_DELETED)
+        .bind(0_i64)
+        .bind(operator)
+        .bind(now)
+        .bind(id)
+        .bind(DELETED)
+        .execute(&self.pool)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn delete_face_infos_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "face_info", "delete_face_infos_by_ids");
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        for table in ["face_info_trigram", "face_info_star_name_suffix"] {
+            let sql = format!(
+                "DELETE FROM {} WHERE face_info_id IN ({})",
+                table,
+                placeholders(1, ids.len())
+            );
+            let mut query = sqlx::query(&sql);
+            for id in ids {
+                query = query.bind(id);
+            }
+            query.execute(&mut *tx).await?;
+        }
+
+        let sql = format!(
+            "DELETE FROM face_info WHERE id IN ({})",
+            placeholders(1, ids.len())
+        );
+        let mut query = sqlx::query(&sql);
+        for id in ids {
+            query = query.bind(id);
+        }
+        let deleted_cnt = query.execute(&mut *tx).await?.rows_affected();
+        tx.commit().await?;
+        Ok(deleted_cnt)
+    }
+}
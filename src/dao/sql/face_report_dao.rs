@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use sqlx::any::AnyRow;
+use sqlx::{AnyPool, Row};
+
+use crate::dao::sql::{from_text, placeholders, to_text};
+use crate::dao::start_operation;
+use crate::entity::face_report::{FaceReport, FaceReportSummary, ReportReasonCnt};
+use crate::entity::NOT_DELETED;
+use crate::repository::{FaceReportRepository, RepositoryResult};
+
+const COLUMNS: &str = "id, face_info_id, reporter, reason, comment, is_resolved, resolved_on, \
+    creator, updater, created_on, updated_on, deleted_on, is_deleted";
+
+/// The face_report repository backed by the "face_report" table.
+pub struct SqlFaceReportDao {
+    pool: AnyPool,
+}
+
+impl SqlFaceReportDao {
+    pub fn new(pool: AnyPool) -> Self {
+        SqlFaceReportDao { pool }
+    }
+}
+
+fn face_report_from_row(row: &AnyRow) -> Result<FaceReport, sqlx::Error> {
+    Ok(FaceReport {
+        id: row.try_get("id")?,
+        face_info_id: row.try_get("face_info_id")?,
+        reporter: row.try_get("reporter")?,
+        reason: from_text(row.try_get("reason")?)?,
+        comment: row.try_get("comment")?,
+        is_resolved: row.try_get("is_resolved")?,
+        resolved_on: row.try_get("resolved_on")?,
+        creator: row.try_get("creator")?,
+        updater: row.try_get("updater")?,
+        created_on: row.try_get("created_on")?,
+        updated_on: row.try_get("updated_on")?,
+        deleted_on: row.try_get("deleted_on")?,
+        is_deleted: row.try_get("is_deleted")?,
+    })
+}
+
+#[async_trait]
+impl FaceReportRepository for SqlFaceReportDao {
+    async fn add_face_report_if_absent(&self, face_report: &FaceReport) -> RepositoryResult<bool> {
+        let _operation = start_operation("sql", "face_report", "add_face_report_if_absent");
+        // The unique index on the unresolved reports skips a second one of the reporter
+        let res = sqlx::query(&format!(
+            "INSERT INTO face_report ({}) VALUES ({}) ON CONFLICT DO NOTHING",
+            COLUMNS,
+            placeholders(1, 13)
+        ))
+        .bind(&face_report.id)
+        .bind(&face_report.face_info_id)
+        .bind(&face_report.reporter)
+        .bind(to_text(&face_report.reason))
+        .bind(&face_report.comment)
+        .bind(face_report.is_resolved)
+        .bind(face_report.resolved_on)
+        .bind(&face_report.creator)
+        .bind(&face_report.updater)
+        .bind(face_report.created_on)
+        .bind(face_report.updated_on)
+        .bind(face_report.deleted_on)
+        .bind(face_report.is_deleted)
+        .execute(&self.pool)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn add_face_reports(&self, face_reports: Vec<FaceReport>) -> RepositoryResult<()> {
+        let operation = start_operation("sql", "face_report", "add_face_reports");
+        operation.record("face_report.count", face_reports.len() as i64);
+        let sql = format!(
+            "INSERT INTO face_report ({}) VALUES ({})",
+            COLUMNS,
+            placeholders(1, 13)
+        );
+        let mut tx = self.pool.begin().await?;
+        for face_report in &face_reports {
+            sqlx::query(&sql)
+                .bind(&face_report.id)
+                .bind(&face_report.face_info_id)
+                .bind(&face_report.reporter)
+                .bind(to_text(&face_report.reason))
+                .bind(&face_report.comment)
+                .bind(face_report.is_resolved)
+                .bind(face_report.resolved_on)
+                .bind(&face_report.creator)
+                .bind(&face_report.updater)
+                .bind(face_report.created_on)
+                .bind(face_report.updated_on)
+                .bind(face_report.deleted_on)
+                .bind(face_report.is_deleted)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn count_unresolved_face_reports(&self, face_info_id: &str) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "face_report", "count_unresolved_face_reports");
+        let cnt: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM face_report \
+             WHERE face_info_id = $1 AND is_resolved = 0 AND is_deleted = $2",
+        )
+        .bind(face_info_id)
+        .bind(NOT_DELETED)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(cnt as u64)
+    }
+
+    async fn resolve_face_reports(
+        &self,
+        face_info_id: &str,
+        moderator: &str,
+        now: i64,
+    ) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "face_report", "resolve_face_reports");
+        let res = sqlx::query(
+            "UPDATE face_report SET is_resolved = 1, resolved_on = $1, updater = $2, \
+             updated_on = $3 WHERE face_info_id = $4 AND is_resolved = 0",
+        )
+        .bind(now)
+        .bind(moderator)
+        .bind(now)
+        .bind(face_info_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(res.rows_affected())
+    }
+
+    async fn get_unresolved_face_report_summaries(
+        &self,
+        skip: u64,
+        limit: i64,
+    ) -> RepositoryResult<Vec<FaceReportSummary>> {
+        let _operation =
+            start_operation("sql", "face_report", "get_unresolved_face_report_summaries");
+        // Step 1: Get a page of the reported face_infos
+        let rows = sqlx::query(
+            "SELECT face_info_id, COUNT(*) AS report_cnt, MAX(created_on) AS last_reported_on \
+             FROM face_report WHERE is_resolved = 0 AND is_deleted = $1 \
+             GROUP BY face_info_id ORDER BY report_cnt DESC, face_info_id LIMIT $2 OFFSET $3",
+        )
+        .bind(NOT_DELETED)
+        .bind(limit)
+        .bind(skip as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut summaries = rows
+            .iter()
+            .map(|row| {
+                Ok(FaceReportSummary {
+                    face_info_id: row.try_get("face_info_id")?,
+                    report_cnt: row.try_get("report_cnt")?,
+                    reason_cnts: vec![],
+                    last_reported_on: row.try_get("last_reported_on")?,
+                })
+            })
+            .collect::<Result<Vec<_>, sqlx::Error>>()?;
+        if summaries.is_empty() {
+            return Ok(summaries);
+        }
+
+        // Step 2: Count the reasons of the page
+        let sql = format!(
+            "SELECT face_info_id, reason, COUNT(*) AS cnt FROM face_report \
+             WHERE is_resolved = 0 AND is_deleted = $1 AND face_info_id IN ({}) \
+             GROUP BY face_info_id, reason",
+            placeholders(2, summaries.len())
+        );
+        let mut query = sqlx::query(&sql).bind(NOT_DELETED);
+        for summary in &summaries {
+            query = query.bind(summary.face_info_id.as_str());
+        }
+        let mut reason_cnts: HashMap<String, Vec<ReportReasonCnt>> = HashMap::new();
+        for row in query.fetch_all(&self.pool).await? {
+            reason_cnts
+                .entry(row.try_get("face_info_id")?)
+                .or_default()
+                .push(ReportReasonCnt {
+                    reason: from_text(row.try_get("reason")?)?,
+                    cnt: row.try_get("cnt")?,
+                });
+        }
+        for summary in &mut summaries {
+            summary.reason_cnts = reason_cnts
+                .remove(&summary.face_info_id)
+                .unwrap_or_default();
+        }
+        Ok(summaries)
+    }
+
+    async fn get_all_face_reports(&self) -> RepositoryResult<Vec<FaceReport>> {
+        let _operation = start_operation("sql", "face_report", "get_all_face_reports");
+        let rows = sqlx::query(&format!("SELECT {} FROM face_report", COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(face_report_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn count_all_face_reports(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "face_report", "count_all_face_reports");
+        let cnt: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM face_report")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(cnt as u64)
+    }
+
+    async fn delete_face_reports_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "face_report", "delete_face_reports_by_ids");
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let sql = format!(
+            "DELETE FROM face_report WHERE id IN ({})",
+            placeholders(1, ids.len())
+        );
+        let mut query = sqlx::query(&sql);
+        for id in ids {
+            query = query.bind(id);
+        }
+        Ok(query.execute(&self.pool).await?.rows_affected())
+    }
+}
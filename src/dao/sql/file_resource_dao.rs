@@ -0,0 +1,210 @@
+use async_trait::async_trait;
+use sqlx::any::{AnyArguments, AnyRow};
+use sqlx::{Any, AnyPool, Row};
+
+use crate::dao::sql::{from_text, placeholders, to_text};
+use crate::dao::start_operation;
+use crate::entity::file_resource::FileResource;
+use crate::entity::{DELETED, NOT_DELETED};
+use crate::repository::{FileResourceRepository, RepositoryResult};
+
+const COLUMNS: &str = "id, file_name, file_uri, uri_type, md5, thumb_uri, thumb_type, \
+    creator, updater, created_on, updated_on, deleted_on, is_deleted";
+
+/// The file_resource repository backed by the "file_resource" table.
+pub struct SqlFileResourceDao {
+    pool: AnyPool,
+}
+
+impl SqlFileResourceDao {
+    pub fn new(pool: AnyPool) -> Self {
+        SqlFileResourceDao { pool }
+    }
+
+    async fn fetch_file_resources<'q>(
+        &self,
+        query: sqlx::query::Query<'q, Any, AnyArguments<'q>>,
+    ) -> RepositoryResult<Vec<FileResource>> {
+        let rows = query.fetch_all(&self.pool).await?;
+        Ok(rows
+            .iter()
+            .map(file_resource_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    /// Flips `is_deleted` of the file_resource, returns false if not matched.
+    async fn set_deleted(
+        &self,
+        id: &str,
+        is_deleted: i64,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let deleted_on = if is_deleted == DELETED { now } else { 0 };
+        let res = sqlx::query(
+            "UPDATE file_resource SET is_deleted = $1, deleted_on = $2, updater = $3, \
+             updated_on = $4 WHERE id = $5 AND is_deleted <> $6",
+        )
+        .bind(is_deleted)
+        .bind(deleted_on)
+        .bind(operator)
+        .bind(now)
+        .bind(id)
+        .bind(is_deleted)
+        .execute(&self.pool)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+}
+
+fn file_resource_from_row(row: &AnyRow) -> Result<FileResource, sqlx::Error> {
+    Ok(FileResource {
+        id: row.try_get("id")?,
+        file_name: row.try_get("file_name")?,
+        file_uri: row.try_get("file_uri")?,
+        uri_type: from_text(row.try_get("uri_type")?)?,
+        md5: row.try_get("md5")?,
+        thumb_uri: row.try_get("thumb_uri")?,
+        thumb_type: from_text(row.try_get("thumb_type")?)?,
+        creator: row.try_get("creator")?,
+        updater: row.try_get("updater")?,
+        created_on: row.try_get("created_on")?,
+        updated_on: row.try_get("updated_on")?,
+        deleted_on: row.try_get("deleted_on")?,
+        is_deleted: row.try_get("is_deleted")?,
+    })
+}
+
+/// Inserts the file_resource in the transaction.
+async fn insert_file_resource(
+    tx: &mut sqlx::Transaction<'_, Any>,
+    file_resource: &FileResource,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!(
+        "INSERT INTO file_resource ({}) VALUES ({})",
+        COLUMNS,
+        placeholders(1, 13)
+    ))
+    .bind(&file_resource.id)
+    .bind(&file_resource.file_name)
+    .bind(&file_resource.file_uri)
+    .bind(to_text(&file_resource.uri_type))
+    .bind(&file_resource.md5)
+    .bind(&file_resource.thumb_uri)
+    .bind(to_text(&file_resource.thumb_type))
+    .bind(&file_resource.creator)
+    .bind(&file_resource.updater)
+    .bind(file_resource.created_on)
+    .bind(file_resource.updated_on)
+    .bind(file_resource.deleted_on)
+    .bind(file_resource.is_deleted)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+#[async_trait]
+impl FileResourceRepository for SqlFileResourceDao {
+    async fn add_file_resource(&self, file_resource: &FileResource) -> RepositoryResult<()> {
+        let _operation = start_operation("sql", "file_resource", "add_file_resource");
+        let mut tx = self.pool.begin().await?;
+        insert_file_resource(&mut tx, file_resource).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn add_file_resources(&self, file_resources: Vec<FileResource>) -> RepositoryResult<()> {
+        let _operation = start_operation("sql", "file_resource", "add_file_resources");
+        let mut tx = self.pool.begin().await?;
+        for file_resource in &file_resources {
+            insert_file_resource(&mut tx, file_resource).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_file_resource_by_id(&self, id: &str) -> RepositoryResult<Option<FileResource>> {
+        let _operation = start_operation("sql", "file_resource", "get_file_resource_by_id");
+        let sql = format!(
+            "SELECT {} FROM file_resource WHERE id = $1 AND is_deleted = $2",
+            COLUMNS
+        );
+        let query = sqlx::query(&sql).bind(id).bind(NOT_DELETED);
+        Ok(self.fetch_file_resources(query).await?.pop())
+    }
+
+    async fn get_file_resource_by_md5(&self, md5: &str) -> RepositoryResult<Option<FileResource>> {
+        let _operation = start_operation("sql", "file_resource", "get_file_resource_by_md5");
+        let sql = format!(
+            "SELECT {} FROM file_resource WHERE md5 = $1 AND is_deleted = $2 LIMIT 1",
+            COLUMNS
+        );
+        let query = sqlx::query(&sql).bind(md5).bind(NOT_DELETED);
+        Ok(self.fetch_file_resources(query).await?.pop())
+    }
+
+    async fn get_all_file_resources(&self) -> RepositoryResult<Vec<FileResource>> {
+        let _operation = start_operation("sql", "file_resource", "get_all_file_resources");
+        let sql = format!("SELECT {} FROM file_resource", COLUMNS);
+        self.fetch_file_resources(sqlx::query(&sql)).await
+    }
+
+    async fn count_all_file_resources(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "file_resource", "count_all_file_resources");
+        let cnt: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM file_resource")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(cnt as u64)
+    }
+
+    async fn get_deleted_file_resources_before(
+        &self,
+        deleted_before: i64,
+    ) -> RepositoryResult<Vec<FileResource>> {
+        let _operation =
+            start_operation("sql", "file_resource", "get_deleted_file_resources_before");
+        let sql = format!(
+            "SELECT {} FROM file_resource WHERE is_deleted = $1 AND deleted_on < $2",
+            COLUMNS
+        );
+        let query = sqlx::query(&sql).bind(DELETED).bind(deleted_before);
+        self.fetch_file_resources(query).await
+    }
+
+    async fn soft_delete_file_resource(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let _operation = start_operation("sql", "file_resource", "soft_delete_file_resource");
+        self.set_deleted(id, DELETED, operator, now).await
+    }
+
+    async fn restore_file_resource(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        let _operation = start_operation("sql", "file_resource", "restore_file_resource");
+        self.set_deleted(id, NOT_DELETED, operator, now).await
+    }
+
+    async fn delete_file_resources_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "file_resource", "delete_file_resources_by_ids");
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let sql = format!(
+            "DELETE FROM file_resource WHERE id IN ({})",
+            placeholders(1, ids.len())
+        );
+        let mut query = sqlx::query(&sql);
+        for id in ids {
+            query = query.bind(id);
+        }
+        Ok(query.execute(&self.pool).await?.rows_affected())
+    }
+}
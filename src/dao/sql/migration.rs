@@ -0,0 +1,246 @@
+use futures_util::future::BoxFuture;
+use sqlx::{AnyConnection, AnyPool};
+
+use crate::dao::sql::face_info_dao;
+
+/// Fills the data of a migration the statements can't, in its transaction.
+type Backfill = fn(&mut AnyConnection) -> BoxFuture<'_, Result<(), sqlx::Error>>;
+
+/// A schema change, applied once in its own transaction.
+struct Migration {
+    version: i64,
+    name: &'static str,
+    statements: &'static [&'static str],
+    /// Run after the statements
+    backfill: Option<Backfill>,
+}
+
+/// The schema changes, append only, in version order.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create tables",
+        statements: &[
+            "CREATE TABLE face_info (
+            id TEXT PRIMARY KEY,
+            star_name TEXT NOT NULL,
+            file_id TEXT NOT NULL,
+            upvote_count BIGINT NOT NULL,
+            downvote_count BIGINT NOT NULL,
+            score DOUBLE PRECISION NOT NULL,
+            status TEXT NOT NULL,
+            moderator TEXT NOT NULL,
+            moderation_reason TEXT NOT NULL,
+            moderated_on BIGINT NOT NULL,
+            creator TEXT NOT NULL,
+            updater TEXT NOT NULL,
+            created_on BIGINT NOT NULL,
+            updated_on BIGINT NOT NULL,
+            deleted_on BIGINT NOT NULL,
+            is_deleted BIGINT NOT NULL
+        )",
+            "CREATE INDEX idx_face_info_status ON face_info (status, created_on)",
+            "CREATE INDEX idx_face_info_file_id ON face_info (file_id)",
+            "CREATE TABLE file_resource (
+            id TEXT PRIMARY KEY,
+            file_name TEXT NOT NULL,
+            file_uri TEXT NOT NULL,
+            uri_type TEXT NOT NULL,
+            md5 TEXT NOT NULL,
+            thumb_uri TEXT NOT NULL,
+            thumb_type TEXT NOT NULL,
+            creator TEXT NOT NULL,
+            updater TEXT NOT NULL,
+            created_on BIGINT NOT NULL,
+            updated_on BIGINT NOT NULL,
+            deleted_on BIGINT NOT NULL,
+            is_deleted BIGINT NOT NULL
+        )",
+            "CREATE INDEX idx_file_resource_md5 ON file_resource (md5)",
+            "CREATE TABLE rating_log (
+            id TEXT PRIMARY KEY,
+            win_face_id TEXT NOT NULL,
+            loss_face_id TEXT NOT NULL,
+            creator TEXT NOT NULL,
+            updater TEXT NOT NULL,
+            created_on BIGINT NOT NULL,
+            updated_on BIGINT NOT NULL,
+            deleted_on BIGINT NOT NULL,
+            is_deleted BIGINT NOT NULL
+        )",
+            "CREATE INDEX idx_rating_log_win_face_id ON rating_log (win_face_id)",
+            "CREATE INDEX idx_rating_log_loss_face_id ON rating_log (loss_face_id)",
+            "CREATE TABLE face_info_audit (
+            id TEXT PRIMARY KEY,
+            face_info_id TEXT NOT NULL,
+            field TEXT NOT NULL,
+            old_value TEXT NOT NULL,
+            new_value TEXT NOT NULL,
+            creator TEXT NOT NULL,
+            updater TEXT NOT NULL,
+            created_on BIGINT NOT NULL,
+            updated_on BIGINT NOT NULL,
+            deleted_on BIGINT NOT NULL,
+            is_deleted BIGINT NOT NULL
+        )",
+            "CREATE INDEX idx_face_info_audit_face_info_id ON face_info_audit (face_info_id)",
+            "CREATE TABLE face_report (
+            id TEXT PRIMARY KEY,
+            face_info_id TEXT NOT NULL,
+            reporter TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            comment TEXT NOT NULL,
+            is_resolved BIGINT NOT NULL,
+            resolved_on BIGINT NOT NULL,
+            creator TEXT NOT NULL,
+            updater TEXT NOT NULL,
+            created_on BIGINT NOT NULL,
+            updated_on BIGINT NOT NULL,
+            deleted_on BIGINT NOT NULL,
+            is_deleted BIGINT NOT NULL
+        )",
+            // One unresolved report per reporter and face_info
+            "CREATE UNIQUE INDEX idx_face_report_unresolved ON face_report (face_info_id, reporter)
+            WHERE is_resolved = 0 AND is_deleted = 0",
+        ],
+        backfill: None,
+    },
+    Migration {
+        version: 2,
+        name: "index face_info by score",
+        statements: &["CREATE INDEX idx_face_info_score ON face_info (status, score)"],
+        backfill: None,
+    },
+    Migration {
+        version: 3,
+        name: "index face_info by star_name trigrams",
+        statements: &[
+            "CREATE TABLE face_info_trigram (
+            face_info_id TEXT NOT NULL,
+            trigram TEXT NOT NULL,
+            PRIMARY KEY (trigram, face_info_id)
+        )",
+            "CREATE INDEX idx_face_info_trigram_face_info_id ON face_info_trigram (face_info_id)",
+        ],
+        backfill: Some(face_info_dao::index_all_star_names),
+    },
+    Migration {
+        version: 4,
+        name: "add categories and category_rating",
+        statements: &[
+            "ALTER TABLE face_info ADD COLUMN categories TEXT NOT NULL DEFAULT ''",
+            "ALTER TABLE rating_log ADD COLUMN category TEXT NOT NULL DEFAULT ''",
+            "CREATE TABLE category_rating (
+            category TEXT NOT NULL,
+            face_info_id TEXT NOT NULL,
+            score DOUBLE PRECISION NOT NULL,
+            upvote_count BIGINT NOT NULL,
+            downvote_count BIGINT NOT NULL,
+            created_on BIGINT NOT NULL,
+            updated_on BIGINT NOT NULL,
+            PRIMARY KEY (category, face_info_id)
+        )",
+            "CREATE INDEX idx_category_rating_score ON category_rating (category, score)",
+            "CREATE INDEX idx_category_rating_face_info_id ON category_rating (face_info_id)",
+        ],
+        backfill: None,
+    },
+    Migration {
+        version: 5,
+        name: "add seasons",
+        statements: &[
+            "ALTER TABLE rating_log ADD COLUMN season_id TEXT NOT NULL DEFAULT ''",
+            "CREATE INDEX idx_rating_log_season_id ON rating_log (season_id)",
+            "CREATE TABLE season (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            soft_reset DOUBLE PRECISION NOT NULL,
+            creator TEXT NOT NULL,
+            started_on BIGINT NOT NULL,
+            ended_on BIGINT NOT NULL
+        )",
+            "CREATE INDEX idx_season_ended_on ON season (ended_on)",
+            "CREATE TABLE season_snapshot (
+            season_id TEXT NOT NULL,
+            face_info_id TEXT NOT NULL,
+            star_name TEXT NOT NULL,
+            score DOUBLE PRECISION NOT NULL,
+            upvote_count BIGINT NOT NULL,
+            downvote_count BIGINT NOT NULL,
+            created_on BIGINT NOT NULL,
+            PRIMARY KEY (season_id, face_info_id)
+        )",
+            "CREATE INDEX idx_season_snapshot_score ON season_snapshot (season_id, score)",
+        ],
+        backfill: None,
+    },
+    Migration {
+        version: 6,
+        name: "index face_info by star_name word suffixes",
+        statements: &[
+            "CREATE TABLE face_info_star_name_suffix (
+            face_info_id TEXT NOT NULL,
+            suffix TEXT NOT NULL,
+            PRIMARY KEY (suffix, face_info_id)
+        )",
+            "CREATE INDEX idx_face_info_star_name_suffix_face_info_id \
+             ON face_info_star_name_suffix (face_info_id)",
+        ],
+        backfill: Some(face_info_dao::index_all_star_name_suffixes),
+    },
+    Migration {
+        version: 7,
+        name: "add the pending soft reset of seasons",
+        statements: &["ALTER TABLE season ADD COLUMN reset_pending BIGINT NOT NULL DEFAULT 0"],
+        backfill: None,
+    },
+];
+
+/// Applies the migrations which have not been recorded in the "schema_migration" table,
+/// returns the number applied.
+pub async fn migrate(pool: &AnyPool) -> Result<usize, sqlx::Error> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_migration (
+            version BIGINT PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_on BIGINT NOT NULL
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    let applied_version: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(version) FROM schema_migration")
+            .fetch_one(pool)
+            .await?;
+    let applied_version = applied_version.unwrap_or_default();
+
+    let mut applied_cnt = 0;
+    for migration in MIGRATIONS {
+        if migration.version <= applied_version {
+            continue;
+        }
+
+        let mut tx = pool.begin().await?;
+        for statement in migration.statements {
+            sqlx::query(statement).execute(&mut *tx).await?;
+        }
+        if let Some(backfill) = migration.backfill {
+            backfill(&mut tx).await?;
+        }
+        sqlx::query("INSERT INTO schema_migration (version, name, applied_on) VALUES ($1, $2, $3)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(chrono::Utc::now().timestamp())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        info!(
+            "Applied sql migration, version: {}, name: {:?}",
+            migration.version, migration.name
+        );
+        applied_cnt += 1;
+    }
+    Ok(applied_cnt)
+}
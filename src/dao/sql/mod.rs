@@ -0,0 +1,455 @@
+//! The repositories backed by a relational database, SQLite or PostgreSQL.
+//!
+//! The queries stick to the SQL both databases understand, and the
+//! parameters are numbered (`$1`) as both drivers accept them.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::repository::get_page_skip;
+
+pub mod category_rating_dao;
+pub mod database_dao;
+pub mod face_info_audit_dao;
+pub mod face_info_dao;
+pub mod face_report_dao;
+pub mod file_resource_dao;
+pub mod migration;
+pub mod rating_log_dao;
+pub mod season_dao;
+pub mod season_snapshot_dao;
+
+/// Gets `cnt` numbered parameters from `start`, e.g. `$2, $3, $4`.
+fn placeholders(start: usize, cnt: usize) -> String {
+    (start..start + cnt)
+        .map(|idx| format!("${}", idx))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Gets the `OFFSET` of the page, see `repository::get_page_skip`.
+fn page_offset(page: u64, page_size: i64) -> i64 {
+    i64::try_from(get_page_skip(page, page_size)).unwrap_or(i64::MAX)
+}
+
+/// Gets the serde name of an enum value, which is stored as text.
+fn to_text<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(text)) => text,
+        _ => String::new(),
+    }
+}
+
+/// Parses an enum value from its serde name.
+fn from_text<T: DeserializeOwned>(text: String) -> Result<T, sqlx::Error> {
+    serde_json::from_value(serde_json::Value::String(text))
+        .map_err(|err| sqlx::Error::Decode(Box::new(err)))
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::algorithm::fuzzy_match;
+    use crate::entity::face_info::{FaceInfo, FaceInfoPatch, FaceStatus};
+    use crate::entity::face_report::{FaceReport, ReportReason};
+    use crate::entity::rating_log::RatingLog;
+    use crate::entity::season::Season;
+    use crate::entity::season_snapshot::SeasonSnapshot;
+    use crate::repository::Repositories;
+    use crate::resource;
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(placeholders(2, 3), "$2, $3, $4");
+        assert_eq!(placeholders(1, 0), "");
+    }
+
+    #[actix_rt::test]
+    async fn test_sql_repositories() {
+        let pool = resource::sql::connect("sqlite::memory:").await.unwrap();
+        assert!(migration::migrate(&pool).await.unwrap() > 0);
+        assert_eq!(migration::migrate(&pool).await.unwrap(), 0);
+        let repos = Repositories::sql(pool);
+
+        // Step 1: face_infos
+        for (id, status) in [("1", FaceStatus::Approved), ("2", FaceStatus::Pending)] {
+            repos
+                .face_info
+                .add_face_info(&FaceInfo {
+                    id: id.to_string(),
+                    star_name: format!("star {}", id),
+                    categories: vec!["actors".to_string(), "best_of".to_string()],
+                    status,
+                    ..FaceInfo::default()
+                })
+                .await
+                .unwrap();
+        }
+        let sample = repos.face_info.get_face_info_sample(2, None).await.unwrap();
+        assert_eq!(sample.len(), 1);
+        assert_eq!(sample[0].id, "1");
+        assert_eq!(sample[0].categories, vec!["actors", "best_of"]);
+        // A category matches as a whole, `_` included
+        for (category, cnt) in [("best_of", 1), ("bestxof", 0), ("best", 0)] {
+            let sample = repos
+                .face_info
+                .get_face_info_sample(2, Some(category))
+                .await
+                .unwrap();
+            assert_eq!(sample.len(), cnt, "{}", category);
+        }
+
+        assert!(repos
+            .face_info
+            .update_face_info_rating("1", 1416.0, true, "voter", 1)
+            .await
+            .unwrap());
+        let face_info = repos.face_info.get_face_info_by_id("1").await.unwrap();
+        let face_info = face_info.unwrap();
+        assert_eq!(face_info.score, 1416.0);
+        assert_eq!(face_info.upvote_count, 1);
+        assert_eq!(face_info.status, FaceStatus::Approved);
+
+        // A stale patch doesn't match
+        let patch = FaceInfoPatch {
+            star_name: Some("renamed".to_string()),
+            file_id: None,
+            categories: Some(vec!["actors".to_string()]),
+        };
+        assert!(repos
+            .face_info
+            .update_face_info_by_patch(&face_info, &patch, "updater", 2)
+            .await
+            .unwrap());
+        assert!(!repos
+            .face_info
+            .update_face_info_by_patch(&face_info, &patch, "updater", 2)
+            .await
+            .unwrap());
+        // The renamed star_name is searched by its trigrams
+        let face_infos = repos
+            .face_info
+            .search_face_infos_by_trigrams(&fuzzy_match::trigrams("renamd"), 10)
+            .await
+            .unwrap();
+        assert_eq!(face_infos.len(), 1);
+        assert_eq!(face_infos[0].star_name, "renamed");
+        assert_eq!(face_infos[0].categories, vec!["actors"]);
+        assert!(repos
+            .face_info
+            .search_face_infos_by_trigrams(&fuzzy_match::trigrams("star"), 10)
+            .await
+            .unwrap()
+            .is_empty());
+        // And by its prefix
+        let face_infos = repos
+            .face_info
+            .search_face_infos_by_prefix("REN", 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(face_infos.len(), 1);
+        assert_eq!(face_infos[0].star_name, "renamed");
+        assert_eq!(
+            repos
+                .face_info
+                .count_face_infos_by_prefix("ren")
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            repos
+                .face_info
+                .count_face_infos_by_prefix("star")
+                .await
+                .unwrap(),
+            0
+        );
+
+        assert!(repos
+            .face_info
+            .update_face_info_status("2", FaceStatus::Pending, FaceStatus::Approved, "mod", "", 3)
+            .await
+            .unwrap());
+        let face_infos = repos
+            .face_info
+            .get_face_infos_by_ids(&["1", "2"], FaceStatus::Approved)
+            .await
+            .unwrap();
+        assert_eq!(face_infos.len(), 2);
+
+        // Step 2: category_ratings, upserted by the votes
+        for (face_info_id, score, upvote) in [("1", 1416.0, true), ("2", 1384.0, false)] {
+            repos
+                .category_rating
+                .update_category_rating("actors", face_info_id, score, upvote, 3)
+                .await
+                .unwrap();
+        }
+        repos
+            .category_rating
+            .update_category_rating("actors", "2", 1400.0, true, 4)
+            .await
+            .unwrap();
+        let category_ratings = repos
+            .category_rating
+            .get_category_ratings_page("actors", 0, 10)
+            .await
+            .unwrap();
+        let ranks: Vec<(&str, f64, u64, u64)> = category_ratings
+            .iter()
+            .map(|r| {
+                (
+                    r.face_info_id.as_str(),
+                    r.score,
+                    r.upvote_count,
+                    r.downvote_count,
+                )
+            })
+            .collect();
+        assert_eq!(ranks, vec![("1", 1416.0, 1, 0), ("2", 1400.0, 1, 1)]);
+        assert_eq!(category_ratings[1].created_on, 3);
+        assert_eq!(category_ratings[1].updated_on, 4);
+        assert!(repos
+            .category_rating
+            .get_category_ratings("best_of", &["1", "2"])
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Step 3: seasons, their snapshots and the soft reset
+        repos
+            .season
+            .add_season(&Season {
+                id: "1".to_string(),
+                started_on: 3,
+                ..Season::default()
+            })
+            .await
+            .unwrap();
+        let current_season = repos.season.get_current_season().await.unwrap();
+        assert_eq!(current_season.unwrap().id, "1");
+        assert!(repos.season.end_season("1", 4).await.unwrap());
+        assert!(!repos.season.end_season("1", 4).await.unwrap());
+        assert!(repos.season.get_current_season().await.unwrap().is_none());
+        let season = repos.season.get_season_by_id("1").await.unwrap().unwrap();
+        assert_eq!(season.ended_on, 4);
+
+        let face_infos = repos
+            .face_info
+            .get_face_infos_by_ids(&["1", "2"], FaceStatus::Approved)
+            .await
+            .unwrap();
+        repos
+            .season_snapshot
+            .save_season_snapshots(
+                face_infos
+                    .iter()
+                    .map(|face_info| SeasonSnapshot::of("1", face_info, 4))
+                    .collect(),
+            )
+            .await
+            .unwrap();
+        let season_snapshots = repos
+            .season_snapshot
+            .get_season_snapshots_page("1", 0, 10)
+            .await
+            .unwrap();
+        let standings: Vec<(&str, f64)> = season_snapshots
+            .iter()
+            .map(|s| (s.face_info_id.as_str(), s.score))
+            .collect();
+        assert_eq!(standings, vec![("1", 1416.0), ("2", 1400.0)]);
+        assert_eq!(season_snapshots[0].star_name, "renamed");
+
+        assert_eq!(
+            repos
+                .face_info
+                .soft_reset_face_info_scores(1400.0, 0.25)
+                .await
+                .unwrap(),
+            2
+        );
+        let face_info = repos.face_info.get_face_info_by_id("1").await.unwrap();
+        assert_eq!(face_info.unwrap().score, 1412.0);
+
+        // Step 4: soft delete & purge
+        repos
+            .rating_log
+            .add_rating_logs(vec![RatingLog {
+                id: "1".to_string(),
+                win_face_id: "1".to_string(),
+                loss_face_id: "2".to_string(),
+                ..RatingLog::default()
+            }])
+            .await
+            .unwrap();
+        assert!(repos
+            .face_info
+            .soft_delete_face_info("2", "operator", 4)
+            .await
+            .unwrap());
+        assert!(repos
+            .face_info
+            .get_face_info_by_id("2")
+            .await
+            .unwrap()
+            .is_none());
+        let deleted_ids: Vec<String> = repos
+            .face_info
+            .get_deleted_face_infos_before(5)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|face_info| face_info.id)
+            .collect();
+        assert_eq!(deleted_ids, vec!["2".to_string()]);
+        assert_eq!(
+            repos
+                .rating_log
+                .purge_rating_logs(&deleted_ids, 5)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            repos
+                .face_info
+                .delete_face_infos_by_ids(&deleted_ids)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            repos
+                .category_rating
+                .delete_category_ratings_by_face_info_ids(&deleted_ids)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(repos.face_info.count_all_face_infos().await.unwrap(), 1);
+        // The standings of the ended seasons are kept
+        let season_snapshots = repos
+            .season_snapshot
+            .get_season_snapshots_page("1", 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(season_snapshots.len(), 2);
+
+        // Step 5: face_reports, once per reporter
+        for (id, reporter) in [("1", "a"), ("2", "a"), ("3", "b")] {
+            repos
+                .face_report
+                .add_face_report_if_absent(&FaceReport {
+                    id: id.to_string(),
+                    face_info_id: "1".to_string(),
+                    reporter: reporter.to_string(),
+                    reason: ReportReason::Duplicate,
+                    created_on: 6,
+                    ..FaceReport::default()
+                })
+                .await
+                .unwrap();
+        }
+        let summaries = repos
+            .face_report
+            .get_unresolved_face_report_summaries(0, 10)
+            .await
+            .unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].report_cnt, 2);
+        assert_eq!(summaries[0].reason_cnts[0].reason, ReportReason::Duplicate);
+        assert_eq!(
+            repos
+                .face_report
+                .resolve_face_reports("1", "mod", 7)
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            repos
+                .face_report
+                .count_unresolved_face_reports("1")
+                .await
+                .unwrap(),
+            0
+        );
+
+        // Step 6: the full collections of the archive, see `archive_service`
+        let face_reports = repos.face_report.get_all_face_reports().await.unwrap();
+        assert_eq!(face_reports.len(), 2);
+        assert!(face_reports.iter().all(|report| report.is_resolved == 1));
+        let season_snapshots = repos
+            .season_snapshot
+            .get_all_season_snapshots()
+            .await
+            .unwrap();
+        assert_eq!(season_snapshots.len(), 2);
+        assert_eq!(
+            repos
+                .season_snapshot
+                .delete_season_snapshots_by_season_ids(&["1".to_string()])
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            repos
+                .season_snapshot
+                .count_all_season_snapshots()
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            repos
+                .face_report
+                .delete_face_reports_by_ids(&["1".to_string(), "3".to_string()])
+                .await
+                .unwrap(),
+            2
+        );
+        repos
+            .face_report
+            .add_face_reports(face_reports)
+            .await
+            .unwrap();
+        assert_eq!(repos.face_report.count_all_face_reports().await.unwrap(), 2);
+        let category_ratings = repos
+            .category_rating
+            .get_all_category_ratings()
+            .await
+            .unwrap();
+        assert_eq!(
+            repos
+                .category_rating
+                .delete_category_ratings_by_face_info_ids(&["1".to_string()])
+                .await
+                .unwrap(),
+            category_ratings.len() as u64
+        );
+        repos
+            .category_rating
+            .add_category_ratings(category_ratings.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            repos
+                .category_rating
+                .count_all_category_ratings()
+                .await
+                .unwrap(),
+            category_ratings.len() as u64
+        );
+        assert_eq!(
+            repos
+                .season
+                .delete_seasons_by_ids(&["1".to_string()])
+                .await
+                .unwrap(),
+            1
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use sqlx::any::AnyRow;
+use sqlx::{AnyPool, Row};
+
+use crate::dao::sql::placeholders;
+use crate::dao::start_operation;
+use crate::entity::rating_log::RatingLog;
+use crate::entity::DELETED;
+use crate::repository::{RatingLogRepository, RepositoryResult};
+
+const COLUMNS: &str = "id, win_face_id, loss_face_id, category, season_id, creator, updater, \
+    created_on, updated_on, deleted_on, is_deleted";
+
+/// The rating_log repository backed by the "rating_log" table.
+pub struct SqlRatingLogDao {
+    pool: AnyPool,
+}
+
+impl SqlRatingLogDao {
+    pub fn new(pool: AnyPool) -> Self {
+        SqlRatingLogDao { pool }
+    }
+}
+
+fn rating_log_from_row(row: &AnyRow) -> Result<RatingLog, sqlx::Error> {
+    Ok(RatingLog {
+        id: row.try_get("id")?,
+        win_face_id: row.try_get("win_face_id")?,
+        loss_face_id: row.try_get("loss_face_id")?,
+        category: row.try_get("category")?,
+        season_id: row.try_get("season_id")?,
+        creator: row.try_get("creator")?,
+        updater: row.try_get("updater")?,
+        created_on: row.try_get("created_on")?,
+        updated_on: row.try_get("updated_on")?,
+        deleted_on: row.try_get("deleted_on")?,
+        is_deleted: row.try_get("is_deleted")?,
+    })
+}
+
+#[async_trait]
+impl RatingLogRepository for SqlRatingLogDao {
+    async fn add_rating_logs(&self, rating_logs: Vec<RatingLog>) -> RepositoryResult<()> {
+        let operation = start_operation("sql", "rating_log", "add_rating_logs");
+        operation.record("rating_log.count", rating_logs.len() as i64);
+        let sql = format!(
+            "INSERT INTO rating_log ({}) VALUES ({})",
+            COLUMNS,
+            placeholders(1, 11)
+        );
+        let mut tx = self.pool.begin().await?;
+        for rating_log in &rating_logs {
+            sqlx::query(&sql)
+                .bind(&rating_log.id)
+                .bind(&rating_log.win_face_id)
+                .bind(&rating_log.loss_face_id)
+                .bind(&rating_log.category)
+                .bind(&rating_log.season_id)
+                .bind(&rating_log.creator)
+                .bind(&rating_log.updater)
+                .bind(rating_log.created_on)
+                .bind(rating_log.updated_on)
+                .bind(rating_log.deleted_on)
+                .bind(rating_log.is_deleted)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_all_rating_logs(&self) -> RepositoryResult<Vec<RatingLog>> {
+        let _operation = start_operation("sql", "rating_log", "get_all_rating_logs");
+        let rows = sqlx::query(&format!("SELECT {} FROM rating_log", COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(rating_log_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn count_all_rating_logs(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "rating_log", "count_all_rating_logs");
+        let cnt: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM rating_log")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(cnt as u64)
+    }
+
+    async fn purge_rating_logs(
+        &self,
+        face_info_ids: &[String],
+        deleted_before: i64,
+    ) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "rating_log", "purge_rating_logs");
+        let mut sql =
+            "DELETE FROM rating_log WHERE (is_deleted = $1 AND deleted_on < $2)".to_string();
+        if !face_info_ids.is_empty() {
+            let ids = placeholders(3, face_info_ids.len());
+            sql.push_str(&format!(
+                " OR win_face_id IN ({0}) OR loss_face_id IN ({0})",
+                ids
+            ));
+        }
+
+        let mut query = sqlx::query(&sql).bind(DELETED).bind(deleted_before);
+        for face_info_id in face_info_ids {
+            query = query.bind(face_info_id);
+        }
+        Ok(query.execute(&self.pool).await?.rows_affected())
+    }
+
+    async fn delete_rating_logs_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "rating_log", "delete_rating_logs_by_ids");
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let sql = format!(
+            "DELETE FROM rating_log WHERE id IN ({})",
+            placeholders(1, ids.len())
+        );
+        let mut query = sqlx::query(&sql);
+        for id in ids {
+            query = query.bind(id);
+        }
+        Ok(query.execute(&self.pool).await?.rows_affected())
+    }
+}
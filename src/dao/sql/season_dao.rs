@@ -0,0 +1,127 @@
+use async_trait::async_trait;
+use sqlx::any::AnyRow;
+use sqlx::{AnyPool, Row};
+
+use crate::dao::sql::placeholders;
+use crate::dao::start_operation;
+use crate::entity::season::Season;
+use crate::repository::{RepositoryResult, SeasonRepository};
+
+const COLUMNS: &str = "id, name, soft_reset, creator, started_on, ended_on, reset_pending";
+
+/// The season repository backed by the "season" table.
+pub struct SqlSeasonDao {
+    pool: AnyPool,
+}
+
+impl SqlSeasonDao {
+    pub fn new(pool: AnyPool) -> Self {
+        SqlSeasonDao { pool }
+    }
+}
+
+fn season_from_row(row: &AnyRow) -> Result<Season, sqlx::Error> {
+    Ok(Season {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        soft_reset: row.try_get("soft_reset")?,
+        creator: row.try_get("creator")?,
+        started_on: row.try_get("started_on")?,
+        ended_on: row.try_get("ended_on")?,
+        reset_pending: row.try_get::<i64, _>("reset_pending")? != 0,
+    })
+}
+
+#[async_trait]
+impl SeasonRepository for SqlSeasonDao {
+    async fn add_season(&self, season: &Season) -> RepositoryResult<()> {
+        let operation = start_operation("sql", "season", "add_season");
+        operation.record("season.id", season.id.clone());
+        sqlx::query(&format!(
+            "INSERT INTO season ({}) VALUES ({})",
+            COLUMNS,
+            placeholders(1, 7)
+        ))
+        .bind(&season.id)
+        .bind(&season.name)
+        .bind(season.soft_reset)
+        .bind(&season.creator)
+        .bind(season.started_on)
+        .bind(season.ended_on)
+        .bind(season.reset_pending as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_season_by_id(&self, id: &str) -> RepositoryResult<Option<Season>> {
+        let operation = start_operation("sql", "season", "get_season_by_id");
+        operation.record("season.id", id.to_string());
+        let row = sqlx::query(&format!("SELECT {} FROM season WHERE id = $1", COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.as_ref().map(season_from_row).transpose()?)
+    }
+
+    async fn get_current_season(&self) -> RepositoryResult<Option<Season>> {
+        let _operation = start_operation("sql", "season", "get_current_season");
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM season WHERE ended_on = 0 LIMIT 1",
+            COLUMNS
+        ))
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.as_ref().map(season_from_row).transpose()?)
+    }
+
+    async fn get_seasons(&self) -> RepositoryResult<Vec<Season>> {
+        let _operation = start_operation("sql", "season", "get_seasons");
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM season ORDER BY started_on DESC, ended_on",
+            COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.iter().map(season_from_row).collect::<Result<_, _>>()?)
+    }
+
+    async fn end_season(&self, id: &str, now: i64) -> RepositoryResult<bool> {
+        let operation = start_operation("sql", "season", "end_season");
+        operation.record("season.id", id.to_string());
+        let res = sqlx::query("UPDATE season SET ended_on = $1 WHERE id = $2 AND ended_on = 0")
+            .bind(now)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn finish_season_reset(&self, id: &str) -> RepositoryResult<bool> {
+        let operation = start_operation("sql", "season", "finish_season_reset");
+        operation.record("season.id", id.to_string());
+        let res =
+            sqlx::query("UPDATE season SET reset_pending = 0 WHERE id = $1 AND reset_pending = 1")
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn delete_seasons_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "season", "delete_seasons_by_ids");
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let sql = format!(
+            "DELETE FROM season WHERE id IN ({})",
+            placeholders(1, ids.len())
+        );
+        let mut query = sqlx::query(&sql);
+        for id in ids {
+            query = query.bind(id);
+        }
+        Ok(query.execute(&self.pool).await?.rows_affected())
+    }
+}
@@ -0,0 +1,134 @@
+use async_trait::async_trait;
+use sqlx::any::AnyRow;
+use sqlx::{AnyPool, Row};
+
+use crate::dao::sql::{page_offset, placeholders};
+use crate::dao::start_operation;
+use crate::entity::season_snapshot::SeasonSnapshot;
+use crate::repository::{RepositoryResult, SeasonSnapshotRepository};
+
+const COLUMNS: &str =
+    "season_id, face_info_id, star_name, score, upvote_count, downvote_count, created_on";
+
+/// The season_snapshot repository backed by the "season_snapshot" table.
+pub struct SqlSeasonSnapshotDao {
+    pool: AnyPool,
+}
+
+impl SqlSeasonSnapshotDao {
+    pub fn new(pool: AnyPool) -> Self {
+        SqlSeasonSnapshotDao { pool }
+    }
+}
+
+fn season_snapshot_from_row(row: &AnyRow) -> Result<SeasonSnapshot, sqlx::Error> {
+    Ok(SeasonSnapshot {
+        season_id: row.try_get("season_id")?,
+        face_info_id: row.try_get("face_info_id")?,
+        star_name: row.try_get("star_name")?,
+        score: row.try_get("score")?,
+        upvote_count: row.try_get::<i64, _>("upvote_count")? as u64,
+        downvote_count: row.try_get::<i64, _>("downvote_count")? as u64,
+        created_on: row.try_get("created_on")?,
+    })
+}
+
+#[async_trait]
+impl SeasonSnapshotRepository for SqlSeasonSnapshotDao {
+    async fn save_season_snapshots(
+        &self,
+        season_snapshots: Vec<SeasonSnapshot>,
+    ) -> RepositoryResult<()> {
+        let operation = start_operation("sql", "season_snapshot", "save_season_snapshots");
+        operation.record("season_snapshot.count", season_snapshots.len() as i64);
+        let sql = format!(
+            "INSERT INTO season_snapshot ({}) VALUES ({}) \
+             ON CONFLICT (season_id, face_info_id) DO UPDATE SET star_name = $3, score = $4, \
+             upvote_count = $5, downvote_count = $6, created_on = $7",
+            COLUMNS,
+            placeholders(1, 7)
+        );
+        let mut tx = self.pool.begin().await?;
+        for season_snapshot in &season_snapshots {
+            sqlx::query(&sql)
+                .bind(&season_snapshot.season_id)
+                .bind(&season_snapshot.face_info_id)
+                .bind(&season_snapshot.star_name)
+                .bind(season_snapshot.score)
+                .bind(season_snapshot.upvote_count as i64)
+                .bind(season_snapshot.downvote_count as i64)
+                .bind(season_snapshot.created_on)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_season_snapshots_page(
+        &self,
+        season_id: &str,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<SeasonSnapshot>> {
+        let operation = start_operation("sql", "season_snapshot", "get_season_snapshots_page");
+        operation.record("season.id", season_id.to_string());
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM season_snapshot WHERE season_id = $1 \
+             ORDER BY score DESC, face_info_id LIMIT $2 OFFSET $3",
+            COLUMNS
+        ))
+        .bind(season_id)
+        .bind(page_size)
+        .bind(page_offset(page, page_size))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .iter()
+            .map(season_snapshot_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn get_all_season_snapshots(&self) -> RepositoryResult<Vec<SeasonSnapshot>> {
+        let _operation = start_operation("sql", "season_snapshot", "get_all_season_snapshots");
+        let rows = sqlx::query(&format!("SELECT {} FROM season_snapshot", COLUMNS))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(season_snapshot_from_row)
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn count_all_season_snapshots(&self) -> RepositoryResult<u64> {
+        let _operation = start_operation("sql", "season_snapshot", "count_all_season_snapshots");
+        let cnt: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM season_snapshot")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(cnt as u64)
+    }
+
+    async fn delete_season_snapshots_by_season_ids(
+        &self,
+        season_ids: &[String],
+    ) -> RepositoryResult<u64> {
+        let _operation = start_operation(
+            "sql",
+            "season_snapshot",
+            "delete_season_snapshots_by_season_ids",
+        );
+        if season_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let sql = format!(
+            "DELETE FROM season_snapshot WHERE season_id IN ({})",
+            placeholders(1, season_ids.len())
+        );
+        let mut query = sqlx::query(&sql);
+        for id in season_ids {
+            query = query.bind(id);
+        }
+        Ok(query.execute(&self.pool).await?.rows_affected())
+    }
+}
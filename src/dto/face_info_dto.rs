@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::dto::{validate_categories, ID_REGEX, MAX_STAR_NAME_LEN, MAX_USER_NAME_LEN};
+use crate::entity::face_info::{FaceInfo, FaceStatus};
+
+/// A face_info to add, it is pending until moderated.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NewFaceInfo {
+    #[validate(length(min = 1, max = MAX_STAR_NAME_LEN))]
+    pub star_name: String,
+    #[validate(regex(path = *ID_REGEX))]
+    pub file_id: String,
+    /// The arenas the face competes in, e.g. "actors"
+    #[serde(default)]
+    #[validate(custom(function = validate_categories))]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    #[validate(length(max = MAX_USER_NAME_LEN))]
+    pub creator: String,
+}
+
+impl NewFaceInfo {
+    pub fn into_face_info(mut self, id: String, score: f64, created_on: i64) -> FaceInfo {
+        self.categories.sort();
+        FaceInfo {
+            id,
+            star_name: self.star_name,
+            categories: self.categories,
+            file_id: self.file_id,
+            score,
+            status: FaceStatus::Pending,
+            creator: self.creator,
+            created_on,
+            ..FaceInfo::default()
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use crate::dto::{validate_uri, MAX_FILE_NAME_LEN, MAX_URI_LEN, MAX_USER_NAME_LEN};
+use crate::entity::file_resource::{FileResource, UriType};
+
+/// A file_resource to create, the uploaded files are created by the stream instead.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+#[validate(schema(function = validate_new_file_resource))]
+pub struct NewFileResource {
+    #[validate(length(min = 1, max = MAX_FILE_NAME_LEN))]
+    pub file_name: String,
+    #[validate(length(min = 1, max = MAX_URI_LEN))]
+    pub file_uri: String,
+    pub uri_type: UriType,
+    /// Optional, no thumbnail if empty
+    #[serde(default)]
+    #[validate(length(max = MAX_URI_LEN))]
+    pub thumb_uri: String,
+    #[serde(default = "default_uri_type")]
+    pub thumb_type: UriType,
+    #[serde(default)]
+    #[validate(length(max = MAX_USER_NAME_LEN))]
+    pub creator: String,
+}
+
+fn default_uri_type() -> UriType {
+    UriType::Local
+}
+
+/// The uris must match their types.
+fn validate_new_file_resource(req: &NewFileResource) -> Result<(), ValidationError> {
+    validate_uri(&req.file_uri, &req.uri_type).map_err(|mut err| {
+        err.add_param("field".into(), &"file_uri");
+        err
+    })?;
+    if !req.thumb_uri.is_empty() {
+        validate_uri(&req.thumb_uri, &req.thumb_type).map_err(|mut err| {
+            err.add_param("field".into(), &"thumb_uri");
+            err
+        })?;
+    }
+    Ok(())
+}
+
+impl NewFileResource {
+    pub fn into_file_resource(self, id: String, created_on: i64) -> FileResource {
+        FileResource {
+            id,
+            file_name: self.file_name,
+            file_uri: self.file_uri,
+            uri_type: self.uri_type,
+            thumb_uri: self.thumb_uri,
+            thumb_type: self.thumb_type,
+            creator: self.creator,
+            created_on,
+            ..FileResource::default()
+        }
+    }
+}
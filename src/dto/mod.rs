@@ -0,0 +1,114 @@
+//! The input DTOs of the handlers, separated from the entities so the clients
+//! can only set the fields they own, e.g. not the `score` of a face_info.
+//!
+//! The rules are declared by `validator`, the broken ones are responded as the
+//! `field_errors` of `error::ErrorResp`.
+
+use std::path::{Component, Path};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use validator::{ValidateUrl, ValidationError};
+
+use crate::config;
+use crate::entity::file_resource::UriType;
+
+pub mod face_info_dto;
+pub mod file_resource_dto;
+
+/// The max length of the names of the users, e.g. `creator` or `voter`
+pub const MAX_USER_NAME_LEN: u64 = 64;
+pub const MAX_STAR_NAME_LEN: u64 = 64;
+pub const MAX_FILE_NAME_LEN: u64 = 255;
+pub const MAX_URI_LEN: u64 = 2048;
+/// The max length of the free texts, e.g. the `comment` of a report
+pub const MAX_TEXT_LEN: u64 = 500;
+/// The max number of the categories of a face_info
+pub const MAX_CATEGORIES: usize = 8;
+
+lazy_static! {
+    /// The ids are generated by snowflake
+    pub static ref ID_REGEX: Regex = Regex::new(r"^[0-9]{1,20}$").unwrap();
+    /// The categories are slugs, e.g. "actors" or "f1-drivers"
+    pub static ref CATEGORY_REGEX: Regex = Regex::new(r"^[a-z0-9][a-z0-9_-]{0,31}$").unwrap();
+}
+
+/// The categories must be distinct slugs, at most `MAX_CATEGORIES` of them.
+pub fn validate_categories(categories: &[String]) -> Result<(), ValidationError> {
+    if categories.len() > MAX_CATEGORIES {
+        return Err(ValidationError::new("length")
+            .with_message(format!("must have at most {} categories!", MAX_CATEGORIES).into()));
+    }
+    if !categories
+        .iter()
+        .all(|category| CATEGORY_REGEX.is_match(category))
+    {
+        return Err(ValidationError::new("regex"));
+    }
+    let mut sorted = categories.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    if sorted.len() < categories.len() {
+        return Err(
+            ValidationError::new("distinct").with_message("must not repeat a category!".into())
+        );
+    }
+    Ok(())
+}
+
+/// The uri must be a http(s) url for `UriType::Url`, or a path in `storage.save_dir`
+/// for `UriType::Local`, since the local files are downloadable.
+pub fn validate_uri(uri: &str, uri_type: &UriType) -> Result<(), ValidationError> {
+    let is_valid = match uri_type {
+        UriType::Url => {
+            (uri.starts_with("http://") || uri.starts_with("https://")) && uri.validate_url()
+        }
+        UriType::Local => {
+            let path = Path::new(uri);
+            path.starts_with(&config::get().storage.save_dir)
+                && path
+                    .components()
+                    .all(|component| component != Component::ParentDir)
+        }
+    };
+    if is_valid {
+        return Ok(());
+    }
+    Err(match uri_type {
+        UriType::Url => ValidationError::new("url"),
+        UriType::Local => ValidationError::new("local_path")
+            .with_message("must be a path in the save directory!".into()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_uri() {
+        assert!(validate_uri("https://example.com/1.jpg", &UriType::Url).is_ok());
+        assert!(validate_uri("ftp://example.com/1.jpg", &UriType::Url).is_err());
+        assert!(validate_uri("example.com/1.jpg", &UriType::Url).is_err());
+
+        let save_dir = Path::new(&config::get().storage.save_dir).to_owned();
+        let uri = save_dir.join("1_a.jpg");
+        assert!(validate_uri(uri.to_str().unwrap(), &UriType::Local).is_ok());
+        let uri = save_dir.join("../../etc/passwd");
+        assert!(validate_uri(uri.to_str().unwrap(), &UriType::Local).is_err());
+        assert!(validate_uri("/etc/passwd", &UriType::Local).is_err());
+        assert!(validate_uri("", &UriType::Local).is_err());
+    }
+
+    #[test]
+    fn test_validate_categories() {
+        let categories =
+            |slugs: &[&str]| -> Vec<String> { slugs.iter().map(|slug| slug.to_string()).collect() };
+        assert!(validate_categories(&[]).is_ok());
+        assert!(validate_categories(&categories(&["actors", "f1-drivers"])).is_ok());
+        assert!(validate_categories(&categories(&["Actors"])).is_err());
+        assert!(validate_categories(&categories(&["-actors"])).is_err());
+        assert!(validate_categories(&categories(&["actors", "actors"])).is_err());
+        assert!(validate_categories(&categories(&["a"; MAX_CATEGORIES + 1])).is_err());
+    }
+}
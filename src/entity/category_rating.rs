@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::entity::face_info::DEFAULT_SCORE;
+
+/// The rating of a face_info within one of its categories, apart from its overall score.
+///
+/// It's added on the first vote in the category, one per category and face_info.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct CategoryRating {
+    pub category: String,
+    pub face_info_id: String,
+    pub score: f64,
+    pub upvote_count: u64,
+    pub downvote_count: u64,
+    pub created_on: i64,
+    pub updated_on: i64,
+}
+
+impl Default for CategoryRating {
+    fn default() -> Self {
+        CategoryRating {
+            category: "".to_string(),
+            face_info_id: "".to_string(),
+            score: DEFAULT_SCORE,
+            upvote_count: 0,
+            downvote_count: 0,
+            created_on: 0,
+            updated_on: 0,
+        }
+    }
+}
+
+impl CategoryRating {
+    pub fn db_name() -> &'static str {
+        "facemash"
+    }
+
+    pub fn coll_name() -> &'static str {
+        "category_rating"
+    }
+}
@@ -1,16 +1,49 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 pub const DEFAULT_SCORE: f64 = 1400.0;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The moderation status of a face_info, only the approved ones are shown to voters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FaceStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl FaceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FaceStatus::Pending => "pending",
+            FaceStatus::Approved => "approved",
+            FaceStatus::Rejected => "rejected",
+        }
+    }
+}
+
+/// The orders of the face_info listings, the highest comes first and the ties by id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaceInfoOrder {
+    Score,
+    CreatedOn,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(default)]
 pub struct FaceInfo {
     pub id: String,
     pub star_name: String,
+    /// The arenas the face_info competes in, e.g. "actors", sorted
+    pub categories: Vec<String>,
     pub file_id: String,
     pub upvote_count: u64,
     pub downvote_count: u64,
     pub score: f64,
+    pub status: FaceStatus,
+    pub moderator: String,
+    pub moderation_reason: String,
+    pub moderated_on: i64,
     pub creator: String,
     pub updater: String,
     pub created_on: i64,
@@ -25,9 +58,14 @@ impl Default for FaceInfo {
             id: "".to_string(),
             file_id: "".to_string(),
             star_name: "".to_string(),
+            categories: vec![],
             upvote_count: 0,
             downvote_count: 0,
             score: DEFAULT_SCORE,
+            status: FaceStatus::Pending,
+            moderator: "".to_string(),
+            moderation_reason: "".to_string(),
+            moderated_on: 0,
             creator: "".to_string(),
             updater: "".to_string(),
             created_on: 0,
@@ -47,3 +85,11 @@ impl FaceInfo {
         "face_info"
     }
 }
+
+/// The editable fields of a face_info, `None` keeps the field unchanged.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FaceInfoPatch {
+    pub star_name: Option<String>,
+    pub file_id: Option<String>,
+    pub categories: Option<Vec<String>>,
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// One changed field of a face_info, written on every face_info update.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct FaceInfoAudit {
+    pub id: String,
+    pub face_info_id: String,
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub creator: String,
+    pub updater: String,
+    pub created_on: i64,
+    pub updated_on: i64,
+    pub deleted_on: i64,
+    pub is_deleted: i64,
+}
+
+impl Default for FaceInfoAudit {
+    fn default() -> Self {
+        FaceInfoAudit {
+            id: "".to_string(),
+            face_info_id: "".to_string(),
+            field: "".to_string(),
+            old_value: "".to_string(),
+            new_value: "".to_string(),
+            creator: "".to_string(),
+            updater: "".to_string(),
+            created_on: 0,
+            updated_on: 0,
+            deleted_on: 0,
+            is_deleted: 0,
+        }
+    }
+}
+
+impl FaceInfoAudit {
+    pub fn db_name() -> &'static str {
+        "facemash"
+    }
+
+    pub fn coll_name() -> &'static str {
+        "face_info_audit"
+    }
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportReason {
+    Inappropriate,
+    Duplicate,
+    Mislabeled,
+    Other,
+}
+
+/// A voter's report of a face_info, one unresolved report per reporter and face_info.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FaceReport {
+    pub id: String,
+    pub face_info_id: String,
+    pub reporter: String,
+    pub reason: ReportReason,
+    pub comment: String,
+    pub is_resolved: i64,
+    pub resolved_on: i64,
+    pub creator: String,
+    pub updater: String,
+    pub created_on: i64,
+    pub updated_on: i64,
+    pub deleted_on: i64,
+    pub is_deleted: i64,
+}
+
+impl Default for FaceReport {
+    fn default() -> Self {
+        FaceReport {
+            id: "".to_string(),
+            face_info_id: "".to_string(),
+            reporter: "".to_string(),
+            reason: ReportReason::Other,
+            comment: "".to_string(),
+            is_resolved: 0,
+            resolved_on: 0,
+            creator: "".to_string(),
+            updater: "".to_string(),
+            created_on: 0,
+            updated_on: 0,
+            deleted_on: 0,
+            is_deleted: 0,
+        }
+    }
+}
+
+impl FaceReport {
+    pub fn db_name() -> &'static str {
+        "facemash"
+    }
+
+    pub fn coll_name() -> &'static str {
+        "face_report"
+    }
+}
+
+/// The number of reports of a reason.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReportReasonCnt {
+    pub reason: ReportReason,
+    pub cnt: i64,
+}
+
+/// The unresolved reports of a face_info, aggregated.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FaceReportSummary {
+    pub face_info_id: String,
+    pub report_cnt: i64,
+    pub reason_cnts: Vec<ReportReasonCnt>,
+    pub last_reported_on: i64,
+}
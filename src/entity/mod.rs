@@ -1,3 +1,13 @@
+pub mod category_rating;
 pub mod face_info;
+pub mod face_info_audit;
+pub mod face_report;
 pub mod file_resource;
 pub mod rating_log;
+pub mod season;
+pub mod season_snapshot;
+
+/// The `is_deleted` value of a live document
+pub const NOT_DELETED: i64 = 0;
+/// The `is_deleted` value of a soft deleted document
+pub const DELETED: i64 = 1;
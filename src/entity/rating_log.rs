@@ -6,6 +6,10 @@ pub struct RatingLog {
     pub id: String,
     pub win_face_id: String,
     pub loss_face_id: String,
+    /// The category the vote was cast in, empty for the overall pool
+    pub category: String,
+    /// The season the vote was cast in, empty before the first season
+    pub season_id: String,
     pub creator: String,
     pub updater: String,
     pub created_on: i64,
@@ -20,6 +24,8 @@ impl Default for RatingLog {
             id: "".to_string(),
             win_face_id: "".to_string(),
             loss_face_id: "".to_string(),
+            category: "".to_string(),
+            season_id: "".to_string(),
             creator: "".to_string(),
             updater: "".to_string(),
             created_on: 0,
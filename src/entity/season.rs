@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A season of the ratings, the votes cast in it are tagged with its id.
+///
+/// Only one season is current at a time, it lasts until the next one starts.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct Season {
+    pub id: String,
+    pub name: String,
+    /// The share of the distance to the initial score taken off the scores at the start,
+    /// from 0.0 for none to 1.0 for a full reset
+    pub soft_reset: f64,
+    pub creator: String,
+    pub started_on: i64,
+    /// 0 while it is the current season
+    pub ended_on: i64,
+    /// True from the start until the soft reset of the scores has been applied,
+    /// a start stopped before that is resumed by the next one
+    pub reset_pending: bool,
+}
+
+impl Default for Season {
+    fn default() -> Self {
+        Season {
+            id: "".to_string(),
+            name: "".to_string(),
+            soft_reset: 0.0,
+            creator: "".to_string(),
+            started_on: 0,
+            ended_on: 0,
+            reset_pending: false,
+        }
+    }
+}
+
+impl Season {
+    pub fn db_name() -> &'static str {
+        "facemash"
+    }
+
+    pub fn coll_name() -> &'static str {
+        "season"
+    }
+}
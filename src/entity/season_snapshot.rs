@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::entity::face_info::{FaceInfo, DEFAULT_SCORE};
+
+/// The rating of a face_info at the end of a season, archived when the next one starts.
+///
+/// The standings before the first season are archived with an empty `season_id`.
+/// There is one per season and face_info, taking it again replaces it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct SeasonSnapshot {
+    pub season_id: String,
+    pub face_info_id: String,
+    pub star_name: String,
+    pub score: f64,
+    pub upvote_count: u64,
+    pub downvote_count: u64,
+    pub created_on: i64,
+}
+
+impl Default for SeasonSnapshot {
+    fn default() -> Self {
+        SeasonSnapshot {
+            season_id: "".to_string(),
+            face_info_id: "".to_string(),
+            star_name: "".to_string(),
+            score: DEFAULT_SCORE,
+            upvote_count: 0,
+            downvote_count: 0,
+            created_on: 0,
+        }
+    }
+}
+
+impl SeasonSnapshot {
+    pub fn db_name() -> &'static str {
+        "facemash"
+    }
+
+    pub fn coll_name() -> &'static str {
+        "season_snapshot"
+    }
+
+    /// Takes the current rating of the face_info.
+    pub fn of(season_id: &str, face_info: &FaceInfo, now: i64) -> Self {
+        SeasonSnapshot {
+            season_id: season_id.to_string(),
+            face_info_id: face_info.id.clone(),
+            star_name: face_info.star_name.clone(),
+            score: face_info.score,
+            upvote_count: face_info.upvote_count,
+            downvote_count: face_info.downvote_count,
+            created_on: now,
+        }
+    }
+}
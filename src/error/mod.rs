@@ -0,0 +1,337 @@
+//! The errors of the handlers, responded in a consistent json envelope:
+//! `{"code": "not_found", "message": "FaceInfo not found!", "request_id": "..."}`.
+//!
+//! The invalid fields of a request are listed one by one in `field_errors`.
+//! The details of the server errors are only logged, their message is generic.
+
+use std::{fmt, io};
+
+use actix_multipart::MultipartError;
+use actix_web::error::{BlockingError, JsonPayloadError, QueryPayloadError};
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::logger::request_id;
+use crate::repository::RepositoryError;
+use crate::service::file_resource_service::SaveFileResourceError;
+
+#[derive(Debug)]
+pub enum AppError {
+    /// The request can't be read, e.g. a malformed json body
+    BadRequest(String),
+    /// The request is invalid as a whole, e.g. it changes nothing
+    Validation(String),
+    /// Some fields of the request are invalid, e.g. a required one is empty
+    InvalidFields(Vec<FieldError>),
+    /// The credential is missing or wrong
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    /// The request conflicts with the current state, e.g. a concurrent change
+    Conflict(String),
+    PayloadTooLarge(String),
+    ServiceUnavailable(String),
+    Repository(RepositoryError),
+    Io(io::Error),
+    Internal(String),
+}
+
+/// The body of the error responses.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorResp {
+    /// Machine readable, e.g. `validation_error`
+    pub code: String,
+    pub message: String,
+    pub request_id: Option<String>,
+    /// The invalid fields, only for the `validation_error`s
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub field_errors: Vec<FieldError>,
+}
+
+/// An invalid field of the request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FieldError {
+    /// The path of the field, e.g. `face_info.star_name`
+    pub field: String,
+    /// The rule broken, e.g. `length`
+    pub code: String,
+    pub message: String,
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn invalid_field(field: &str, code: &str, message: &str) -> Self {
+        AppError::InvalidFields(vec![FieldError {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.to_string(),
+        }])
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Validation(_) | AppError::InvalidFields(_) => "validation_error",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::PayloadTooLarge(_) => "payload_too_large",
+            AppError::ServiceUnavailable(_) => "service_unavailable",
+            AppError::Repository(_) => "database_error",
+            AppError::Io(_) => "io_error",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    /// The message responded, the server errors don't expose their details.
+    pub fn message(&self) -> String {
+        match self {
+            AppError::BadRequest(msg)
+            | AppError::Validation(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::NotFound(msg)
+            | AppError::Conflict(msg)
+            | AppError::PayloadTooLarge(msg)
+            | AppError::ServiceUnavailable(msg) => msg.clone(),
+            AppError::InvalidFields(field_errors) => format!(
+                "Invalid fields: {}",
+                field_errors
+                    .iter()
+                    .map(|field_error| field_error.field.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            AppError::Repository(_) | AppError::Io(_) | AppError::Internal(_) => {
+                "Internal server error!".to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Repository(err) => write!(f, "{}", err),
+            AppError::Io(err) => write!(f, "io error: {}", err),
+            AppError::Internal(msg) => write!(f, "internal error: {}", msg),
+            _ => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) | AppError::Validation(_) | AppError::InvalidFields(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Repository(_) | AppError::Io(_) | AppError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        if status.is_server_error() {
+            error!("Error: {:?}", self);
+        } else {
+            info!("Request failed, {}: {}", self.code(), self);
+        }
+        HttpResponse::build(status).json(ErrorResp {
+            code: self.code().to_string(),
+            message: self.message(),
+            request_id: request_id::current_request_id(),
+            field_errors: match self {
+                AppError::InvalidFields(field_errors) => field_errors.clone(),
+                _ => vec![],
+            },
+        })
+    }
+}
+
+impl From<RepositoryError> for AppError {
+    fn from(err: RepositoryError) -> Self {
+        AppError::Repository(err)
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut field_errors = vec![];
+        collect_field_errors("", &errors, &mut field_errors);
+        field_errors.sort_by(|a, b| a.field.cmp(&b.field));
+        AppError::InvalidFields(field_errors)
+    }
+}
+
+/// Flattens the errors of the nested structs and lists into dotted field paths.
+fn collect_field_errors(prefix: &str, errors: &ValidationErrors, out: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = match (prefix, field.as_ref()) {
+            // The errors of the struct level rules name their field in the params
+            (_, "__all__") => prefix.to_string(),
+            ("", field) => field.to_string(),
+            (prefix, field) => format!("{}.{}", prefix, field),
+        };
+        match kind {
+            ValidationErrorsKind::Field(field_errors) => {
+                for err in field_errors {
+                    let path = match err.params.get("field").and_then(|field| field.as_str()) {
+                        Some(field) if path.is_empty() => field.to_string(),
+                        Some(field) => format!("{}.{}", path, field),
+                        None => path.clone(),
+                    };
+                    out.push(FieldError {
+                        message: describe(&path, err),
+                        field: path,
+                        code: err.code.to_string(),
+                    });
+                }
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(&path, errors, out),
+            ValidationErrorsKind::List(errors) => {
+                for (index, errors) in errors {
+                    collect_field_errors(&format!("{}[{}]", path, index), errors, out);
+                }
+            }
+        }
+    }
+}
+
+/// The message of the rule, unless given by the rule itself.
+fn describe(field: &str, err: &ValidationError) -> String {
+    if let Some(message) = &err.message {
+        return format!("{} {}", field, message);
+    }
+    let param = |name: &str| err.params.get(name).map(|value| value.to_string());
+    match (err.code.as_ref(), param("min"), param("max")) {
+        ("length", Some(min), Some(max)) if min == "1" => {
+            format!("{} is required, at most {} characters!", field, max)
+        }
+        ("length", Some(min), Some(max)) => {
+            format!("{} must be {} to {} characters!", field, min, max)
+        }
+        ("length", Some(_), None) => format!("{} is required!", field),
+        ("length", None, Some(max)) => format!("{} must be at most {} characters!", field, max),
+        ("range", Some(min), _) => format!("{} must be at least {}!", field, min),
+        ("regex", _, _) => format!("{} is malformed!", field),
+        ("url", _, _) => format!("{} must be a http(s) url!", field),
+        (code, _, _) => format!("{} is invalid: {}!", field, code),
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> Self {
+        AppError::Io(err)
+    }
+}
+
+impl From<BlockingError> for AppError {
+    fn from(err: BlockingError) -> Self {
+        AppError::Internal(err.to_string())
+    }
+}
+
+impl From<MultipartError> for AppError {
+    fn from(err: MultipartError) -> Self {
+        AppError::BadRequest(format!("Invalid multipart payload: {}", err))
+    }
+}
+
+impl From<SaveFileResourceError> for AppError {
+    fn from(err: SaveFileResourceError) -> Self {
+        match err {
+            SaveFileResourceError::Duplicated { file_id } => {
+                AppError::Forbidden(format!("File has already been saved, file_id: {}", file_id))
+            }
+            SaveFileResourceError::Io(err) => AppError::Io(err),
+            SaveFileResourceError::Repository(err) => AppError::Repository(err),
+        }
+    }
+}
+
+/// Responds the unreadable json bodies in the envelope, see `web::JsonConfig`.
+pub fn handle_json_error(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    match err {
+        JsonPayloadError::OverflowKnownLength { .. } | JsonPayloadError::Overflow { .. } => {
+            AppError::PayloadTooLarge(err.to_string()).into()
+        }
+        _ => AppError::BadRequest(format!("Invalid json body: {}", err)).into(),
+    }
+}
+
+/// Responds the unreadable query strings in the envelope, see `web::QueryConfig`.
+pub fn handle_query_error(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    AppError::BadRequest(format!("Invalid query string: {}", err)).into()
+}
+
+/// Responds the requests matching no route, see `web::ServiceConfig::default_service`.
+pub async fn handle_not_found() -> AppResult<HttpResponse> {
+    Err(AppError::NotFound("Route not found!".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App};
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_error_response() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().error_handler(handle_json_error))
+                .route(
+                    "/",
+                    web::post().to(|_: web::Json<serde_json::Value>| async {
+                        Err::<HttpResponse, _>(AppError::Repository(
+                            sqlx::Error::PoolTimedOut.into(),
+                        ))
+                    }),
+                )
+                .default_service(web::to(handle_not_found)),
+        )
+        .await;
+
+        // The server errors don't expose their details
+        let req = test::TestRequest::post()
+            .uri("/")
+            .set_json(serde_json::json!({}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body: ErrorResp = test::read_body_json(resp).await;
+        assert_eq!(body.code, "database_error");
+        assert_eq!(body.message, "Internal server error!");
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("content-type", "application/json"))
+            .set_payload("{")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: ErrorResp = test::read_body_json(resp).await;
+        assert_eq!(body.code, "bad_request");
+
+        let req = test::TestRequest::get().uri("/no_such_route").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body: ErrorResp = test::read_body_json(resp).await;
+        assert_eq!(body.code, "not_found");
+        assert_eq!(body.request_id, None);
+    }
+}
@@ -0,0 +1,227 @@
+//! The log file, rotated by size and time.
+//!
+//! A rotated file is renamed to `<path>.<%Y%m%d-%H%M%S%.3f>`, gzipped if configured,
+//! and the oldest rotated files beyond the retention are removed.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::config::settings::{LogFileSettings, LogRotation};
+
+pub struct RollingFile {
+    settings: LogFileSettings,
+    path: PathBuf,
+    file: File,
+    size: u64,
+    /// The rotation period the file was written in, None if never rotated by time
+    period: Option<String>,
+    /// Compresses and removes the rotated files off the logging thread
+    cleanup: Option<JoinHandle<()>>,
+}
+
+impl RollingFile {
+    /// Opens the file for appending, the directories are created if missing.
+    pub fn open(settings: &LogFileSettings) -> io::Result<RollingFile> {
+        let path = PathBuf::from(&settings.path);
+        if let Some(dir) = path.parent() {
+            if !dir.as_os_str().is_empty() {
+                fs::create_dir_all(dir)?;
+            }
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+
+        // A file left by the last run is rotated once its period is over
+        let written_on = match metadata.modified() {
+            Ok(modified) if metadata.len() > 0 => DateTime::<Utc>::from(modified),
+            _ => Utc::now(),
+        };
+        Ok(RollingFile {
+            settings: settings.clone(),
+            path,
+            file,
+            size: metadata.len(),
+            period: period_of(settings.rotation, written_on),
+            cleanup: None,
+        })
+    }
+
+    pub fn settings(&self) -> &LogFileSettings {
+        &self.settings
+    }
+
+    /// Appends the line, the file is rotated first if the line would exceed
+    /// `max_size` or the rotation period is over.
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let now = Utc::now();
+        let len = line.len() as u64 + 1;
+        let size_exceeded =
+            self.settings.max_size > 0 && self.size > 0 && self.size + len > self.settings.max_size;
+        if size_exceeded || period_of(self.settings.rotation, now) != self.period {
+            self.rotate(now)?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    fn rotate(&mut self, now: DateTime<Utc>) -> io::Result<()> {
+        self.file.flush()?;
+        // The cleanups never run concurrently
+        self.join_cleanup();
+
+        let rotated_path = get_rotated_path(&self.path, now);
+        fs::rename(&self.path, &rotated_path)?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        self.period = period_of(self.settings.rotation, now);
+
+        // Can't log from the logger, so the errors go to stderr
+        let path = self.path.clone();
+        let settings = self.settings.clone();
+        self.cleanup = Some(thread::spawn(move || {
+            if settings.compress {
+                if let Err(err) = compress(&rotated_path) {
+                    eprintln!("Failed to compress {:?}: {}", rotated_path, err);
+                }
+            }
+            if let Err(err) = remove_expired(&path, settings.retention) {
+                eprintln!("Failed to remove the expired logs of {:?}: {}", path, err);
+            }
+        }));
+        Ok(())
+    }
+
+    /// Waits for the compression and removal of the last rotation.
+    pub fn join_cleanup(&mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            let _ = cleanup.join();
+        }
+    }
+}
+
+fn period_of(rotation: LogRotation, time: DateTime<Utc>) -> Option<String> {
+    match rotation {
+        LogRotation::Never => None,
+        LogRotation::Hourly => Some(time.format("%Y%m%d%H").to_string()),
+        LogRotation::Daily => Some(time.format("%Y%m%d").to_string()),
+    }
+}
+
+/// `<path>.<%Y%m%d-%H%M%S%.3f>`, with a counter appended if rotated twice in a millisecond,
+/// so the names sort in the rotated order.
+fn get_rotated_path(path: &Path, now: DateTime<Utc>) -> PathBuf {
+    let rotated_path = format!("{}.{}", path.display(), now.format("%Y%m%d-%H%M%S%.3f"));
+    let is_free = |rotated_path: &str| {
+        !Path::new(rotated_path).exists() && !Path::new(&format!("{}.gz", rotated_path)).exists()
+    };
+    if is_free(&rotated_path) {
+        return PathBuf::from(rotated_path);
+    }
+    (1..)
+        .map(|i| format!("{}_{:03}", rotated_path, i))
+        .find(|rotated_path| is_free(rotated_path))
+        .map(PathBuf::from)
+        .unwrap()
+}
+
+/// Gzips the file into `<path>.gz` and removes it.
+fn compress(path: &Path) -> io::Result<()> {
+    let mut input = File::open(path)?;
+    let gz_path = PathBuf::from(format!("{}.gz", path.display()));
+    let mut encoder = GzEncoder::new(File::create(&gz_path)?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    fs::remove_file(path)
+}
+
+/// Removes the oldest rotated files of the log file beyond the retention.
+fn remove_expired(path: &Path, retention: usize) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let prefix = match path.file_name() {
+        Some(file_name) => format!("{}.", file_name.to_string_lossy()),
+        None => return Ok(()),
+    };
+
+    // The timestamps in the names sort the rotated files from the oldest
+    let mut rotated_paths = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            rotated_paths.push(entry.path());
+        }
+    }
+    rotated_paths.sort();
+
+    let expired_cnt = rotated_paths.len().saturating_sub(retention);
+    for rotated_path in &rotated_paths[..expired_cnt] {
+        fs::remove_file(rotated_path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    #[test]
+    fn test_rolling_file() {
+        let dir = env::temp_dir().join(format!("facemash-log-{}", uuid::Uuid::new_v4()));
+        let settings = LogFileSettings {
+            path: dir.join("facemash.log").to_string_lossy().to_string(),
+            max_size: 16,
+            rotation: LogRotation::Never,
+            retention: 2,
+            compress: true,
+        };
+        let mut file = RollingFile::open(&settings).unwrap();
+
+        // Every line fills the max size, so each one rotates the last one
+        for i in 0..4 {
+            file.write_line(&format!("line {:010}", i)).unwrap();
+            file.join_cleanup();
+        }
+
+        let mut rotated_paths = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "gz"))
+            .collect::<Vec<_>>();
+        rotated_paths.sort();
+        assert_eq!(rotated_paths.len(), 2);
+
+        let mut content = String::new();
+        GzDecoder::new(File::open(&rotated_paths[1]).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "line 0000000002\n");
+        assert_eq!(
+            fs::read_to_string(&settings.path).unwrap(),
+            "line 0000000003\n"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
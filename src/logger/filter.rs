@@ -0,0 +1,119 @@
+//! The `RUST_LOG` style filter of the logs, e.g. `info,facemash_backend::controller=debug,mongodb=warn`.
+//!
+//! A directive is either a level, which is the default of the targets not matched,
+//! or `<module>=<level>`, where the longest matched module wins. A bare `<module>` means TRACE.
+
+use log::LevelFilter;
+
+use crate::config::settings::parse_log_level;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    /// The modules and their levels, the longest module comes first
+    directives: Vec<(String, LevelFilter)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        LogFilter {
+            default: LevelFilter::Info,
+            directives: vec![],
+        }
+    }
+}
+
+impl LogFilter {
+    /// Parses the comma separated directives, the default level is INFO if not given.
+    pub fn parse(directives: &str) -> Result<LogFilter, String> {
+        let mut filter = LogFilter::default();
+        for directive in directives.split(',').map(str::trim) {
+            if directive.is_empty() {
+                continue;
+            }
+            match directive.split_once('=') {
+                None => match parse_log_level(directive) {
+                    Some(level) => filter.default = level,
+                    None => filter
+                        .directives
+                        .push((directive.to_string(), LevelFilter::Trace)),
+                },
+                Some((module, level)) => {
+                    let module = module.trim();
+                    let level = parse_log_level(level.trim())
+                        .ok_or_else(|| format!("invalid level in directive {:?}", directive))?;
+                    if module.is_empty() {
+                        return Err(format!("missing module in directive {:?}", directive));
+                    }
+                    filter.directives.push((module.to_string(), level));
+                }
+            }
+        }
+        // The later directive of the same module wins, like the later flags
+        filter.directives.reverse();
+        filter
+            .directives
+            .sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        filter.directives.dedup_by(|a, b| a.0 == b.0);
+        Ok(filter)
+    }
+
+    /// The level of the logs of the target, e.g. `facemash_backend::controller::face_info_controller`.
+    pub fn level_of(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .find(|(module, _)| {
+                target
+                    .strip_prefix(module.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    /// The most verbose level of all, anything above it is skipped by the `log` macros.
+    pub fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_filter() {
+        let filter = LogFilter::parse(
+            "warn, facemash_backend=info,facemash_backend::controller=debug,mongodb=error,mongodb=off",
+        )
+        .unwrap();
+        assert_eq!(filter.level_of("sqlx::query"), LevelFilter::Warn);
+        assert_eq!(filter.level_of("facemash_backend::dao"), LevelFilter::Info);
+        assert_eq!(
+            filter.level_of("facemash_backend::controller::face_info_controller"),
+            LevelFilter::Debug
+        );
+        assert_eq!(
+            filter.level_of("facemash_backend::controllers"),
+            LevelFilter::Info
+        );
+        assert_eq!(filter.level_of("mongodb::cmap"), LevelFilter::Off);
+        assert_eq!(filter.max_level(), LevelFilter::Debug);
+
+        assert_eq!(
+            LogFilter::parse("DEBUG").unwrap().max_level(),
+            LevelFilter::Debug
+        );
+        assert_eq!(
+            LogFilter::parse("actix_web")
+                .unwrap()
+                .level_of("actix_web::middleware"),
+            LevelFilter::Trace
+        );
+        assert!(LogFilter::parse("mongodb=loud").is_err());
+        assert!(LogFilter::parse("=debug").is_err());
+    }
+}
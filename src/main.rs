@@ -1,43 +1,114 @@
 #[macro_use]
 extern crate log;
 
-use actix_web::{middleware, App, HttpServer};
+use std::process;
+
+use actix_web::middleware::{from_fn, Logger};
+use actix_web::{web, App, HttpServer};
+use clap::Parser;
 use dotenv::dotenv;
 use mongodb::bson::doc;
 
-use crate::controller::{face_info_controller, file_controller};
+use crate::cli::{Cli, Command};
+use crate::config::settings::Settings;
+use crate::logger::request_id::request_id_middleware;
+use crate::metrics::metrics_middleware;
+use crate::repository::Repositories;
 use crate::resource::mongo;
+use crate::service::import_service::ImportOptions;
+use crate::telemetry::tracing_middleware;
 
 mod algorithm;
+mod cli;
 mod config;
 mod controller;
 mod dao;
+mod dto;
 mod entity;
+mod error;
 mod logger;
+mod metrics;
+mod repository;
 mod resource;
 mod service;
+mod shutdown;
+mod telemetry;
 mod utils;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
+    let cli = Cli::parse();
+    let settings = match Settings::load(&cli.settings) {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    };
+    config::init(settings, cli.settings.clone());
     logger::init();
+    telemetry::init();
 
     resource::check_resources().await;
-    service::init_file_service().await;
+    // The dependencies are retried with backoff, the process only exits once they run out
+    if let Err(err) = service::init_file_service().await {
+        error!("Failed to create the file storage, error: {}", err);
+        telemetry::shutdown();
+        process::exit(1);
+    }
+    let repos = match repository::init_repositories().await {
+        Ok(repos) => repos,
+        Err(err) => {
+            error!("Failed to connect the storage, error: {}", err);
+            telemetry::shutdown();
+            process::exit(1);
+        }
+    };
+    let res = match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve(repos).await,
+        Command::Import {
+            dir,
+            manifest,
+            approve,
+            creator,
+        } => cli::import(&repos, dir, manifest, ImportOptions { creator, approve }).await,
+        Command::ExportArchive { output } => cli::export_archive(&repos, output).await,
+        Command::ImportArchive { input } => cli::import_archive(&repos, input).await,
+    };
+    telemetry::shutdown();
+    res
+}
 
-    HttpServer::new(|| {
+async fn serve(repos: Repositories) -> std::io::Result<()> {
+    let settings = config::get();
+    #[cfg(unix)]
+    shutdown::spawn_background_job(
+        "reload_settings_on_sighup",
+        service::admin_service::reload_settings_on_sighup(),
+    );
+    let repos = web::Data::new(repos);
+    let server = HttpServer::new(move || {
         App::new()
-            .wrap(middleware::Logger::default())
-            .service(face_info_controller::get_face_info_randomly)
-            .service(face_info_controller::get_face_info_by_id)
-            .service(face_info_controller::add_face_info)
-            .service(face_info_controller::vote_face_info)
-            .service(file_controller::create_file_resource_by_stream)
-            .service(file_controller::create_file_resource)
-            .service(file_controller::download_local_file)
+            .wrap(from_fn(tracing_middleware))
+            .wrap(from_fn(metrics_middleware))
+            .wrap(from_fn(request_id_middleware))
+            // The default format with the request id appended
+            .wrap(Logger::new(
+                r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T %{x-request-id}o"#,
+            ))
+            .app_data(repos.clone())
+            .configure(controller::configure)
     })
-    .bind(("0.0.0.0", 8080))?
-    .run()
-    .await
+    .bind((settings.server.host.as_str(), settings.server.port))?
+    // The signals are handled by the shutdown, which drains the writes first
+    .disable_signals()
+    .shutdown_timeout(settings.server.shutdown_timeout_secs)
+    .run();
+
+    let shutdown = actix_rt::spawn(shutdown::shutdown_on_signal(server.handle()));
+    server.await?;
+    // The server only stops on the shutdown, which cleans up after it
+    let _ = shutdown.await;
+    Ok(())
 }
@@ -0,0 +1,95 @@
+//! The prometheus metrics, exposed by `GET /metrics`.
+//!
+//! The counters only grow, e.g. the votes per minute are `rate(facemash_votes_total[1m]) * 60`.
+
+use std::time::Instant;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::Error;
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge_vec,
+    HistogramTimer, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec,
+};
+
+lazy_static! {
+    pub static ref HTTP_REQUESTS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "facemash_http_requests_total",
+        "The http requests handled, by route and status",
+        &["method", "route", "status"]
+    )
+    .unwrap();
+    pub static ref HTTP_REQUEST_DURATION_SECONDS: HistogramVec = register_histogram_vec!(
+        "facemash_http_request_duration_seconds",
+        "The latency of the http requests, by route",
+        &["method", "route"]
+    )
+    .unwrap();
+    pub static ref VOTES_TOTAL: IntCounter =
+        register_int_counter!("facemash_votes_total", "The votes counted").unwrap();
+    pub static ref UPLOADS_TOTAL: IntCounter = register_int_counter!(
+        "facemash_uploads_total",
+        "The files stored, the duplicated ones excluded"
+    )
+    .unwrap();
+    pub static ref UPLOADED_BYTES_TOTAL: IntCounter = register_int_counter!(
+        "facemash_uploaded_bytes_total",
+        "The bytes of the files stored"
+    )
+    .unwrap();
+    pub static ref DUPLICATE_UPLOADS_TOTAL: IntCounter = register_int_counter!(
+        "facemash_duplicate_uploads_total",
+        "The files rejected since a file with the same md5 has been stored"
+    )
+    .unwrap();
+    pub static ref REPOSITORY_OPERATION_DURATION_SECONDS: HistogramVec = register_histogram_vec!(
+        "facemash_repository_operation_duration_seconds",
+        "The latency of the repository operations, by storage backend and dao function",
+        &["backend", "repository", "operation"],
+        vec![0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5]
+    )
+    .unwrap();
+    pub static ref FACE_INFOS: IntGaugeVec = register_int_gauge_vec!(
+        "facemash_face_infos",
+        "The face_infos not deleted, by moderation status, refreshed on scrape",
+        &["status"]
+    )
+    .unwrap();
+}
+
+/// Times a repository operation until the returned timer is dropped.
+pub fn start_repository_timer(backend: &str, repository: &str, operation: &str) -> HistogramTimer {
+    REPOSITORY_OPERATION_DURATION_SECONDS
+        .with_label_values(&[backend, repository, operation])
+        .start_timer()
+}
+
+/// The middleware counting and timing the requests by their route pattern,
+/// see `actix_web::middleware::from_fn`.
+pub async fn metrics_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let start = Instant::now();
+    let method = req.method().to_string();
+    // The pattern instead of the path keeps the label values bounded
+    let route = req
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let res = next.call(req).await;
+
+    let status = match &res {
+        Ok(res) => res.status(),
+        Err(err) => err.as_response_error().status_code(),
+    };
+    HTTP_REQUESTS_TOTAL
+        .with_label_values(&[method.as_str(), route.as_str(), status.as_str()])
+        .inc();
+    HTTP_REQUEST_DURATION_SECONDS
+        .with_label_values(&[method.as_str(), route.as_str()])
+        .observe(start.elapsed().as_secs_f64());
+    res
+}
@@ -0,0 +1,967 @@
+//! In-memory repositories, so the services and handlers can be tested without MongoDB.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use rand::seq::SliceRandom;
+
+use crate::algorithm::fuzzy_match;
+use crate::entity::category_rating::CategoryRating;
+use crate::entity::face_info::{FaceInfo, FaceInfoOrder, FaceInfoPatch, FaceStatus};
+use crate::entity::face_info_audit::FaceInfoAudit;
+use crate::entity::face_report::{FaceReport, FaceReportSummary, ReportReasonCnt};
+use crate::entity::file_resource::FileResource;
+use crate::entity::rating_log::RatingLog;
+use crate::entity::season::Season;
+use crate::entity::season_snapshot::SeasonSnapshot;
+use crate::entity::{DELETED, NOT_DELETED};
+use crate::repository::{
+    get_page_skip, CategoryRatingRepository, DatabaseRepository, FaceInfoAuditRepository,
+    FaceInfoRepository, FaceReportRepository, FileResourceRepository, RatingLogRepository,
+    RepositoryResult, SeasonRepository, SeasonSnapshotRepository,
+};
+
+fn get_page<T: Clone>(docs: Vec<&T>, skip: u64, limit: i64) -> Vec<T> {
+    docs.into_iter()
+        .skip(skip as usize)
+        .take(limit.max(0) as usize)
+        .cloned()
+        .collect()
+}
+
+#[derive(Default)]
+pub struct InMemoryFaceInfoRepository {
+    face_infos: RwLock<Vec<FaceInfo>>,
+}
+
+impl InMemoryFaceInfoRepository {
+    /// Updates the first face_info matching the predicate, returns false if none matched.
+    fn update_one<P, U>(&self, predicate: P, update: U) -> bool
+    where
+        P: Fn(&FaceInfo) -> bool,
+        U: FnOnce(&mut FaceInfo),
+    {
+        let mut face_infos = self.face_infos.write().unwrap();
+        match face_infos.iter_mut().find(|face_info| predicate(face_info)) {
+            None => false,
+            Some(face_info) => {
+                update(face_info);
+                true
+            }
+        }
+    }
+
+    fn find_live<P: Fn(&FaceInfo) -> bool>(&self, predicate: P) -> Vec<FaceInfo> {
+        self.face_infos
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|face_info| face_info.is_deleted == NOT_DELETED && predicate(face_info))
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl FaceInfoRepository for InMemoryFaceInfoRepository {
+    async fn add_face_info(&self, face_info: &FaceInfo) -> RepositoryResult<()> {
+        self.face_infos.write().unwrap().push(face_info.clone());
+        Ok(())
+    }
+
+    async fn add_face_infos(&self, face_infos: Vec<FaceInfo>) -> RepositoryResult<()> {
+        self.face_infos.write().unwrap().extend(face_infos);
+        Ok(())
+    }
+
+    async fn get_face_info_by_id(&self, id: &str) -> RepositoryResult<Option<FaceInfo>> {
+        Ok(self.find_live(|face_info| face_info.id == id).pop())
+    }
+
+    async fn get_face_info_by_file_id(&self, file_id: &str) -> RepositoryResult<Option<FaceInfo>> {
+        Ok(self
+            .find_live(|face_info| face_info.file_id == file_id)
+            .pop())
+    }
+
+    async fn get_face_infos_by_ids(
+        &self,
+        ids: &[&str],
+        status: FaceStatus,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        Ok(self.find_live(|face_info| {
+            face_info.status == status && ids.contains(&face_info.id.as_str())
+        }))
+    }
+
+    async fn get_face_infos_by_status(
+        &self,
+        status: FaceStatus,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let mut face_infos = self.find_live(|face_info| face_info.status == status);
+        face_infos.sort_by(|a, b| (a.created_on, &a.id).cmp(&(b.created_on, &b.id)));
+        Ok(get_page(
+            face_infos.iter().collect(),
+            get_page_skip(page, page_size),
+            page_size,
+        ))
+    }
+
+    async fn get_approved_face_infos(
+        &self,
+        order: FaceInfoOrder,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let mut face_infos = self.find_live(|face_info| face_info.status == FaceStatus::Approved);
+        face_infos.sort_by(|a, b| {
+            let ord = match order {
+                FaceInfoOrder::Score => b.score.total_cmp(&a.score),
+                FaceInfoOrder::CreatedOn => b.created_on.cmp(&a.created_on),
+            };
+            ord.then_with(|| a.id.cmp(&b.id))
+        });
+        Ok(get_page(
+            face_infos.iter().collect(),
+            get_page_skip(page, page_size),
+            page_size,
+        ))
+    }
+
+    async fn get_approved_face_infos_after(
+        &self,
+        after_id: &str,
+        limit: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let mut face_infos = self.find_live(|face_info| {
+            face_info.status == FaceStatus::Approved && face_info.id.as_str() > after_id
+        });
+        face_infos.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(get_page(face_infos.iter().collect(), 0, limit))
+    }
+
+    async fn search_face_infos_by_trigrams(
+        &self,
+        trigrams: &[String],
+        limit: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let mut matches: Vec<(usize, FaceInfo)> = self
+            .find_live(|face_info| face_info.status == FaceStatus::Approved)
+            .into_iter()
+            .map(|face_info| {
+                let star_name_trigrams = fuzzy_match::trigrams(&face_info.star_name);
+                let matched_cnt = trigrams
+                    .iter()
+                    .filter(|trigram| star_name_trigrams.contains(trigram))
+                    .count();
+                (matched_cnt, face_info)
+            })
+            .filter(|(matched_cnt, _)| *matched_cnt > 0)
+            .collect();
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.id.cmp(&b.1.id)));
+        Ok(matches
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|(_, face_info)| face_info)
+            .collect())
+    }
+
+    async fn search_face_infos_by_prefix(
+        &self,
+        prefix: &str,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let mut face_infos = self.find_live(|face_info| {
+            face_info.status == FaceStatus::Approved
+                && fuzzy_match::is_prefix(prefix, &face_info.star_name)
+        });
+        face_infos.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+        Ok(get_page(
+            face_infos.iter().collect(),
+            get_page_skip(page, page_size),
+            page_size,
+        ))
+    }
+
+    async fn count_face_infos_by_prefix(&self, prefix: &str) -> RepositoryResult<u64> {
+        Ok(self
+            .find_live(|face_info| {
+                face_info.status == FaceStatus::Approved
+                    && fuzzy_match::is_prefix(prefix, &face_info.star_name)
+            })
+            .len() as u64)
+    }
+
+    async fn get_face_info_sample(
+        &self,
+        size: i64,
+        category: Option<&str>,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        let face_infos = self.find_live(|face_info| {
+            face_info.status == FaceStatus::Approved
+                && category
+                    .is_none_or(|category| face_info.categories.iter().any(|c| c == category))
+        });
+        Ok(face_infos
+            .choose_multiple(&mut rand::thread_rng(), size.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_all_face_infos(&self) -> RepositoryResult<Vec<FaceInfo>> {
+        Ok(self.face_infos.read().unwrap().clone())
+    }
+
+    async fn count_all_face_infos(&self) -> RepositoryResult<u64> {
+        Ok(self.face_infos.read().unwrap().len() as u64)
+    }
+
+    async fn count_face_infos_by_status(&self, status: FaceStatus) -> RepositoryResult<u64> {
+        Ok(self.find_live(|face_info| face_info.status == status).len() as u64)
+    }
+
+    async fn get_deleted_face_infos_before(
+        &self,
+        deleted_before: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>> {
+        Ok(self
+            .face_infos
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|face_info| {
+                face_info.is_deleted == DELETED && face_info.deleted_on < deleted_before
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn update_face_info_rating(
+        &self,
+        id: &str,
+        score: f64,
+        upvote: bool,
+        voter: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        Ok(self.update_one(
+            |face_info| face_info.id == id,
+            |face_info| {
+                face_info.score = score;
+                if upvote {
+                    face_info.upvote_count += 1;
+                } else {
+                    face_info.downvote_count += 1;
+                }
+                face_info.updater = voter.to_string();
+                face_info.updated_on = now;
+            },
+        ))
+    }
+
+    async fn soft_reset_face_info_scores(
+        &self,
+        target: f64,
+        soft_reset: f64,
+    ) -> RepositoryResult<u64> {
+        let mut face_infos = self.face_infos.write().unwrap();
+        let mut updated_cnt = 0;
+        for face_info in face_infos.iter_mut() {
+            if face_info.is_deleted == NOT_DELETED {
+                face_info.score += (target - face_info.score) * soft_reset;
+                updated_cnt += 1;
+            }
+        }
+        Ok(updated_cnt)
+    }
+
+    async fn update_face_info_by_patch(
+        &self,
+        face_info: &FaceInfo,
+        patch: &FaceInfoPatch,
+        updater: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        Ok(self.update_one(
+            |current| {
+                current.id == face_info.id
+                    && current.is_deleted == NOT_DELETED
+                    && (patch.star_name.is_none() || current.star_name == face_info.star_name)
+                    && (patch.file_id.is_none() || current.file_id == face_info.file_id)
+                    && (patch.categories.is_none() || current.categories == face_info.categories)
+            },
+            |current| {
+                if let Some(star_name) = &patch.star_name {
+                    current.star_name = star_name.clone();
+                }
+                if let Some(file_id) = &patch.file_id {
+                    current.file_id = file_id.clone();
+                }
+                if let Some(categories) = &patch.categories {
+                    current.categories = categories.clone();
+                }
+                current.updater = updater.to_string();
+                current.updated_on = now;
+            },
+        ))
+    }
+
+    async fn update_face_info_status(
+        &self,
+        id: &str,
+        old_status: FaceStatus,
+        status: FaceStatus,
+        moderator: &str,
+        reason: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        Ok(self.update_one(
+            |face_info| {
+                face_info.id == id
+                    && face_info.is_deleted == NOT_DELETED
+                    && face_info.status == old_status
+            },
+            |face_info| {
+                face_info.status = status;
+                face_info.moderator = moderator.to_string();
+                face_info.moderation_reason = reason.to_string();
+                face_info.moderated_on = now;
+                face_info.updater = moderator.to_string();
+                face_info.updated_on = now;
+            },
+        ))
+    }
+
+    async fn soft_delete_face_info(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        Ok(self.update_one(
+            |face_info| face_info.id == id && face_info.is_deleted == NOT_DELETED,
+            |face_info| {
+                face_info.is_deleted = DELETED;
+                face_info.deleted_on = now;
+                face_info.updater = operator.to_string();
+                face_info.updated_on = now;
+            },
+        ))
+    }
+
+    async fn restore_face_info(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        Ok(self.update_one(
+            |face_info| face_info.id == id && face_info.is_deleted == DELETED,
+            |face_info| {
+                face_info.is_deleted = NOT_DELETED;
+                face_info.deleted_on = 0;
+                face_info.updater = operator.to_string();
+                face_info.updated_on = now;
+            },
+        ))
+    }
+
+    async fn delete_face_infos_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let mut face_infos = self.face_infos.write().unwrap();
+        let before = face_infos.len();
+        face_infos.retain(|face_info| !ids.contains(&face_info.id));
+        Ok((before - face_infos.len()) as u64)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryFileResourceRepository {
+    file_resources: RwLock<Vec<FileResource>>,
+}
+
+impl InMemoryFileResourceRepository {
+    fn set_deleted(&self, id: &str, is_deleted: i64, operator: &str, now: i64) -> bool {
+        let mut file_resources = self.file_resources.write().unwrap();
+        match file_resources
+            .iter_mut()
+            .find(|file_resource| file_resource.id == id && file_resource.is_deleted != is_deleted)
+        {
+            None => false,
+            Some(file_resource) => {
+                file_resource.is_deleted = is_deleted;
+                file_resource.deleted_on = if is_deleted == DELETED { now } else { 0 };
+                file_resource.updater = operator.to_string();
+                file_resource.updated_on = now;
+                true
+            }
+        }
+    }
+
+    fn find_one_live<P: Fn(&FileResource) -> bool>(&self, predicate: P) -> Option<FileResource> {
+        self.file_resources
+            .read()
+            .unwrap()
+            .iter()
+            .find(|file_resource| {
+                file_resource.is_deleted == NOT_DELETED && predicate(file_resource)
+            })
+            .cloned()
+    }
+}
+
+#[async_trait]
+impl FileResourceRepository for InMemoryFileResourceRepository {
+    async fn add_file_resource(&self, file_resource: &FileResource) -> RepositoryResult<()> {
+        self.file_resources
+            .write()
+            .unwrap()
+            .push(file_resource.clone());
+        Ok(())
+    }
+
+    async fn add_file_resources(&self, file_resources: Vec<FileResource>) -> RepositoryResult<()> {
+        self.file_resources.write().unwrap().extend(file_resources);
+        Ok(())
+    }
+
+    async fn get_file_resource_by_id(&self, id: &str) -> RepositoryResult<Option<FileResource>> {
+        Ok(self.find_one_live(|file_resource| file_resource.id == id))
+    }
+
+    async fn get_file_resource_by_md5(&self, md5: &str) -> RepositoryResult<Option<FileResource>> {
+        Ok(self.find_one_live(|file_resource| file_resource.md5 == md5))
+    }
+
+    async fn get_all_file_resources(&self) -> RepositoryResult<Vec<FileResource>> {
+        Ok(self.file_resources.read().unwrap().clone())
+    }
+
+    async fn count_all_file_resources(&self) -> RepositoryResult<u64> {
+        Ok(self.file_resources.read().unwrap().len() as u64)
+    }
+
+    async fn get_deleted_file_resources_before(
+        &self,
+        deleted_before: i64,
+    ) -> RepositoryResult<Vec<FileResource>> {
+        Ok(self
+            .file_resources
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|file_resource| {
+                file_resource.is_deleted == DELETED && file_resource.deleted_on < deleted_before
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn soft_delete_file_resource(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        Ok(self.set_deleted(id, DELETED, operator, now))
+    }
+
+    async fn restore_file_resource(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool> {
+        Ok(self.set_deleted(id, NOT_DELETED, operator, now))
+    }
+
+    async fn delete_file_resources_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let mut file_resources = self.file_resources.write().unwrap();
+        let before = file_resources.len();
+        file_resources.retain(|file_resource| !ids.contains(&file_resource.id));
+        Ok((before - file_resources.len()) as u64)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryRatingLogRepository {
+    rating_logs: RwLock<Vec<RatingLog>>,
+}
+
+#[async_trait]
+impl RatingLogRepository for InMemoryRatingLogRepository {
+    async fn add_rating_logs(&self, rating_logs: Vec<RatingLog>) -> RepositoryResult<()> {
+        self.rating_logs.write().unwrap().extend(rating_logs);
+        Ok(())
+    }
+
+    async fn get_all_rating_logs(&self) -> RepositoryResult<Vec<RatingLog>> {
+        Ok(self.rating_logs.read().unwrap().clone())
+    }
+
+    async fn count_all_rating_logs(&self) -> RepositoryResult<u64> {
+        Ok(self.rating_logs.read().unwrap().len() as u64)
+    }
+
+    async fn purge_rating_logs(
+        &self,
+        face_info_ids: &[String],
+        deleted_before: i64,
+    ) -> RepositoryResult<u64> {
+        let mut rating_logs = self.rating_logs.write().unwrap();
+        let before = rating_logs.len();
+        rating_logs.retain(|rating_log| {
+            !(face_info_ids.contains(&rating_log.win_face_id)
+                || face_info_ids.contains(&rating_log.loss_face_id)
+                || (rating_log.is_deleted == DELETED && rating_log.deleted_on < deleted_before))
+        });
+        Ok((before - rating_logs.len()) as u64)
+    }
+
+    async fn delete_rating_logs_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let mut rating_logs = self.rating_logs.write().unwrap();
+        let before = rating_logs.len();
+        rating_logs.retain(|rating_log| !ids.contains(&rating_log.id));
+        Ok((before - rating_logs.len()) as u64)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryCategoryRatingRepository {
+    category_ratings: RwLock<Vec<CategoryRating>>,
+}
+
+#[async_trait]
+impl CategoryRatingRepository for InMemoryCategoryRatingRepository {
+    async fn add_category_ratings(
+        &self,
+        category_ratings: Vec<CategoryRating>,
+    ) -> RepositoryResult<()> {
+        self.category_ratings
+            .write()
+            .unwrap()
+            .extend(category_ratings);
+        Ok(())
+    }
+
+    async fn get_all_category_ratings(&self) -> RepositoryResult<Vec<CategoryRating>> {
+        Ok(self.category_ratings.read().unwrap().clone())
+    }
+
+    async fn count_all_category_ratings(&self) -> RepositoryResult<u64> {
+        Ok(self.category_ratings.read().unwrap().len() as u64)
+    }
+
+    async fn get_category_ratings(
+        &self,
+        category: &str,
+        face_info_ids: &[&str],
+    ) -> RepositoryResult<Vec<CategoryRating>> {
+        Ok(self
+            .category_ratings
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|rating| {
+                rating.category == category && face_info_ids.contains(&rating.face_info_id.as_str())
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn update_category_rating(
+        &self,
+        category: &str,
+        face_info_id: &str,
+        score: f64,
+        upvote: bool,
+        now: i64,
+    ) -> RepositoryResult<()> {
+        let mut category_ratings = self.category_ratings.write().unwrap();
+        let idx = match category_ratings
+            .iter()
+            .position(|rating| rating.category == category && rating.face_info_id == face_info_id)
+        {
+            Some(idx) => idx,
+            None => {
+                category_ratings.push(CategoryRating {
+                    category: category.to_string(),
+                    face_info_id: face_info_id.to_string(),
+                    created_on: now,
+                    ..CategoryRating::default()
+                });
+                category_ratings.len() - 1
+            }
+        };
+        let rating = &mut category_ratings[idx];
+        rating.score = score;
+        if upvote {
+            rating.upvote_count += 1;
+        } else {
+            rating.downvote_count += 1;
+        }
+        rating.updated_on = now;
+        Ok(())
+    }
+
+    async fn get_category_ratings_page(
+        &self,
+        category: &str,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<CategoryRating>> {
+        let category_ratings = self.category_ratings.read().unwrap();
+        let mut ratings: Vec<&CategoryRating> = category_ratings
+            .iter()
+            .filter(|rating| rating.category == category)
+            .collect();
+        ratings.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.face_info_id.cmp(&b.face_info_id))
+        });
+        Ok(get_page(ratings, get_page_skip(page, page_size), page_size))
+    }
+
+    async fn delete_category_ratings_by_face_info_ids(
+        &self,
+        face_info_ids: &[String],
+    ) -> RepositoryResult<u64> {
+        let mut category_ratings = self.category_ratings.write().unwrap();
+        let before = category_ratings.len();
+        category_ratings.retain(|rating| !face_info_ids.contains(&rating.face_info_id));
+        Ok((before - category_ratings.len()) as u64)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemorySeasonRepository {
+    seasons: RwLock<Vec<Season>>,
+}
+
+#[async_trait]
+impl SeasonRepository for InMemorySeasonRepository {
+    async fn add_season(&self, season: &Season) -> RepositoryResult<()> {
+        self.seasons.write().unwrap().push(season.clone());
+        Ok(())
+    }
+
+    async fn get_season_by_id(&self, id: &str) -> RepositoryResult<Option<Season>> {
+        Ok(self
+            .seasons
+            .read()
+            .unwrap()
+            .iter()
+            .find(|season| season.id == id)
+            .cloned())
+    }
+
+    async fn get_current_season(&self) -> RepositoryResult<Option<Season>> {
+        Ok(self
+            .seasons
+            .read()
+            .unwrap()
+            .iter()
+            .find(|season| season.ended_on == 0)
+            .cloned())
+    }
+
+    async fn get_seasons(&self) -> RepositoryResult<Vec<Season>> {
+        let mut seasons = self.seasons.read().unwrap().clone();
+        seasons.sort_by(|a, b| {
+            b.started_on
+                .cmp(&a.started_on)
+                .then_with(|| a.ended_on.cmp(&b.ended_on))
+        });
+        Ok(seasons)
+    }
+
+    async fn end_season(&self, id: &str, now: i64) -> RepositoryResult<bool> {
+        let mut seasons = self.seasons.write().unwrap();
+        match seasons
+            .iter_mut()
+            .find(|season| season.id == id && season.ended_on == 0)
+        {
+            None => Ok(false),
+            Some(season) => {
+                season.ended_on = now;
+                Ok(true)
+            }
+        }
+    }
+
+    async fn finish_season_reset(&self, id: &str) -> RepositoryResult<bool> {
+        let mut seasons = self.seasons.write().unwrap();
+        match seasons
+            .iter_mut()
+            .find(|season| season.id == id && season.reset_pending)
+        {
+            None => Ok(false),
+            Some(season) => {
+                season.reset_pending = false;
+                Ok(true)
+            }
+        }
+    }
+
+    async fn delete_seasons_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let mut seasons = self.seasons.write().unwrap();
+        let before = seasons.len();
+        seasons.retain(|season| !ids.contains(&season.id));
+        Ok((before - seasons.len()) as u64)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemorySeasonSnapshotRepository {
+    season_snapshots: RwLock<Vec<SeasonSnapshot>>,
+}
+
+#[async_trait]
+impl SeasonSnapshotRepository for InMemorySeasonSnapshotRepository {
+    async fn save_season_snapshots(
+        &self,
+        season_snapshots: Vec<SeasonSnapshot>,
+    ) -> RepositoryResult<()> {
+        let mut saved_snapshots = self.season_snapshots.write().unwrap();
+        for season_snapshot in season_snapshots {
+            saved_snapshots.retain(|snapshot| {
+                (&snapshot.season_id, &snapshot.face_info_id)
+                    != (&season_snapshot.season_id, &season_snapshot.face_info_id)
+            });
+            saved_snapshots.push(season_snapshot);
+        }
+        Ok(())
+    }
+
+    async fn get_season_snapshots_page(
+        &self,
+        season_id: &str,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<SeasonSnapshot>> {
+        let season_snapshots = self.season_snapshots.read().unwrap();
+        let mut snapshots: Vec<&SeasonSnapshot> = season_snapshots
+            .iter()
+            .filter(|snapshot| snapshot.season_id == season_id)
+            .collect();
+        snapshots.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.face_info_id.cmp(&b.face_info_id))
+        });
+        Ok(get_page(
+            snapshots,
+            get_page_skip(page, page_size),
+            page_size,
+        ))
+    }
+
+    async fn get_all_season_snapshots(&self) -> RepositoryResult<Vec<SeasonSnapshot>> {
+        Ok(self.season_snapshots.read().unwrap().clone())
+    }
+
+    async fn count_all_season_snapshots(&self) -> RepositoryResult<u64> {
+        Ok(self.season_snapshots.read().unwrap().len() as u64)
+    }
+
+    async fn delete_season_snapshots_by_season_ids(
+        &self,
+        season_ids: &[String],
+    ) -> RepositoryResult<u64> {
+        let mut season_snapshots = self.season_snapshots.write().unwrap();
+        let before = season_snapshots.len();
+        season_snapshots.retain(|snapshot| !season_ids.contains(&snapshot.season_id));
+        Ok((before - season_snapshots.len()) as u64)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryFaceInfoAuditRepository {
+    face_info_audits: RwLock<Vec<FaceInfoAudit>>,
+}
+
+#[async_trait]
+impl FaceInfoAuditRepository for InMemoryFaceInfoAuditRepository {
+    async fn add_face_info_audits(
+        &self,
+        face_info_audits: Vec<FaceInfoAudit>,
+    ) -> RepositoryResult<()> {
+        self.face_info_audits
+            .write()
+            .unwrap()
+            .extend(face_info_audits);
+        Ok(())
+    }
+
+    async fn get_face_info_audits_by_face_info_id(
+        &self,
+        face_info_id: &str,
+    ) -> RepositoryResult<Vec<FaceInfoAudit>> {
+        let mut face_info_audits: Vec<FaceInfoAudit> = self
+            .face_info_audits
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|audit| audit.face_info_id == face_info_id && audit.is_deleted == NOT_DELETED)
+            .cloned()
+            .collect();
+        // Stable sort keeps the insertion order of the audits written at once
+        face_info_audits.sort_by_key(|audit| std::cmp::Reverse(audit.created_on));
+        Ok(face_info_audits)
+    }
+
+    async fn get_all_face_info_audits(&self) -> RepositoryResult<Vec<FaceInfoAudit>> {
+        Ok(self.face_info_audits.read().unwrap().clone())
+    }
+
+    async fn count_all_face_info_audits(&self) -> RepositoryResult<u64> {
+        Ok(self.face_info_audits.read().unwrap().len() as u64)
+    }
+
+    async fn delete_face_info_audits_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let mut face_info_audits = self.face_info_audits.write().unwrap();
+        let before = face_info_audits.len();
+        face_info_audits.retain(|audit| !ids.contains(&audit.id));
+        Ok((before - face_info_audits.len()) as u64)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryFaceReportRepository {
+    face_reports: RwLock<Vec<FaceReport>>,
+}
+
+impl InMemoryFaceReportRepository {
+    fn is_unresolved(face_report: &FaceReport) -> bool {
+        face_report.is_resolved == 0 && face_report.is_deleted == NOT_DELETED
+    }
+}
+
+#[async_trait]
+impl FaceReportRepository for InMemoryFaceReportRepository {
+    async fn add_face_report_if_absent(&self, face_report: &FaceReport) -> RepositoryResult<bool> {
+        let mut face_reports = self.face_reports.write().unwrap();
+        if face_reports.iter().any(|existing| {
+            Self::is_unresolved(existing)
+                && existing.face_info_id == face_report.face_info_id
+                && existing.reporter == face_report.reporter
+        }) {
+            return Ok(false);
+        }
+        face_reports.push(face_report.clone());
+        Ok(true)
+    }
+
+    async fn add_face_reports(&self, face_reports: Vec<FaceReport>) -> RepositoryResult<()> {
+        self.face_reports.write().unwrap().extend(face_reports);
+        Ok(())
+    }
+
+    async fn count_unresolved_face_reports(&self, face_info_id: &str) -> RepositoryResult<u64> {
+        Ok(self
+            .face_reports
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|face_report| {
+                Self::is_unresolved(face_report) && face_report.face_info_id == face_info_id
+            })
+            .count() as u64)
+    }
+
+    async fn resolve_face_reports(
+        &self,
+        face_info_id: &str,
+        moderator: &str,
+        now: i64,
+    ) -> RepositoryResult<u64> {
+        let mut resolved_cnt = 0;
+        for face_report in self.face_reports.write().unwrap().iter_mut() {
+            if face_report.face_info_id == face_info_id && face_report.is_resolved == 0 {
+                face_report.is_resolved = 1;
+                face_report.resolved_on = now;
+                face_report.updater = moderator.to_string();
+                face_report.updated_on = now;
+                resolved_cnt += 1;
+            }
+        }
+        Ok(resolved_cnt)
+    }
+
+    async fn get_unresolved_face_report_summaries(
+        &self,
+        skip: u64,
+        limit: i64,
+    ) -> RepositoryResult<Vec<FaceReportSummary>> {
+        let mut summaries: HashMap<String, FaceReportSummary> = HashMap::new();
+        for face_report in self.face_reports.read().unwrap().iter() {
+            if !Self::is_unresolved(face_report) {
+                continue;
+            }
+            let summary = summaries
+                .entry(face_report.face_info_id.clone())
+                .or_insert_with(|| FaceReportSummary {
+                    face_info_id: face_report.face_info_id.clone(),
+                    report_cnt: 0,
+                    reason_cnts: vec![],
+                    last_reported_on: 0,
+                });
+            summary.report_cnt += 1;
+            summary.last_reported_on = summary.last_reported_on.max(face_report.created_on);
+            match summary
+                .reason_cnts
+                .iter_mut()
+                .find(|reason_cnt| reason_cnt.reason == face_report.reason)
+            {
+                Some(reason_cnt) => reason_cnt.cnt += 1,
+                None => summary.reason_cnts.push(ReportReasonCnt {
+                    reason: face_report.reason,
+                    cnt: 1,
+                }),
+            }
+        }
+
+        let mut summaries: Vec<&FaceReportSummary> = summaries.values().collect();
+        summaries.sort_by(|a, b| {
+            b.report_cnt
+                .cmp(&a.report_cnt)
+                .then_with(|| a.face_info_id.cmp(&b.face_info_id))
+        });
+        Ok(get_page(summaries, skip, limit))
+    }
+
+    async fn get_all_face_reports(&self) -> RepositoryResult<Vec<FaceReport>> {
+        Ok(self.face_reports.read().unwrap().clone())
+    }
+
+    async fn count_all_face_reports(&self) -> RepositoryResult<u64> {
+        Ok(self.face_reports.read().unwrap().len() as u64)
+    }
+
+    async fn delete_face_reports_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+        let mut face_reports = self.face_reports.write().unwrap();
+        let before = face_reports.len();
+        face_reports.retain(|face_report| !ids.contains(&face_report.id));
+        Ok((before - face_reports.len()) as u64)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryDatabaseRepository {
+    /// Fails the pings, to test the dependents of a database down
+    pub is_down: bool,
+}
+
+#[async_trait]
+impl DatabaseRepository for InMemoryDatabaseRepository {
+    async fn ping(&self) -> RepositoryResult<()> {
+        if self.is_down {
+            return Err(sqlx::Error::PoolClosed.into());
+        }
+        Ok(())
+    }
+}
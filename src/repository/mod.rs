@@ -0,0 +1,545 @@
+use std::sync::Arc;
+use std::{error, fmt};
+
+use async_trait::async_trait;
+use sqlx::AnyPool;
+
+use crate::config::settings::StorageBackend;
+
+use crate::dao::category_rating_dao::MongoCategoryRatingDao;
+use crate::dao::database_dao::MongoDatabaseDao;
+use crate::dao::face_info_audit_dao::MongoFaceInfoAuditDao;
+use crate::dao::face_info_dao::MongoFaceInfoDao;
+use crate::dao::face_report_dao::MongoFaceReportDao;
+use crate::dao::file_resource_dao::MongoFileResourceDao;
+use crate::dao::rating_log_dao::MongoRatingLogDao;
+use crate::dao::season_dao::MongoSeasonDao;
+use crate::dao::season_snapshot_dao::MongoSeasonSnapshotDao;
+use crate::dao::sql::category_rating_dao::SqlCategoryRatingDao;
+use crate::dao::sql::database_dao::SqlDatabaseDao;
+use crate::dao::sql::face_info_audit_dao::SqlFaceInfoAuditDao;
+use crate::dao::sql::face_info_dao::SqlFaceInfoDao;
+use crate::dao::sql::face_report_dao::SqlFaceReportDao;
+use crate::dao::sql::file_resource_dao::SqlFileResourceDao;
+use crate::dao::sql::migration;
+use crate::dao::sql::rating_log_dao::SqlRatingLogDao;
+use crate::dao::sql::season_dao::SqlSeasonDao;
+use crate::dao::sql::season_snapshot_dao::SqlSeasonSnapshotDao;
+use crate::entity::category_rating::CategoryRating;
+use crate::entity::face_info::{FaceInfo, FaceInfoOrder, FaceInfoPatch, FaceStatus};
+use crate::entity::face_info_audit::FaceInfoAudit;
+use crate::entity::face_report::{FaceReport, FaceReportSummary};
+use crate::entity::file_resource::FileResource;
+use crate::entity::rating_log::RatingLog;
+use crate::entity::season::Season;
+use crate::entity::season_snapshot::SeasonSnapshot;
+#[cfg(test)]
+use crate::repository::memory::{
+    InMemoryCategoryRatingRepository, InMemoryDatabaseRepository, InMemoryFaceInfoAuditRepository,
+    InMemoryFaceInfoRepository, InMemoryFaceReportRepository, InMemoryFileResourceRepository,
+    InMemoryRatingLogRepository, InMemorySeasonRepository, InMemorySeasonSnapshotRepository,
+};
+use crate::{config, dao, resource};
+
+#[cfg(test)]
+pub mod memory;
+
+#[derive(Debug)]
+pub enum RepositoryError {
+    Mongo(mongodb::error::Error),
+    Sql(sqlx::Error),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepositoryError::Mongo(err) => write!(f, "mongo error: {}", err),
+            RepositoryError::Sql(err) => write!(f, "sql error: {}", err),
+        }
+    }
+}
+
+impl error::Error for RepositoryError {}
+
+impl From<mongodb::error::Error> for RepositoryError {
+    fn from(err: mongodb::error::Error) -> Self {
+        RepositoryError::Mongo(err)
+    }
+}
+
+impl From<sqlx::Error> for RepositoryError {
+    fn from(err: sqlx::Error) -> Self {
+        RepositoryError::Sql(err)
+    }
+}
+
+impl From<mongodb::bson::ser::Error> for RepositoryError {
+    fn from(err: mongodb::bson::ser::Error) -> Self {
+        RepositoryError::Mongo(err.into())
+    }
+}
+
+impl From<mongodb::bson::de::Error> for RepositoryError {
+    fn from(err: mongodb::bson::de::Error) -> Self {
+        RepositoryError::Mongo(err.into())
+    }
+}
+
+pub type RepositoryResult<T> = Result<T, RepositoryError>;
+
+/// Gets the count of the documents before the page, saturated so a page past
+/// the range is empty, the handlers reject it beforehand, see `check_page`.
+pub fn get_page_skip(page: u64, page_size: i64) -> u64 {
+    page.saturating_mul(page_size.max(0) as u64)
+}
+
+/// The storage of face_infos, the soft deleted ones are excluded unless stated otherwise.
+#[async_trait]
+pub trait FaceInfoRepository: Send + Sync {
+    async fn add_face_info(&self, face_info: &FaceInfo) -> RepositoryResult<()>;
+
+    async fn add_face_infos(&self, face_infos: Vec<FaceInfo>) -> RepositoryResult<()>;
+
+    async fn get_face_info_by_id(&self, id: &str) -> RepositoryResult<Option<FaceInfo>>;
+
+    async fn get_face_info_by_file_id(&self, file_id: &str) -> RepositoryResult<Option<FaceInfo>>;
+
+    async fn get_face_infos_by_ids(
+        &self,
+        ids: &[&str],
+        status: FaceStatus,
+    ) -> RepositoryResult<Vec<FaceInfo>>;
+
+    /// Gets a page of the face_infos in the status, the oldest comes first.
+    async fn get_face_infos_by_status(
+        &self,
+        status: FaceStatus,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>>;
+
+    /// Gets a page of the approved face_infos in the order.
+    async fn get_approved_face_infos(
+        &self,
+        order: FaceInfoOrder,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>>;
+
+    /// Gets at most `limit` approved face_infos whose id comes after `after_id`, by id,
+    /// a scan unaffected by the face_infos added or removed meanwhile.
+    async fn get_approved_face_infos_after(
+        &self,
+        after_id: &str,
+        limit: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>>;
+
+    /// Gets the approved face_infos whose star_name shares the most of the trigrams,
+    /// at most `limit` of them, see `algorithm::fuzzy_match`.
+    async fn search_face_infos_by_trigrams(
+        &self,
+        trigrams: &[String],
+        limit: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>>;
+
+    /// Gets a page of the approved face_infos whose star_name, or one of its words, starts
+    /// with the normalized prefix, the highest score first, see `fuzzy_match::word_suffixes`.
+    async fn search_face_infos_by_prefix(
+        &self,
+        prefix: &str,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>>;
+
+    /// Counts the approved face_infos `search_face_infos_by_prefix` matches.
+    async fn count_face_infos_by_prefix(&self, prefix: &str) -> RepositoryResult<u64>;
+
+    /// Gets approved face_infos randomly, only the ones in the category if any.
+    async fn get_face_info_sample(
+        &self,
+        size: i64,
+        category: Option<&str>,
+    ) -> RepositoryResult<Vec<FaceInfo>>;
+
+    /// Gets all the face_infos, including the soft deleted ones.
+    async fn get_all_face_infos(&self) -> RepositoryResult<Vec<FaceInfo>>;
+
+    /// Counts all the face_infos, including the soft deleted ones.
+    async fn count_all_face_infos(&self) -> RepositoryResult<u64>;
+
+    async fn count_face_infos_by_status(&self, status: FaceStatus) -> RepositoryResult<u64>;
+
+    async fn get_deleted_face_infos_before(
+        &self,
+        deleted_before: i64,
+    ) -> RepositoryResult<Vec<FaceInfo>>;
+
+    /// Sets the score and increases the vote count, returns false if not found.
+    async fn update_face_info_rating(
+        &self,
+        id: &str,
+        score: f64,
+        upvote: bool,
+        voter: &str,
+        now: i64,
+    ) -> RepositoryResult<bool>;
+
+    /// Moves the scores of the face_infos toward `target` by the share `soft_reset`
+    /// of their distance to it, returns the updated count.
+    async fn soft_reset_face_info_scores(
+        &self,
+        target: f64,
+        soft_reset: f64,
+    ) -> RepositoryResult<u64>;
+
+    /// Applies the patch if the patched fields still hold their values in `face_info`,
+    /// returns false if not matched.
+    async fn update_face_info_by_patch(
+        &self,
+        face_info: &FaceInfo,
+        patch: &FaceInfoPatch,
+        updater: &str,
+        now: i64,
+    ) -> RepositoryResult<bool>;
+
+    /// Moves the face_info into the status if it is still in `old_status`,
+    /// returns false if not matched.
+    async fn update_face_info_status(
+        &self,
+        id: &str,
+        old_status: FaceStatus,
+        status: FaceStatus,
+        moderator: &str,
+        reason: &str,
+        now: i64,
+    ) -> RepositoryResult<bool>;
+
+    /// Returns false if there is no live face_info with the id.
+    async fn soft_delete_face_info(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool>;
+
+    /// Returns false if there is no soft deleted face_info with the id.
+    async fn restore_face_info(&self, id: &str, operator: &str, now: i64)
+        -> RepositoryResult<bool>;
+
+    /// Hard deletes the face_infos, returns the deleted count.
+    async fn delete_face_infos_by_ids(&self, ids: &[String]) -> RepositoryResult<u64>;
+}
+
+/// The storage of file_resources, the soft deleted ones are excluded unless stated otherwise.
+#[async_trait]
+pub trait FileResourceRepository: Send + Sync {
+    async fn add_file_resource(&self, file_resource: &FileResource) -> RepositoryResult<()>;
+
+    async fn add_file_resources(&self, file_resources: Vec<FileResource>) -> RepositoryResult<()>;
+
+    async fn get_file_resource_by_id(&self, id: &str) -> RepositoryResult<Option<FileResource>>;
+
+    async fn get_file_resource_by_md5(&self, md5: &str) -> RepositoryResult<Option<FileResource>>;
+
+    /// Gets all the file_resources, including the soft deleted ones.
+    async fn get_all_file_resources(&self) -> RepositoryResult<Vec<FileResource>>;
+
+    /// Counts all the file_resources, including the soft deleted ones.
+    async fn count_all_file_resources(&self) -> RepositoryResult<u64>;
+
+    async fn get_deleted_file_resources_before(
+        &self,
+        deleted_before: i64,
+    ) -> RepositoryResult<Vec<FileResource>>;
+
+    /// Returns false if there is no live file_resource with the id.
+    async fn soft_delete_file_resource(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool>;
+
+    /// Returns false if there is no soft deleted file_resource with the id.
+    async fn restore_file_resource(
+        &self,
+        id: &str,
+        operator: &str,
+        now: i64,
+    ) -> RepositoryResult<bool>;
+
+    /// Hard deletes the file_resources, returns the deleted count.
+    async fn delete_file_resources_by_ids(&self, ids: &[String]) -> RepositoryResult<u64>;
+}
+
+/// The storage of rating_logs.
+#[async_trait]
+pub trait RatingLogRepository: Send + Sync {
+    async fn add_rating_logs(&self, rating_logs: Vec<RatingLog>) -> RepositoryResult<()>;
+
+    /// Gets all the rating_logs, including the soft deleted ones.
+    async fn get_all_rating_logs(&self) -> RepositoryResult<Vec<RatingLog>>;
+
+    /// Counts all the rating_logs, including the soft deleted ones.
+    async fn count_all_rating_logs(&self) -> RepositoryResult<u64>;
+
+    /// Hard deletes the rating_logs of the face_infos and the ones soft deleted
+    /// before `deleted_before`, returns the deleted count.
+    async fn purge_rating_logs(
+        &self,
+        face_info_ids: &[String],
+        deleted_before: i64,
+    ) -> RepositoryResult<u64>;
+
+    async fn delete_rating_logs_by_ids(&self, ids: &[String]) -> RepositoryResult<u64>;
+}
+
+/// The storage of category_ratings.
+#[async_trait]
+pub trait CategoryRatingRepository: Send + Sync {
+    async fn add_category_ratings(
+        &self,
+        category_ratings: Vec<CategoryRating>,
+    ) -> RepositoryResult<()>;
+
+    async fn get_all_category_ratings(&self) -> RepositoryResult<Vec<CategoryRating>>;
+
+    async fn count_all_category_ratings(&self) -> RepositoryResult<u64>;
+
+    async fn get_category_ratings(
+        &self,
+        category: &str,
+        face_info_ids: &[&str],
+    ) -> RepositoryResult<Vec<CategoryRating>>;
+
+    /// Sets the score and increases the vote count, the rating is added on the first vote.
+    async fn update_category_rating(
+        &self,
+        category: &str,
+        face_info_id: &str,
+        score: f64,
+        upvote: bool,
+        now: i64,
+    ) -> RepositoryResult<()>;
+
+    /// Gets a page of the ratings in the category, the highest score comes first
+    /// and the ties by face_info_id.
+    async fn get_category_ratings_page(
+        &self,
+        category: &str,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<CategoryRating>>;
+
+    /// Hard deletes the ratings of the face_infos, returns the deleted count.
+    async fn delete_category_ratings_by_face_info_ids(
+        &self,
+        face_info_ids: &[String],
+    ) -> RepositoryResult<u64>;
+}
+
+/// The storage of seasons.
+#[async_trait]
+pub trait SeasonRepository: Send + Sync {
+    async fn add_season(&self, season: &Season) -> RepositoryResult<()>;
+
+    async fn get_season_by_id(&self, id: &str) -> RepositoryResult<Option<Season>>;
+
+    /// Gets the season not ended yet, if any.
+    async fn get_current_season(&self) -> RepositoryResult<Option<Season>>;
+
+    /// Gets all the seasons, the latest started comes first, the current one
+    /// ahead of the ones started in the same second.
+    async fn get_seasons(&self) -> RepositoryResult<Vec<Season>>;
+
+    /// Ends the season if it is still current, returns false if not matched.
+    async fn end_season(&self, id: &str, now: i64) -> RepositoryResult<bool>;
+
+    /// Clears the pending soft reset of the season, returns false if not matched.
+    async fn finish_season_reset(&self, id: &str) -> RepositoryResult<bool>;
+
+    /// Hard deletes the seasons, returns the deleted count.
+    async fn delete_seasons_by_ids(&self, ids: &[String]) -> RepositoryResult<u64>;
+}
+
+/// The storage of season_snapshots.
+#[async_trait]
+pub trait SeasonSnapshotRepository: Send + Sync {
+    /// Adds the snapshots, replacing the ones of the same season and face_info.
+    async fn save_season_snapshots(
+        &self,
+        season_snapshots: Vec<SeasonSnapshot>,
+    ) -> RepositoryResult<()>;
+
+    /// Gets a page of the snapshots of the season, the highest score comes first
+    /// and the ties by face_info_id.
+    async fn get_season_snapshots_page(
+        &self,
+        season_id: &str,
+        page: u64,
+        page_size: i64,
+    ) -> RepositoryResult<Vec<SeasonSnapshot>>;
+
+    async fn get_all_season_snapshots(&self) -> RepositoryResult<Vec<SeasonSnapshot>>;
+
+    async fn count_all_season_snapshots(&self) -> RepositoryResult<u64>;
+
+    /// Hard deletes the snapshots of the seasons, returns the deleted count.
+    async fn delete_season_snapshots_by_season_ids(
+        &self,
+        season_ids: &[String],
+    ) -> RepositoryResult<u64>;
+}
+
+/// The storage of face_info_audits.
+#[async_trait]
+pub trait FaceInfoAuditRepository: Send + Sync {
+    async fn add_face_info_audits(
+        &self,
+        face_info_audits: Vec<FaceInfoAudit>,
+    ) -> RepositoryResult<()>;
+
+    /// Gets the audits of the face_info, the latest comes first.
+    async fn get_face_info_audits_by_face_info_id(
+        &self,
+        face_info_id: &str,
+    ) -> RepositoryResult<Vec<FaceInfoAudit>>;
+
+    /// Gets all the face_info_audits, including the soft deleted ones.
+    async fn get_all_face_info_audits(&self) -> RepositoryResult<Vec<FaceInfoAudit>>;
+
+    /// Counts all the face_info_audits, including the soft deleted ones.
+    async fn count_all_face_info_audits(&self) -> RepositoryResult<u64>;
+
+    async fn delete_face_info_audits_by_ids(&self, ids: &[String]) -> RepositoryResult<u64>;
+}
+
+/// The storage of face_reports.
+#[async_trait]
+pub trait FaceReportRepository: Send + Sync {
+    /// Adds the report unless the reporter has an unresolved report of the face_info,
+    /// returns false if not added.
+    async fn add_face_report_if_absent(&self, face_report: &FaceReport) -> RepositoryResult<bool>;
+
+    async fn add_face_reports(&self, face_reports: Vec<FaceReport>) -> RepositoryResult<()>;
+
+    async fn count_unresolved_face_reports(&self, face_info_id: &str) -> RepositoryResult<u64>;
+
+    /// Resolves the unresolved reports of the face_info, returns the resolved count.
+    async fn resolve_face_reports(
+        &self,
+        face_info_id: &str,
+        moderator: &str,
+        now: i64,
+    ) -> RepositoryResult<u64>;
+
+    /// Aggregates the unresolved reports per face_info, the most reported comes first.
+    async fn get_unresolved_face_report_summaries(
+        &self,
+        skip: u64,
+        limit: i64,
+    ) -> RepositoryResult<Vec<FaceReportSummary>>;
+
+    /// Gets all the face_reports, including the resolved and the soft deleted ones.
+    async fn get_all_face_reports(&self) -> RepositoryResult<Vec<FaceReport>>;
+
+    /// Counts all the face_reports, including the resolved and the soft deleted ones.
+    async fn count_all_face_reports(&self) -> RepositoryResult<u64>;
+
+    async fn delete_face_reports_by_ids(&self, ids: &[String]) -> RepositoryResult<u64>;
+}
+
+/// The database backing the other repositories.
+#[async_trait]
+pub trait DatabaseRepository: Send + Sync {
+    /// Checks the database is reachable.
+    async fn ping(&self) -> RepositoryResult<()>;
+}
+
+/// All the repositories, shared with the handlers by `web::Data`.
+#[derive(Clone)]
+pub struct Repositories {
+    pub face_info: Arc<dyn FaceInfoRepository>,
+    pub file_resource: Arc<dyn FileResourceRepository>,
+    pub rating_log: Arc<dyn RatingLogRepository>,
+    pub category_rating: Arc<dyn CategoryRatingRepository>,
+    pub season: Arc<dyn SeasonRepository>,
+    pub season_snapshot: Arc<dyn SeasonSnapshotRepository>,
+    pub face_info_audit: Arc<dyn FaceInfoAuditRepository>,
+    pub face_report: Arc<dyn FaceReportRepository>,
+    pub database: Arc<dyn DatabaseRepository>,
+}
+
+impl Repositories {
+    /// The repositories backed by MongoDB.
+    pub fn mongo() -> Self {
+        Repositories {
+            face_info: Arc::new(MongoFaceInfoDao),
+            file_resource: Arc::new(MongoFileResourceDao),
+            rating_log: Arc::new(MongoRatingLogDao),
+            category_rating: Arc::new(MongoCategoryRatingDao),
+            season: Arc::new(MongoSeasonDao),
+            season_snapshot: Arc::new(MongoSeasonSnapshotDao),
+            face_info_audit: Arc::new(MongoFaceInfoAuditDao),
+            face_report: Arc::new(MongoFaceReportDao),
+            database: Arc::new(MongoDatabaseDao),
+        }
+    }
+
+    /// The repositories backed by the relational database of the pool.
+    pub fn sql(pool: AnyPool) -> Self {
+        Repositories {
+            face_info: Arc::new(SqlFaceInfoDao::new(pool.clone())),
+            file_resource: Arc::new(SqlFileResourceDao::new(pool.clone())),
+            rating_log: Arc::new(SqlRatingLogDao::new(pool.clone())),
+            category_rating: Arc::new(SqlCategoryRatingDao::new(pool.clone())),
+            season: Arc::new(SqlSeasonDao::new(pool.clone())),
+            season_snapshot: Arc::new(SqlSeasonSnapshotDao::new(pool.clone())),
+            face_info_audit: Arc::new(SqlFaceInfoAuditDao::new(pool.clone())),
+            face_report: Arc::new(SqlFaceReportDao::new(pool.clone())),
+            database: Arc::new(SqlDatabaseDao::new(pool)),
+        }
+    }
+
+    /// The repositories kept in memory, for tests.
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Repositories {
+            face_info: Arc::new(InMemoryFaceInfoRepository::default()),
+            file_resource: Arc::new(InMemoryFileResourceRepository::default()),
+            rating_log: Arc::new(InMemoryRatingLogRepository::default()),
+            category_rating: Arc::new(InMemoryCategoryRatingRepository::default()),
+            season: Arc::new(InMemorySeasonRepository::default()),
+            season_snapshot: Arc::new(InMemorySeasonSnapshotRepository::default()),
+            face_info_audit: Arc::new(InMemoryFaceInfoAuditRepository::default()),
+            face_report: Arc::new(InMemoryFaceReportRepository::default()),
+            database: Arc::new(InMemoryDatabaseRepository::default()),
+        }
+    }
+}
+
+/// Creates the repositories of `storage.backend` in the settings,
+/// and prepares their storage.
+///
+/// The database is retried with backoff until reachable, see `startup` of the settings.
+pub async fn init_repositories() -> RepositoryResult<Repositories> {
+    match config::get().storage.backend {
+        StorageBackend::Mongo => {
+            resource::retry("mongo", resource::check_mongo).await?;
+            info!("Mongo connected successfully.");
+            dao::init_mongo_dao().await?;
+            Ok(Repositories::mongo())
+        }
+        StorageBackend::Sql => {
+            let database_url = config::get().storage.database_url.clone();
+            let pool =
+                resource::retry("database", || resource::sql::connect(&database_url)).await?;
+            let applied_cnt = migration::migrate(&pool).await?;
+            info!(
+                "Sql connected successfully, {} migrations applied.",
+                applied_cnt
+            );
+            Ok(Repositories::sql(pool))
+        }
+    }
+}
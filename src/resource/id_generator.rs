@@ -1,4 +1,3 @@
-use std::env;
 use std::sync::Mutex;
 
 use lazy_static::lazy_static;
@@ -8,14 +7,13 @@ use crate::config;
 
 lazy_static! {
     static ref ID_GENERATOR_BUCKET: Mutex<SnowflakeIdBucket> = Mutex::new({
-        let machine_id: i32 = env::var(config::SNOWFLAKE_MACHINE_ID)
-            .expect("You must set the SNOWFLAKE_MACHINE_ID environment var!")
-            .parse::<i32>()
-            .unwrap();
-        let node_id: i32 = env::var(config::SNOWFLAKE_NODE_ID)
-            .expect("You must set the SNOWFLAKE_NODE_ID environment var!")
-            .parse::<i32>()
-            .unwrap();
+        let snowflake = config::get().snowflake.clone();
+        let machine_id = snowflake
+            .machine_id
+            .expect("You must set snowflake.machine_id in the settings!");
+        let node_id = snowflake
+            .node_id
+            .expect("You must set snowflake.node_id in the settings!");
 
         SnowflakeIdBucket::new(machine_id, node_id)
     });
@@ -25,6 +23,19 @@ pub async fn get_id() -> String {
     ID_GENERATOR_BUCKET.lock().unwrap().get_id().to_string()
 }
 
+/// Like `get_id`, but fails instead of panicking if the generator is unusable.
+pub fn try_get_id() -> Result<String, String> {
+    let id = ID_GENERATOR_BUCKET
+        .lock()
+        .map_err(|err| err.to_string())?
+        .get_id();
+    if id > 0 {
+        Ok(id.to_string())
+    } else {
+        Err(format!("invalid id generated: {}", id))
+    }
+}
+
 #[actix_rt::test]
 async fn generate_id_test() {
     use dotenv::dotenv;
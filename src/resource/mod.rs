@@ -1,24 +1,117 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::config;
+use crate::config::settings::StartupSettings;
 use crate::doc;
 
 pub mod id_generator;
 pub mod mongo;
+pub mod sql;
 
 pub async fn check_resources() {
-    check_mongo().await;
     check_id_generator().await;
 }
 
-async fn check_mongo() {
+pub async fn check_mongo() -> mongodb::error::Result<()> {
     mongo::MONGO_CLIENT
         .get()
-        .await
+        .await?
         .database("admin")
         .run_command(doc! {"ping": 1}, None)
-        .await
-        .unwrap();
-    info!("Mongo connected successfully.");
+        .await?;
+    Ok(())
 }
 
 async fn check_id_generator() {
     info!("Id generate success: {}.", id_generator::get_id().await)
 }
+
+/// Calls `connect` until it succeeds, waiting with exponential backoff between the attempts,
+/// see `startup` of the settings. Returns the last error if the attempts run out.
+pub async fn retry<T, E, F, Fut>(name: &str, connect: F) -> Result<T, E>
+where
+    E: fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    retry_with(name, &config::get().startup, connect).await
+}
+
+async fn retry_with<T, E, F, Fut>(
+    name: &str,
+    startup: &StartupSettings,
+    mut connect: F,
+) -> Result<T, E>
+where
+    E: fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut backoff_ms = startup.initial_backoff_ms;
+    let mut attempt = 1;
+    loop {
+        match connect().await {
+            Ok(value) => return Ok(value),
+            Err(err) if startup.max_attempts == 0 || attempt < startup.max_attempts => {
+                warn!(
+                    "Failed to connect {} (attempt {}), retrying in {}ms, error: {}",
+                    name, attempt, backoff_ms, err
+                );
+                actix_rt::time::sleep(Duration::from_millis(backoff_ms)).await;
+                backoff_ms = backoff_ms.saturating_mul(2).min(startup.max_backoff_ms);
+                attempt += 1;
+            }
+            Err(err) => {
+                error!(
+                    "Failed to connect {} after {} attempts, error: {}",
+                    name, attempt, err
+                );
+                return Err(err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_retry() {
+        let startup = StartupSettings {
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 2,
+        };
+
+        // Succeeds by the second attempt
+        let attempts = Cell::new(0);
+        let res = retry_with("test", &startup, || {
+            attempts.set(attempts.get() + 1);
+            let attempt = attempts.get();
+            async move {
+                if attempt < 2 {
+                    Err("down")
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await;
+        assert_eq!(res, Ok(2));
+
+        // Gives up after the max attempts
+        let attempts = Cell::new(0);
+        let res: Result<(), &str> = retry_with("test", &startup, || {
+            attempts.set(attempts.get() + 1);
+            async { Err("down") }
+        })
+        .await;
+        assert_eq!(res, Err("down"));
+        assert_eq!(attempts.get(), 3);
+    }
+}
@@ -1,14 +1,25 @@
-use std::env;
-
-use async_once::AsyncOnce;
 use lazy_static::lazy_static;
 use mongodb::Client;
+use tokio::sync::OnceCell;
+
+use crate::config;
 
-use crate::config::MONGODB_URI;
+/// The mongo client, built on its first use. A failed build is not kept, so the
+/// next use builds it again, see `resource::retry`.
+pub struct MongoClient {
+    client: OnceCell<Client>,
+}
+
+impl MongoClient {
+    pub async fn get(&self) -> mongodb::error::Result<&Client> {
+        self.client
+            .get_or_try_init(|| async { Client::with_uri_str(&config::get().mongo.uri).await })
+            .await
+    }
+}
 
 lazy_static! {
-    pub static ref MONGO_CLIENT: AsyncOnce<Client> = AsyncOnce::new(async {
-        let uri = env::var(MONGODB_URI).expect("You must set the MONGODB_URI environment var!");
-        Client::with_uri_str(&uri).await.unwrap()
-    });
+    pub static ref MONGO_CLIENT: MongoClient = MongoClient {
+        client: OnceCell::new(),
+    };
 }
@@ -0,0 +1,14 @@
+use sqlx::any::{install_default_drivers, AnyPoolOptions};
+use sqlx::AnyPool;
+
+/// Connects to the relational database of the url, the driver is chosen by the url scheme.
+pub async fn connect(url: &str) -> Result<AnyPool, sqlx::Error> {
+    install_default_drivers();
+
+    // SQLite has a single writer, and every connection of `sqlite::memory:` is a new database
+    let max_connections = if url.starts_with("sqlite:") { 1 } else { 10 };
+    AnyPoolOptions::new()
+        .max_connections(max_connections)
+        .connect(url)
+        .await
+}
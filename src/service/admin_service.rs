@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+use utoipa::ToSchema;
+
+use crate::config::settings::{SettingsError, SettingsReload};
+use crate::entity::file_resource::UriType;
+use crate::repository::{Repositories, RepositoryResult};
+use crate::service::file_resource_service;
+use crate::{config, logger};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct PurgeResult {
+    pub face_info_cnt: u64,
+    pub file_resource_cnt: u64,
+    pub rating_log_cnt: u64,
+    pub category_rating_cnt: u64,
+}
+
+/// Gets the retention window of soft deleted documents from the settings, in days.
+pub fn get_purge_retention_days() -> i64 {
+    config::get().purge.retention_days
+}
+
+/// Reloads the settings, only the rating, limits, logging and moderation sections
+/// take effect, the requests in flight keep the settings they started with.
+pub fn reload_settings() -> Result<SettingsReload, SettingsError> {
+    let reload = config::reload()?;
+    logger::apply(&reload.settings.logging);
+
+    info!(
+        "Settings reloaded, version: {}, changed: {:?}",
+        reload.settings.version, reload.changed
+    );
+    if !reload.ignored.is_empty() {
+        warn!(
+            "Settings changed but only read at startup, restart to apply: {:?}",
+            reload.ignored
+        );
+    }
+    Ok(reload)
+}
+
+/// Reloads the settings on every SIGHUP, runs until the process exits.
+#[cfg(unix)]
+pub async fn reload_settings_on_sighup() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(err) => {
+            error!("Failed to listen to SIGHUP, error: {:?}", err);
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        if let Err(err) = reload_settings() {
+            error!("Failed to reload settings on SIGHUP, error: {}", err);
+        }
+    }
+}
+
+/// Gets the cutoff of `purge_deleted` from the retention window,
+/// none if it is out of the range of timestamps.
+pub fn get_purge_deleted_before(retention_days: i64, now: i64) -> Option<i64> {
+    retention_days
+        .checked_mul(SECONDS_PER_DAY)
+        .and_then(|retention| now.checked_sub(retention))
+}
+
+/// Hard deletes the documents which have been soft deleted before `deleted_before`,
+/// see `get_purge_deleted_before`.
+///
+/// The rating_logs and the category_ratings of the purged face_infos, and the local files
+/// of the purged file_resources are removed as well. The season_snapshots are kept, they
+/// are the standings of the ended seasons and carry the star_names and scores.
+#[instrument(skip_all, fields(deleted_before))]
+pub async fn purge_deleted(
+    repos: &Repositories,
+    deleted_before: i64,
+) -> RepositoryResult<PurgeResult> {
+    // Step 1: Purge face_infos and their rating_logs & category_ratings
+    let face_info_ids: Vec<String> = repos
+        .face_info
+        .get_deleted_face_infos_before(deleted_before)
+        .await?
+        .into_iter()
+        .map(|face_info| face_info.id)
+        .collect();
+
+    let rating_log_cnt = repos
+        .rating_log
+        .purge_rating_logs(&face_info_ids, deleted_before)
+        .await?;
+    let category_rating_cnt = repos
+        .category_rating
+        .delete_category_ratings_by_face_info_ids(&face_info_ids)
+        .await?;
+    let face_info_cnt = repos
+        .face_info
+        .delete_face_infos_by_ids(&face_info_ids)
+        .await?;
+
+    // Step 2: Purge file_resources and their local files
+    let file_resources = repos
+        .file_resource
+        .get_deleted_file_resources_before(deleted_before)
+        .await?;
+    for file_resource in &file_resources {
+        if let UriType::Local = file_resource.uri_type {
+            file_resource_service::delete_file(&file_resource.file_uri).await;
+        }
+    }
+    let file_resource_ids: Vec<String> = file_resources.into_iter().map(|f| f.id).collect();
+    let file_resource_cnt = repos
+        .file_resource
+        .delete_file_resources_by_ids(&file_resource_ids)
+        .await?;
+
+    Ok(PurgeResult {
+        face_info_cnt,
+        file_resource_cnt,
+        rating_log_cnt,
+        category_rating_cnt,
+    })
+}
@@ -0,0 +1,855 @@
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::{env, fmt, fs, io};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::dto::ID_REGEX;
+use crate::entity::category_rating::CategoryRating;
+use crate::entity::face_info::FaceInfo;
+use crate::entity::face_info_audit::FaceInfoAudit;
+use crate::entity::face_report::FaceReport;
+use crate::entity::file_resource::{FileResource, UriType};
+use crate::entity::rating_log::RatingLog;
+use crate::entity::season::Season;
+use crate::entity::season_snapshot::SeasonSnapshot;
+use crate::repository::{Repositories, RepositoryError};
+use crate::service::file_resource_service;
+use crate::utils::sha256;
+
+/// The version of the archive layout, bumped on incompatible changes.
+pub const ARCHIVE_FORMAT_VERSION: u32 = 2;
+
+const MANIFEST_PATH: &str = "manifest.json";
+const BLOB_DIR: &str = "blobs";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format_version: u32,
+    pub created_on: i64,
+    pub collections: Vec<ArchiveCollection>,
+    pub blobs: Vec<ArchiveBlob>,
+}
+
+/// A collection stored as json lines in the archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveCollection {
+    pub name: String,
+    pub path: String,
+    pub count: usize,
+    pub sha256: String,
+}
+
+/// The local file of a file_resource stored in the archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveBlob {
+    pub file_id: String,
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ArchiveContent {
+    pub face_infos: Vec<FaceInfo>,
+    pub file_resources: Vec<FileResource>,
+    pub rating_logs: Vec<RatingLog>,
+    pub category_ratings: Vec<CategoryRating>,
+    pub seasons: Vec<Season>,
+    pub season_snapshots: Vec<SeasonSnapshot>,
+    pub face_info_audits: Vec<FaceInfoAudit>,
+    pub face_reports: Vec<FaceReport>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ArchiveSummary {
+    pub face_info_cnt: usize,
+    pub file_resource_cnt: usize,
+    pub rating_log_cnt: usize,
+    pub category_rating_cnt: usize,
+    pub season_cnt: usize,
+    pub season_snapshot_cnt: usize,
+    pub face_info_audit_cnt: usize,
+    pub face_report_cnt: usize,
+    pub blob_cnt: usize,
+}
+
+#[derive(Debug)]
+pub enum ArchiveError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Repository(RepositoryError),
+    /// The archive is malformed or fails the integrity check
+    Invalid(String),
+    /// The deployment to import into already has data
+    NotEmpty(String),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveError::Io(err) => write!(f, "io error: {}", err),
+            ArchiveError::Json(err) => write!(f, "json error: {}", err),
+            ArchiveError::Repository(err) => write!(f, "{}", err),
+            ArchiveError::Invalid(msg) => write!(f, "invalid archive: {}", msg),
+            ArchiveError::NotEmpty(msg) => write!(f, "deployment is not empty: {}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for ArchiveError {
+    fn from(err: io::Error) -> Self {
+        ArchiveError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ArchiveError {
+    fn from(err: serde_json::Error) -> Self {
+        ArchiveError::Json(err)
+    }
+}
+
+impl From<RepositoryError> for ArchiveError {
+    fn from(err: RepositoryError) -> Self {
+        ArchiveError::Repository(err)
+    }
+}
+
+/// Exports every collection, including the soft deleted documents,
+/// and the local files into a tar archive.
+#[instrument(skip_all, fields(output = ?output))]
+pub async fn export_archive(
+    repos: &Repositories,
+    output: &Path,
+) -> Result<ArchiveSummary, ArchiveError> {
+    let content = ArchiveContent {
+        face_infos: repos.face_info.get_all_face_infos().await?,
+        file_resources: repos.file_resource.get_all_file_resources().await?,
+        rating_logs: repos.rating_log.get_all_rating_logs().await?,
+        category_ratings: repos.category_rating.get_all_category_ratings().await?,
+        seasons: repos.season.get_seasons().await?,
+        season_snapshots: repos.season_snapshot.get_all_season_snapshots().await?,
+        face_info_audits: repos.face_info_audit.get_all_face_info_audits().await?,
+        face_reports: repos.face_report.get_all_face_reports().await?,
+    };
+
+    let manifest = write_archive(output, &content, chrono::Utc::now().timestamp()).await?;
+    Ok(get_archive_summary(&manifest, &content))
+}
+
+/// Imports the archive into an empty deployment.
+///
+/// The whole archive is verified against the hashes of its manifest, and its paths
+/// checked, before anything is written. If the restore fails, what it restored is removed.
+#[instrument(skip_all, fields(input = ?input))]
+pub async fn import_archive(
+    repos: &Repositories,
+    input: &Path,
+) -> Result<ArchiveSummary, ArchiveError> {
+    let staging_dir = StagingDir::new()?;
+
+    // Step 1: Unpack & verify the archive
+    let (manifest, mut content) = read_archive(input, &staging_dir.0).await?;
+
+    // Step 2: The deployment must be empty
+    check_empty_deployment(repos).await?;
+
+    // Step 3: Restore the local files
+    let mut restored_files = Vec::new();
+    let mut res = restore_local_files(&staging_dir.0, &manifest, &mut content, &mut restored_files);
+
+    // Step 4: Restore the documents, all or none of them
+    let summary = get_archive_summary(&manifest, &content);
+    let restored = RestoredIds::of(&content);
+    if res.is_ok() {
+        res = restore_documents(repos, content).await;
+    }
+    if let Err(err) = res {
+        error!("Failed to import archive, rolling back, error: {}", err);
+        rollback_import(repos, &restored, &restored_files).await;
+        return Err(err);
+    }
+
+    Ok(summary)
+}
+
+/// Copies the blobs to the local files of their file_resources,
+/// the copied ones are kept in `restored_files`.
+fn restore_local_files(
+    staging_dir: &Path,
+    manifest: &ArchiveManifest,
+    content: &mut ArchiveContent,
+    restored_files: &mut Vec<String>,
+) -> Result<(), ArchiveError> {
+    for blob in &manifest.blobs {
+        let file_resource = content
+            .file_resources
+            .iter_mut()
+            .find(|file_resource| file_resource.id == blob.file_id)
+            .ok_or_else(|| ArchiveError::Invalid(format!("unknown blob: {}", blob.path)))?;
+        let file_uri =
+            file_resource_service::get_local_filepath(&file_resource.id, &file_resource.file_name);
+        fs::copy(staging_dir.join(&blob.path), &file_uri)?;
+        restored_files.push(file_uri.clone());
+        file_resource.file_uri = file_uri;
+    }
+    Ok(())
+}
+
+/// The ids of the documents an import restores, removed if it fails.
+struct RestoredIds {
+    face_info_ids: Vec<String>,
+    file_resource_ids: Vec<String>,
+    rating_log_ids: Vec<String>,
+    /// The category_ratings are keyed by their face_infos
+    category_rating_face_info_ids: Vec<String>,
+    season_ids: Vec<String>,
+    /// The season_snapshots are keyed by their seasons, the empty one included
+    season_snapshot_season_ids: Vec<String>,
+    face_info_audit_ids: Vec<String>,
+    face_report_ids: Vec<String>,
+}
+
+impl RestoredIds {
+    fn of(content: &ArchiveContent) -> Self {
+        let mut category_rating_face_info_ids: Vec<String> = content
+            .category_ratings
+            .iter()
+            .map(|c| c.face_info_id.clone())
+            .collect();
+        category_rating_face_info_ids.sort();
+        category_rating_face_info_ids.dedup();
+        let mut season_snapshot_season_ids: Vec<String> = content
+            .season_snapshots
+            .iter()
+            .map(|s| s.season_id.clone())
+            .collect();
+        season_snapshot_season_ids.sort();
+        season_snapshot_season_ids.dedup();
+
+        RestoredIds {
+            face_info_ids: content.face_infos.iter().map(|f| f.id.clone()).collect(),
+            file_resource_ids: content
+                .file_resources
+                .iter()
+                .map(|f| f.id.clone())
+                .collect(),
+            rating_log_ids: content.rating_logs.iter().map(|r| r.id.clone()).collect(),
+            category_rating_face_info_ids,
+            season_ids: content.seasons.iter().map(|s| s.id.clone()).collect(),
+            season_snapshot_season_ids,
+            face_info_audit_ids: content
+                .face_info_audits
+                .iter()
+                .map(|a| a.id.clone())
+                .collect(),
+            face_report_ids: content.face_reports.iter().map(|r| r.id.clone()).collect(),
+        }
+    }
+}
+
+async fn restore_documents(
+    repos: &Repositories,
+    content: ArchiveContent,
+) -> Result<(), ArchiveError> {
+    if !content.face_infos.is_empty() {
+        repos.face_info.add_face_infos(content.face_infos).await?;
+    }
+    if !content.file_resources.is_empty() {
+        repos
+            .file_resource
+            .add_file_resources(content.file_resources)
+            .await?;
+    }
+    if !content.rating_logs.is_empty() {
+        repos
+            .rating_log
+            .add_rating_logs(content.rating_logs)
+            .await?;
+    }
+    if !content.category_ratings.is_empty() {
+        repos
+            .category_rating
+            .add_category_ratings(content.category_ratings)
+            .await?;
+    }
+    for season in &content.seasons {
+        repos.season.add_season(season).await?;
+    }
+    if !content.season_snapshots.is_empty() {
+        repos
+            .season_snapshot
+            .save_season_snapshots(content.season_snapshots)
+            .await?;
+    }
+    if !content.face_info_audits.is_empty() {
+        repos
+            .face_info_audit
+            .add_face_info_audits(content.face_info_audits)
+            .await?;
+    }
+    if !content.face_reports.is_empty() {
+        repos
+            .face_report
+            .add_face_reports(content.face_reports)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Removes what a failed import restored. The deployment was empty before,
+/// so only the restored documents and files are removed.
+async fn rollback_import(repos: &Repositories, restored: &RestoredIds, restored_files: &[String]) {
+    let results = [
+        repos
+            .face_info
+            .delete_face_infos_by_ids(&restored.face_info_ids)
+            .await,
+        repos
+            .file_resource
+            .delete_file_resources_by_ids(&restored.file_resource_ids)
+            .await,
+        repos
+            .rating_log
+            .delete_rating_logs_by_ids(&restored.rating_log_ids)
+            .await,
+        repos
+            .category_rating
+            .delete_category_ratings_by_face_info_ids(&restored.category_rating_face_info_ids)
+            .await,
+        repos
+            .season
+            .delete_seasons_by_ids(&restored.season_ids)
+            .await,
+        repos
+            .season_snapshot
+            .delete_season_snapshots_by_season_ids(&restored.season_snapshot_season_ids)
+            .await,
+        repos
+            .face_info_audit
+            .delete_face_info_audits_by_ids(&restored.face_info_audit_ids)
+            .await,
+        repos
+            .face_report
+            .delete_face_reports_by_ids(&restored.face_report_ids)
+            .await,
+    ];
+    for err in results.into_iter().filter_map(Result::err) {
+        error!("Failed to roll back archive import, error: {}", err);
+    }
+    for file_uri in restored_files {
+        file_resource_service::delete_file(file_uri).await;
+    }
+}
+
+/// Writes the content and the local files of its file_resources into a tar archive,
+/// the manifest comes first.
+pub async fn write_archive(
+    output: &Path,
+    content: &ArchiveContent,
+    now: i64,
+) -> Result<ArchiveManifest, ArchiveError> {
+    let collections = vec![
+        (
+            FaceInfo::coll_name(),
+            to_jsonl(&content.face_infos)?,
+            content.face_infos.len(),
+        ),
+        (
+            FileResource::coll_name(),
+            to_jsonl(&content.file_resources)?,
+            content.file_resources.len(),
+        ),
+        (
+            RatingLog::coll_name(),
+            to_jsonl(&content.rating_logs)?,
+            content.rating_logs.len(),
+        ),
+        (
+            CategoryRating::coll_name(),
+            to_jsonl(&content.category_ratings)?,
+            content.category_ratings.len(),
+        ),
+        (
+            Season::coll_name(),
+            to_jsonl(&content.seasons)?,
+            content.seasons.len(),
+        ),
+        (
+            SeasonSnapshot::coll_name(),
+            to_jsonl(&content.season_snapshots)?,
+            content.season_snapshots.len(),
+        ),
+        (
+            FaceInfoAudit::coll_name(),
+            to_jsonl(&content.face_info_audits)?,
+            content.face_info_audits.len(),
+        ),
+        (
+            FaceReport::coll_name(),
+            to_jsonl(&content.face_reports)?,
+            content.face_reports.len(),
+        ),
+    ];
+
+    let mut blobs = Vec::new();
+    let mut blob_files = Vec::new();
+    for file_resource in &content.file_resources {
+        if !matches!(file_resource.uri_type, UriType::Local) {
+            continue;
+        }
+        let metadata = match fs::metadata(&file_resource.file_uri) {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                warn!(
+                    "Skip missing local file, file_id: {:?}, file_uri: {:?}, error: {:?}",
+                    file_resource.id, file_resource.file_uri, err
+                );
+                continue;
+            }
+        };
+        blobs.push(ArchiveBlob {
+            file_id: file_resource.id.clone(),
+            path: format!("{}/{}", BLOB_DIR, file_resource.id),
+            size: metadata.len(),
+            sha256: sha256::get_file_sha256(&file_resource.file_uri).await?,
+        });
+        blob_files.push(file_resource.file_uri.as_str());
+    }
+
+    let manifest = ArchiveManifest {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        created_on: now,
+        collections: collections
+            .iter()
+            .map(|(name, jsonl, count)| ArchiveCollection {
+                name: name.to_string(),
+                path: format!("{}.jsonl", name),
+                count: *count,
+                sha256: sha256::get_bytes_sha256(jsonl),
+            })
+            .collect(),
+        blobs,
+    };
+
+    let mut builder = tar::Builder::new(File::create(output)?);
+    append_bytes(
+        &mut builder,
+        MANIFEST_PATH,
+        &serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    for (collection, (_, jsonl, _)) in manifest.collections.iter().zip(&collections) {
+        append_bytes(&mut builder, &collection.path, jsonl)?;
+    }
+    for (blob, file_uri) in manifest.blobs.iter().zip(blob_files) {
+        builder.append_path_with_name(file_uri, &blob.path)?;
+    }
+    builder.into_inner()?.flush()?;
+
+    Ok(manifest)
+}
+
+/// Unpacks the archive into the staging directory and verifies it against its manifest.
+pub async fn read_archive(
+    input: &Path,
+    staging_dir: &Path,
+) -> Result<(ArchiveManifest, ArchiveContent), ArchiveError> {
+    fs::create_dir_all(staging_dir)?;
+    let mut archive = tar::Archive::new(File::open(input)?);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.unpack_in(staging_dir)? {
+            return Err(ArchiveError::Invalid(format!(
+                "entry outside the archive: {}",
+                entry.path()?.display()
+            )));
+        }
+    }
+
+    let manifest: ArchiveManifest = serde_json::from_reader(
+        File::open(staging_dir.join(MANIFEST_PATH))
+            .map_err(|_| ArchiveError::Invalid("manifest not found".to_string()))?,
+    )?;
+    if manifest.format_version != ARCHIVE_FORMAT_VERSION {
+        return Err(ArchiveError::Invalid(format!(
+            "unsupported format version: {}",
+            manifest.format_version
+        )));
+    }
+
+    let content = ArchiveContent {
+        face_infos: read_collection(staging_dir, &manifest, FaceInfo::coll_name()).await?,
+        file_resources: read_collection(staging_dir, &manifest, FileResource::coll_name()).await?,
+        rating_logs: read_collection(staging_dir, &manifest, RatingLog::coll_name()).await?,
+        category_ratings: read_collection(staging_dir, &manifest, CategoryRating::coll_name())
+            .await?,
+        seasons: read_collection(staging_dir, &manifest, Season::coll_name()).await?,
+        season_snapshots: read_collection(staging_dir, &manifest, SeasonSnapshot::coll_name())
+            .await?,
+        face_info_audits: read_collection(staging_dir, &manifest, FaceInfoAudit::coll_name())
+            .await?,
+        face_reports: read_collection(staging_dir, &manifest, FaceReport::coll_name()).await?,
+    };
+
+    check_archive_content(&manifest, &content)?;
+
+    for blob in &manifest.blobs {
+        let path = verified_path(staging_dir, &blob.path, &blob.sha256).await?;
+        if fs::metadata(path)?.len() != blob.size {
+            return Err(ArchiveError::Invalid(format!(
+                "size mismatch: {}",
+                blob.path
+            )));
+        }
+        if !content
+            .file_resources
+            .iter()
+            .any(|file_resource| file_resource.id == blob.file_id)
+        {
+            return Err(ArchiveError::Invalid(format!(
+                "unknown blob: {}",
+                blob.path
+            )));
+        }
+    }
+
+    Ok((manifest, content))
+}
+
+/// Checks the ids and the file_names of the file_resources, and the paths of the blobs,
+/// which make the paths of the local files, so none of them leads out of its directory.
+fn check_archive_content(
+    manifest: &ArchiveManifest,
+    content: &ArchiveContent,
+) -> Result<(), ArchiveError> {
+    for file_resource in &content.file_resources {
+        if !ID_REGEX.is_match(&file_resource.id) {
+            return Err(ArchiveError::Invalid(format!(
+                "invalid file_resource id: {:?}",
+                file_resource.id
+            )));
+        }
+        if !is_plain_file_name(&file_resource.file_name) {
+            return Err(ArchiveError::Invalid(format!(
+                "invalid file_name: {:?}",
+                file_resource.file_name
+            )));
+        }
+    }
+    for blob in &manifest.blobs {
+        if blob.path != format!("{}/{}", BLOB_DIR, blob.file_id) {
+            return Err(ArchiveError::Invalid(format!(
+                "invalid blob path: {:?}",
+                blob.path
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether the name is a single path component, without separators, `.` or `..`.
+fn is_plain_file_name(file_name: &str) -> bool {
+    !file_name.contains(['/', '\\'])
+        && Path::new(file_name).file_name() == Some(OsStr::new(file_name))
+}
+
+async fn check_empty_deployment(repos: &Repositories) -> Result<(), ArchiveError> {
+    let counts = [
+        (
+            FaceInfo::coll_name(),
+            repos.face_info.count_all_face_infos().await?,
+        ),
+        (
+            FileResource::coll_name(),
+            repos.file_resource.count_all_file_resources().await?,
+        ),
+        (
+            RatingLog::coll_name(),
+            repos.rating_log.count_all_rating_logs().await?,
+        ),
+        (
+            CategoryRating::coll_name(),
+            repos.category_rating.count_all_category_ratings().await?,
+        ),
+        (
+            Season::coll_name(),
+            repos.season.get_seasons().await?.len() as u64,
+        ),
+        (
+            SeasonSnapshot::coll_name(),
+            repos.season_snapshot.count_all_season_snapshots().await?,
+        ),
+        (
+            FaceInfoAudit::coll_name(),
+            repos.face_info_audit.count_all_face_info_audits().await?,
+        ),
+        (
+            FaceReport::coll_name(),
+            repos.face_report.count_all_face_reports().await?,
+        ),
+    ];
+    for (name, count) in counts {
+        if count > 0 {
+            return Err(ArchiveError::NotEmpty(format!(
+                "{} has {} documents",
+                name, count
+            )));
+        }
+    }
+    Ok(())
+}
+
+async fn read_collection<T: DeserializeOwned>(
+    staging_dir: &Path,
+    manifest: &ArchiveManifest,
+    name: &str,
+) -> Result<Vec<T>, ArchiveError> {
+    let collection = manifest
+        .collections
+        .iter()
+        .find(|collection| collection.name == name)
+        .ok_or_else(|| ArchiveError::Invalid(format!("collection not found: {}", name)))?;
+
+    let path = verified_path(staging_dir, &collection.path, &collection.sha256).await?;
+    let docs: Vec<T> = from_jsonl(BufReader::new(File::open(path)?))?;
+    if docs.len() != collection.count {
+        return Err(ArchiveError::Invalid(format!(
+            "count mismatch: {}, expect {}, got {}",
+            collection.path,
+            collection.count,
+            docs.len()
+        )));
+    }
+    Ok(docs)
+}
+
+/// Gets the path of the archive entry, checking its sha256.
+async fn verified_path(
+    staging_dir: &Path,
+    path: &str,
+    expected_sha256: &str,
+) -> Result<PathBuf, ArchiveError> {
+    let full_path = staging_dir.join(path);
+    let actual_sha256 = sha256::get_file_sha256(&full_path.to_string_lossy())
+        .await
+        .map_err(|_| ArchiveError::Invalid(format!("entry not found: {}", path)))?;
+    if actual_sha256 != expected_sha256 {
+        return Err(ArchiveError::Invalid(format!("sha256 mismatch: {}", path)));
+    }
+    Ok(full_path)
+}
+
+fn get_archive_summary(manifest: &ArchiveManifest, content: &ArchiveContent) -> ArchiveSummary {
+    ArchiveSummary {
+        face_info_cnt: content.face_infos.len(),
+        file_resource_cnt: content.file_resources.len(),
+        rating_log_cnt: content.rating_logs.len(),
+        category_rating_cnt: content.category_ratings.len(),
+        season_cnt: content.seasons.len(),
+        season_snapshot_cnt: content.season_snapshots.len(),
+        face_info_audit_cnt: content.face_info_audits.len(),
+        face_report_cnt: content.face_reports.len(),
+        blob_cnt: manifest.blobs.len(),
+    }
+}
+
+fn append_bytes<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    bytes: &[u8],
+) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp() as u64);
+    builder.append_data(&mut header, path, bytes)
+}
+
+fn to_jsonl<T: Serialize>(docs: &[T]) -> Result<Vec<u8>, serde_json::Error> {
+    let mut jsonl = Vec::new();
+    for doc in docs {
+        serde_json::to_writer(&mut jsonl, doc)?;
+        jsonl.push(b'\n');
+    }
+    Ok(jsonl)
+}
+
+fn from_jsonl<T: DeserializeOwned, R: BufRead>(reader: R) -> Result<Vec<T>, ArchiveError> {
+    let mut docs = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        docs.push(serde_json::from_str(&line)?);
+    }
+    Ok(docs)
+}
+
+/// A temporary directory removed on drop.
+struct StagingDir(PathBuf);
+
+impl StagingDir {
+    fn new() -> io::Result<Self> {
+        let dir = env::temp_dir().join(format!("facemash-archive-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir)?;
+        Ok(StagingDir(dir))
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.0) {
+            error!(
+                "Failed to remove staging dir: {:?}, error: {:?}",
+                self.0, err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_write_and_read_archive() {
+        let content = ArchiveContent {
+            face_infos: vec![FaceInfo {
+                id: "1".to_string(),
+                file_id: "2".to_string(),
+                star_name: "John Doe".to_string(),
+                ..FaceInfo::default()
+            }],
+            file_resources: vec![FileResource {
+                id: "2".to_string(),
+                file_name: "README.md".to_string(),
+                file_uri: "./README.md".to_string(),
+                ..FileResource::default()
+            }],
+            rating_logs: vec![],
+            category_ratings: vec![CategoryRating {
+                category: "actors".to_string(),
+                face_info_id: "1".to_string(),
+                score: 1510.0,
+                ..CategoryRating::default()
+            }],
+            seasons: vec![Season {
+                id: "3".to_string(),
+                name: "Spring".to_string(),
+                ..Season::default()
+            }],
+            season_snapshots: vec![SeasonSnapshot {
+                season_id: "3".to_string(),
+                face_info_id: "1".to_string(),
+                score: 1520.0,
+                ..SeasonSnapshot::default()
+            }],
+            face_info_audits: vec![FaceInfoAudit {
+                id: "4".to_string(),
+                face_info_id: "1".to_string(),
+                field: "star_name".to_string(),
+                ..FaceInfoAudit::default()
+            }],
+            face_reports: vec![FaceReport {
+                id: "5".to_string(),
+                face_info_id: "1".to_string(),
+                reporter: "127.0.0.1".to_string(),
+                ..FaceReport::default()
+            }],
+        };
+
+        let staging_dir = StagingDir::new().unwrap();
+        let archive = staging_dir.0.join("archive.tar");
+        let manifest = write_archive(&archive, &content, 0).await.unwrap();
+        assert_eq!(manifest.blobs.len(), 1);
+
+        let unpack_dir = staging_dir.0.join("unpack");
+        let (read_manifest, read_content) = read_archive(&archive, &unpack_dir).await.unwrap();
+        assert_eq!(read_manifest.blobs[0].sha256, manifest.blobs[0].sha256);
+        assert_eq!(read_content.face_infos[0].star_name, "John Doe");
+        assert_eq!(read_content.file_resources[0].id, "2");
+        assert!(read_content.rating_logs.is_empty());
+        assert_eq!(read_content.category_ratings[0].score, 1510.0);
+        assert_eq!(read_content.seasons[0].name, "Spring");
+        assert_eq!(read_content.season_snapshots[0].score, 1520.0);
+        assert_eq!(read_content.face_info_audits[0].field, "star_name");
+        assert_eq!(read_content.face_reports[0].reporter, "127.0.0.1");
+
+        // The restored collections make the deployment not empty
+        let repos = Repositories::in_memory();
+        check_empty_deployment(&repos).await.unwrap();
+        let restored = RestoredIds::of(&read_content);
+        restore_documents(&repos, read_content).await.unwrap();
+        let summary = get_archive_summary(
+            &read_manifest,
+            &ArchiveContent {
+                category_ratings: repos
+                    .category_rating
+                    .get_all_category_ratings()
+                    .await
+                    .unwrap(),
+                seasons: repos.season.get_seasons().await.unwrap(),
+                season_snapshots: repos
+                    .season_snapshot
+                    .get_all_season_snapshots()
+                    .await
+                    .unwrap(),
+                face_info_audits: repos
+                    .face_info_audit
+                    .get_all_face_info_audits()
+                    .await
+                    .unwrap(),
+                face_reports: repos.face_report.get_all_face_reports().await.unwrap(),
+                ..ArchiveContent::default()
+            },
+        );
+        assert_eq!(
+            (
+                summary.category_rating_cnt,
+                summary.season_cnt,
+                summary.season_snapshot_cnt,
+                summary.face_info_audit_cnt,
+                summary.face_report_cnt
+            ),
+            (1, 1, 1, 1, 1)
+        );
+        assert!(matches!(
+            check_empty_deployment(&repos).await,
+            Err(ArchiveError::NotEmpty(_))
+        ));
+
+        // The rollback removes all of them
+        rollback_import(&repos, &restored, &[]).await;
+        check_empty_deployment(&repos).await.unwrap();
+
+        // A tampered entry fails the integrity check
+        fs::write(unpack_dir.join(&manifest.blobs[0].path), b"tampered").unwrap();
+        let tampered = staging_dir.0.join("tampered.tar");
+        let mut builder = tar::Builder::new(File::create(&tampered).unwrap());
+        builder.append_dir_all(".", &unpack_dir).unwrap();
+        builder.into_inner().unwrap();
+        assert!(matches!(
+            read_archive(&tampered, &staging_dir.0.join("tampered")).await,
+            Err(ArchiveError::Invalid(_))
+        ));
+
+        // A file_name leading out of the storage is refused
+        let mut escaping = content;
+        escaping.file_resources[0].file_name = "../../etc/cron.d/job".to_string();
+        let archive = staging_dir.0.join("escaping.tar");
+        write_archive(&archive, &escaping, 0).await.unwrap();
+        assert!(matches!(
+            read_archive(&archive, &staging_dir.0.join("escaping")).await,
+            Err(ArchiveError::Invalid(_))
+        ));
+        assert!(is_plain_file_name("face.jpg"));
+        assert!(!is_plain_file_name(".."));
+        assert!(!is_plain_file_name("a\\b.jpg"));
+        assert!(!is_plain_file_name(""));
+    }
+}
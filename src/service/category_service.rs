@@ -0,0 +1,89 @@
+use tracing::instrument;
+
+use crate::config::settings::RatingSettings;
+use crate::entity::category_rating::CategoryRating;
+use crate::entity::face_info::{FaceInfo, FaceStatus};
+use crate::repository::{Repositories, RepositoryResult};
+use crate::service::face_info_service;
+
+/// Rates the winner and the loser of a vote in the category by their ratings in it,
+/// apart from their overall scores. The ratings start at `rating.initial_score`.
+#[instrument(skip_all, fields(category))]
+pub async fn rate_category_vote(
+    repos: &Repositories,
+    category: &str,
+    win_face_info_id: &str,
+    lose_face_info_id: &str,
+    rating: &RatingSettings,
+    now: i64,
+) -> RepositoryResult<(f64, f64)> {
+    let category_ratings = repos
+        .category_rating
+        .get_category_ratings(category, &[win_face_info_id, lose_face_info_id])
+        .await?;
+    let get_category_rating = |face_info_id: &str| {
+        category_ratings
+            .iter()
+            .find(|category_rating| category_rating.face_info_id == face_info_id)
+            .cloned()
+            .unwrap_or_else(|| CategoryRating {
+                category: category.to_string(),
+                face_info_id: face_info_id.to_string(),
+                score: rating.initial_score,
+                ..CategoryRating::default()
+            })
+    };
+
+    let (win_score, lose_score) = face_info_service::get_vote_scores(
+        &get_category_rating(win_face_info_id),
+        &get_category_rating(lose_face_info_id),
+        rating,
+    );
+    repos
+        .category_rating
+        .update_category_rating(category, win_face_info_id, win_score, true, now)
+        .await?;
+    repos
+        .category_rating
+        .update_category_rating(category, lose_face_info_id, lose_score, false, now)
+        .await?;
+    Ok((win_score, lose_score))
+}
+
+/// Gets a page of the leaderboard of the category, the highest rating first.
+///
+/// The face_infos which have left the category, or are no longer approved, keep
+/// their ratings but are skipped, so a page may come short.
+#[instrument(skip_all, fields(category, page, page_size))]
+pub async fn get_category_leaderboard(
+    repos: &Repositories,
+    category: &str,
+    page: u64,
+    page_size: i64,
+) -> RepositoryResult<Vec<(FaceInfo, CategoryRating)>> {
+    let category_ratings = repos
+        .category_rating
+        .get_category_ratings_page(category, page, page_size)
+        .await?;
+    let face_info_ids: Vec<&str> = category_ratings
+        .iter()
+        .map(|category_rating| category_rating.face_info_id.as_str())
+        .collect();
+    let face_infos = repos
+        .face_info
+        .get_face_infos_by_ids(&face_info_ids, FaceStatus::Approved)
+        .await?;
+
+    Ok(category_ratings
+        .into_iter()
+        .filter_map(|category_rating| {
+            face_infos
+                .iter()
+                .find(|face_info| {
+                    face_info.id == category_rating.face_info_id
+                        && face_info.categories.iter().any(|c| c == category)
+                })
+                .map(|face_info| (face_info.clone(), category_rating))
+        })
+        .collect())
+}
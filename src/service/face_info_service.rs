@@ -1,50 +1,407 @@
-use mongodb::bson::Document;
-use mongodb::results::{InsertOneResult, UpdateResult};
+use tracing::instrument;
 
-use crate::dao::face_info_dao;
-use crate::doc;
-use crate::entity::face_info::FaceInfo;
+use crate::algorithm::elo_rating::{
+    compete, compete_fide, compete_icc, compete_uscf, EloScore, WIN,
+};
+use crate::algorithm::fuzzy_match;
+use crate::config::settings::{KFactor, RatingSettings};
+use crate::entity::category_rating::CategoryRating;
+use crate::entity::face_info::{FaceInfo, FaceInfoOrder, FaceInfoPatch, FaceStatus};
+use crate::entity::face_info_audit::FaceInfoAudit;
+use crate::repository::{get_page_skip, Repositories, RepositoryResult};
+use crate::resource;
 
-pub async fn get_face_info_randomly(size: i64) -> Result<Vec<FaceInfo>, mongodb::error::Error> {
-    face_info_dao::get_face_info_sample(size).await
+/// The most candidates of the fuzzy part of a search to rank, the ones sharing
+/// fewer trigrams are cut. The prefix matches are not capped.
+const MAX_SEARCH_CANDIDATES: i64 = 500;
+/// The least similarity of a fuzzy match, see `fuzzy_match::similarity`.
+const MIN_SEARCH_SIMILARITY: f64 = 0.5;
+
+/// A page of the face_infos matching a search, with the count of all the matches,
+/// of which the fuzzy ones are at most `MAX_SEARCH_CANDIDATES`.
+#[derive(Debug, Clone, Default)]
+pub struct FaceInfoSearchPage {
+    pub face_infos: Vec<FaceInfo>,
+    pub total: u64,
+}
+
+/// A changed field of a face_info.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaceInfoChange {
+    pub field: &'static str,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+#[instrument(skip_all, fields(size, category))]
+pub async fn get_face_info_randomly(
+    repos: &Repositories,
+    size: i64,
+    category: Option<&str>,
+) -> RepositoryResult<Vec<FaceInfo>> {
+    repos.face_info.get_face_info_sample(size, category).await
+}
+
+#[instrument(skip_all, fields(face_info.id = %face_info_id))]
+pub async fn get_face_info_by_id(
+    repos: &Repositories,
+    face_info_id: &str,
+) -> RepositoryResult<Option<FaceInfo>> {
+    repos.face_info.get_face_info_by_id(face_info_id).await
 }
 
-pub async fn get_one_face_info_by_doc_filter(
-    doc_filter: Document,
-) -> mongodb::error::Result<Option<FaceInfo>> {
-    face_info_dao::get_one_face_info_by_doc_filter(doc_filter).await
+#[instrument(skip_all, fields(file.id = %file_id))]
+pub async fn get_face_info_by_file_id(
+    repos: &Repositories,
+    file_id: &str,
+) -> RepositoryResult<Option<FaceInfo>> {
+    repos.face_info.get_face_info_by_file_id(file_id).await
 }
 
-pub async fn get_face_infos_by_doc_filter(
-    doc_filter: Document,
-) -> Result<Vec<FaceInfo>, mongodb::error::Error> {
-    face_info_dao::get_face_infos_by_doc_filter(doc_filter).await
+#[instrument(skip_all, fields(face_info.ids = ?face_info_ids))]
+pub async fn get_approved_face_infos_by_ids(
+    repos: &Repositories,
+    face_info_ids: &[&str],
+) -> RepositoryResult<Vec<FaceInfo>> {
+    repos
+        .face_info
+        .get_face_infos_by_ids(face_info_ids, FaceStatus::Approved)
+        .await
 }
 
-pub async fn add_face_info(face_info: &FaceInfo) -> mongodb::error::Result<InsertOneResult> {
-    face_info_dao::add_one_face_info(face_info).await
+#[instrument(skip_all, fields(?order, page, page_size))]
+pub async fn get_approved_face_infos(
+    repos: &Repositories,
+    order: FaceInfoOrder,
+    page: u64,
+    page_size: i64,
+) -> RepositoryResult<Vec<FaceInfo>> {
+    repos
+        .face_info
+        .get_approved_face_infos(order, page, page_size)
+        .await
 }
 
+#[instrument(skip_all)]
+pub async fn count_approved_face_infos(repos: &Repositories) -> RepositoryResult<u64> {
+    repos
+        .face_info
+        .count_face_infos_by_status(FaceStatus::Approved)
+        .await
+}
+
+/// Searches the approved face_infos by star_name, ignoring the case. The names starting
+/// with the query, or with a word starting with it, come first, the highest score first,
+/// then the ones alike despite typos, the most similar first.
+///
+/// The prefix matches are looked up by the index, all of them. The fuzzy ones are ranked
+/// among the `MAX_SEARCH_CANDIDATES` sharing the most trigrams.
+#[instrument(skip_all, fields(query, page, page_size))]
+pub async fn search_face_infos(
+    repos: &Repositories,
+    query: &str,
+    page: u64,
+    page_size: i64,
+) -> RepositoryResult<FaceInfoSearchPage> {
+    // Step 1: Count the prefix matches, they come first
+    let has_prefix = !fuzzy_match::normalize_prefix(query).is_empty();
+    let prefix_cnt = if has_prefix {
+        repos.face_info.count_face_infos_by_prefix(query).await?
+    } else {
+        0
+    };
+
+    // Step 2: Rank the fuzzy matches, apart from the prefix ones
+    let trigrams = fuzzy_match::trigrams(query);
+    let candidates = repos
+        .face_info
+        .search_face_infos_by_trigrams(&trigrams, MAX_SEARCH_CANDIDATES)
+        .await?;
+    let mut fuzzy_matches: Vec<(f64, FaceInfo)> = candidates
+        .into_iter()
+        .filter(|face_info| !fuzzy_match::is_prefix(query, &face_info.star_name))
+        .map(|face_info| {
+            let similarity = fuzzy_match::similarity(query, &face_info.star_name);
+            (similarity, face_info)
+        })
+        .filter(|(similarity, _)| *similarity >= MIN_SEARCH_SIMILARITY)
+        .collect();
+    fuzzy_matches.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.id.cmp(&b.1.id)));
+    let fuzzy_cnt = fuzzy_matches.len() as u64;
+
+    // Step 3: Take the page from the prefix matches, then from the fuzzy ones
+    let page_size = page_size.max(0);
+    let skip = get_page_skip(page, page_size);
+    let mut face_infos = if has_prefix && skip < prefix_cnt {
+        repos
+            .face_info
+            .search_face_infos_by_prefix(query, page, page_size)
+            .await?
+    } else {
+        vec![]
+    };
+    let fuzzy_skip = skip.saturating_sub(prefix_cnt) as usize;
+    let fuzzy_take = (page_size as usize).saturating_sub(face_infos.len());
+    face_infos.extend(
+        fuzzy_matches
+            .into_iter()
+            .skip(fuzzy_skip)
+            .take(fuzzy_take)
+            .map(|(_, face_info)| face_info),
+    );
+
+    Ok(FaceInfoSearchPage {
+        total: prefix_cnt + fuzzy_cnt,
+        face_infos,
+    })
+}
+
+#[instrument(skip_all, fields(face_info.id = %face_info.id))]
+pub async fn add_face_info(repos: &Repositories, face_info: &FaceInfo) -> RepositoryResult<()> {
+    repos.face_info.add_face_info(face_info).await
+}
+
+#[instrument(skip_all, fields(face_info.id = %face_info_id, upvote))]
 pub async fn update_face_info_rating(
+    repos: &Repositories,
     face_info_id: &str,
     rating: f64,
     upvote: bool,
     voter: &str,
     now: i64,
-) -> mongodb::error::Result<UpdateResult> {
-    let filter_doc = doc! {"id": face_info_id};
-
-    let update_doc = if upvote {
-        doc! {
-            "$set": {"score": rating, "updater": voter, "updated_on": now},
-            "$inc": {"upvote_count": 1},
-        }
-    } else {
-        doc! {
-            "$set": {"score": rating, "updater": voter, "updated_on": now},
-            "$inc": {"downvote_count": 1},
-        }
+) -> RepositoryResult<bool> {
+    repos
+        .face_info
+        .update_face_info_rating(face_info_id, rating, upvote, voter, now)
+        .await
+}
+
+/// A contender of a vote, rated overall or within a category.
+pub trait Rated {
+    fn score(&self) -> f64;
+
+    /// The count of the votes it has had, the k_factor of FIDE drops with it
+    fn vote_cnt(&self) -> u64;
+}
+
+impl Rated for FaceInfo {
+    fn score(&self) -> f64 {
+        self.score
+    }
+
+    fn vote_cnt(&self) -> u64 {
+        self.upvote_count + self.downvote_count
+    }
+}
+
+impl Rated for CategoryRating {
+    fn score(&self) -> f64 {
+        self.score
+    }
+
+    fn vote_cnt(&self) -> u64 {
+        self.upvote_count + self.downvote_count
+    }
+}
+
+/// Calculates the new scores of the winner and the loser of a vote.
+pub fn get_vote_scores<R: Rated>(winner: &R, loser: &R, rating: &RatingSettings) -> (f64, f64) {
+    let win_score = winner.score() as EloScore;
+    let lose_score = loser.score() as EloScore;
+    let (win_score, lose_score) = match rating.k_factor {
+        KFactor::Uscf => compete_uscf(win_score, lose_score, WIN),
+        KFactor::Fide => compete_fide(
+            win_score,
+            winner.vote_cnt(),
+            lose_score,
+            loser.vote_cnt(),
+            WIN,
+        ),
+        KFactor::Icc => compete_icc(win_score, lose_score, WIN),
+        KFactor::Fixed => compete(win_score, lose_score, WIN, rating.fixed_k, rating.fixed_k),
     };
+    (win_score as f64, lose_score as f64)
+}
+
+#[instrument(skip_all, fields(face_info.id = %face_info_id))]
+pub async fn soft_delete_face_info(
+    repos: &Repositories,
+    face_info_id: &str,
+    operator: &str,
+    now: i64,
+) -> RepositoryResult<bool> {
+    repos
+        .face_info
+        .soft_delete_face_info(face_info_id, operator, now)
+        .await
+}
+
+#[instrument(skip_all, fields(face_info.id = %face_info_id))]
+pub async fn restore_face_info(
+    repos: &Repositories,
+    face_info_id: &str,
+    operator: &str,
+    now: i64,
+) -> RepositoryResult<bool> {
+    repos
+        .face_info
+        .restore_face_info(face_info_id, operator, now)
+        .await
+}
+
+/// Gets the changes the patch makes to the face_info, the unchanged fields are skipped.
+///
+/// The categories are compared, and audited, comma separated.
+pub fn get_face_info_changes(face_info: &FaceInfo, patch: &FaceInfoPatch) -> Vec<FaceInfoChange> {
+    let old_categories = face_info.categories.join(",");
+    let new_categories = patch
+        .categories
+        .as_ref()
+        .map(|categories| categories.join(","));
+    [
+        (
+            "star_name",
+            face_info.star_name.as_str(),
+            patch.star_name.as_deref(),
+        ),
+        (
+            "file_id",
+            face_info.file_id.as_str(),
+            patch.file_id.as_deref(),
+        ),
+        (
+            "categories",
+            old_categories.as_str(),
+            new_categories.as_deref(),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(field, old_value, new_value)| match new_value {
+        Some(new_value) if new_value != old_value => Some(FaceInfoChange {
+            field,
+            old_value: old_value.to_string(),
+            new_value: new_value.to_string(),
+        }),
+        _ => None,
+    })
+    .collect()
+}
+
+/// Applies the patch to the face_info and writes an audit for each changed field.
+///
+/// The update only matches if the patched fields still hold their old values,
+/// so the audits never record a stale old value.
+#[instrument(skip_all, fields(face_info.id = %face_info.id))]
+pub async fn update_face_info(
+    repos: &Repositories,
+    face_info: &FaceInfo,
+    patch: &FaceInfoPatch,
+    updater: &str,
+    now: i64,
+) -> RepositoryResult<bool> {
+    let changes = get_face_info_changes(face_info, patch);
+
+    let updated = repos
+        .face_info
+        .update_face_info_by_patch(face_info, patch, updater, now)
+        .await?;
+    if updated {
+        add_face_info_audits(repos, &face_info.id, &changes, updater, now).await?;
+    }
+
+    Ok(updated)
+}
+
+/// Writes an audit for each change of the face_info.
+#[instrument(skip_all, fields(face_info.id = %face_info_id, changes = changes.len()))]
+pub async fn add_face_info_audits(
+    repos: &Repositories,
+    face_info_id: &str,
+    changes: &[FaceInfoChange],
+    operator: &str,
+    now: i64,
+) -> RepositoryResult<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let mut face_info_audits = Vec::with_capacity(changes.len());
+    for change in changes {
+        face_info_audits.push(FaceInfoAudit {
+            id: resource::id_generator::get_id().await,
+            face_info_id: face_info_id.to_string(),
+            field: change.field.to_string(),
+            old_value: change.old_value.clone(),
+            new_value: change.new_value.clone(),
+            creator: operator.to_string(),
+            created_on: now,
+            ..FaceInfoAudit::default()
+        });
+    }
+    repos
+        .face_info_audit
+        .add_face_info_audits(face_info_audits)
+        .await
+}
+
+#[instrument(skip_all, fields(face_info.id = %face_info_id))]
+pub async fn get_face_info_audits(
+    repos: &Repositories,
+    face_info_id: &str,
+) -> RepositoryResult<Vec<FaceInfoAudit>> {
+    repos
+        .face_info_audit
+        .get_face_info_audits_by_face_info_id(face_info_id)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_face_info_changes() {
+        let face_info = FaceInfo {
+            star_name: "Jon Doe".to_string(),
+            file_id: "1".to_string(),
+            ..FaceInfo::default()
+        };
+
+        let patch = FaceInfoPatch {
+            star_name: Some("John Doe".to_string()),
+            file_id: Some("1".to_string()),
+            categories: Some(vec!["actors".to_string(), "singers".to_string()]),
+        };
+        assert_eq!(
+            get_face_info_changes(&face_info, &patch),
+            vec![
+                FaceInfoChange {
+                    field: "star_name",
+                    old_value: "Jon Doe".to_string(),
+                    new_value: "John Doe".to_string(),
+                },
+                FaceInfoChange {
+                    field: "categories",
+                    old_value: "".to_string(),
+                    new_value: "actors,singers".to_string(),
+                },
+            ]
+        );
+
+        assert!(get_face_info_changes(&face_info, &FaceInfoPatch::default()).is_empty());
+    }
 
-    face_info_dao::update_face_info_by_doc_filter(filter_doc, update_doc).await
+    #[test]
+    fn test_get_vote_scores() {
+        let face_info = FaceInfo::default();
+        let rating = RatingSettings {
+            k_factor: KFactor::Fixed,
+            fixed_k: 10,
+            ..RatingSettings::default()
+        };
+        assert_eq!(
+            get_vote_scores(&face_info, &face_info, &rating),
+            (face_info.score + 5.0, face_info.score - 5.0)
+        );
+    }
 }
@@ -1,26 +1,95 @@
-use std::fs;
 use std::fs::File;
 use std::io::Write;
+use std::{fmt, fs, io};
 
 use actix_multipart::Multipart;
-use actix_web::{error, web, Error};
+use actix_web::web;
 use futures_util::TryStreamExt as _;
-use mongodb::bson::Document;
-use mongodb::results::InsertOneResult;
+use tracing::instrument;
 
-use crate::dao::file_resource_dao;
 use crate::entity::file_resource::FileResource;
+use crate::error::{AppError, AppResult};
+use crate::repository::{Repositories, RepositoryError, RepositoryResult};
+use crate::{config, metrics, utils};
 
-const SAVE_DIR: &str = "./tmp";
+#[derive(Debug)]
+pub enum SaveFileResourceError {
+    /// A file_resource with the same md5 has already been saved
+    Duplicated {
+        file_id: String,
+    },
+    Io(io::Error),
+    Repository(RepositoryError),
+}
+
+impl fmt::Display for SaveFileResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveFileResourceError::Duplicated { file_id } => {
+                write!(f, "file has already been saved, file_id: {}", file_id)
+            }
+            SaveFileResourceError::Io(err) => write!(f, "io error: {}", err),
+            SaveFileResourceError::Repository(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// The suffix of the files being uploaded, renamed to their filepaths once complete
+const PARTIAL_FILE_SUFFIX: &str = ".part";
+
+/// Creates the directory of the uploaded files, and removes the uploads cut by the last run.
+pub async fn init_local_directory() -> std::io::Result<()> {
+    fs::create_dir_all(&config::get().storage.save_dir)?;
+    let removed_cnt = remove_partial_files()?;
+    if removed_cnt > 0 {
+        info!("Removed {} partially written uploads.", removed_cnt);
+    }
+    Ok(())
+}
 
-pub async fn init_local_directory() {
-    fs::create_dir_all(SAVE_DIR).unwrap()
+/// Removes the partially written uploads left in the directory, returns the removed count.
+pub fn remove_partial_files() -> io::Result<usize> {
+    let mut removed_cnt = 0;
+    for entry in fs::read_dir(&config::get().storage.save_dir)? {
+        let path = entry?.path();
+        if path.to_string_lossy().ends_with(PARTIAL_FILE_SUFFIX) {
+            fs::remove_file(&path)?;
+            removed_cnt += 1;
+        }
+    }
+    Ok(removed_cnt)
+}
+
+/// An upload being written, removed when dropped unless completed,
+/// so a failed or cut upload leaves no file behind.
+struct PartialFile {
+    path: String,
+    is_completed: bool,
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        if !self.is_completed {
+            if let Err(err) = fs::remove_file(&self.path) {
+                if err.kind() != io::ErrorKind::NotFound {
+                    error!("Failed to remove file: {:?}, error: {:?}", self.path, err);
+                }
+            }
+        }
+    }
 }
 
+/// Writes the files of the multipart stream to their local filepaths, returns the last filename.
+///
+/// A file is written to `<filepath>.part` and renamed once complete, the partial file is
+/// removed if the upload fails or is cut. Fails with 413 if a file exceeds
+/// `limits.max_upload_bytes`.
+#[instrument(skip_all, fields(file.id = %file_prefix_id))]
 pub async fn create_file_resource_with_stream(
     mut payload: Multipart,
     file_prefix_id: &str,
-) -> Result<String, Error> {
+) -> AppResult<String> {
+    let max_upload_bytes = config::get().limits.max_upload_bytes;
     let mut filename: String = "".to_string();
 
     // iterate over multipart stream
@@ -30,8 +99,8 @@ pub async fn create_file_resource_with_stream(
 
         filename = match content_disposition.get_filename() {
             None => {
-                return Err(error::ErrorInternalServerError(
-                    "Couldn't read the filename.",
+                return Err(AppError::Validation(
+                    "Couldn't read the filename.".to_string(),
                 ));
             }
             Some(f_name) => {
@@ -41,30 +110,145 @@ pub async fn create_file_resource_with_stream(
         };
 
         let filepath = get_local_filepath(file_prefix_id, &filename);
+        let mut partial_file = PartialFile {
+            path: format!("{}{}", filepath, PARTIAL_FILE_SUFFIX),
+            is_completed: false,
+        };
 
         // File::create is blocking operation, use threadpool
-        let mut f = web::block(|| File::create(filepath)).await??;
+        let cloned_filepath = partial_file.path.clone();
+        let mut f = web::block(|| File::create(cloned_filepath)).await??;
 
         // Field in turn is stream of *Bytes* object
+        let mut written_bytes = 0;
         while let Some(chunk) = field.try_next().await? {
+            written_bytes += chunk.len() as u64;
+            if written_bytes > max_upload_bytes {
+                return Err(AppError::PayloadTooLarge(format!(
+                    "The file exceeds {} bytes.",
+                    max_upload_bytes
+                )));
+            }
             // filesystem operations are blocking, we have to use threadpool
             f = web::block(move || f.write_all(&chunk).map(|_| f)).await??;
         }
+
+        drop(f);
+        let cloned_filepath = partial_file.path.clone();
+        web::block(|| fs::rename(cloned_filepath, filepath)).await??;
+        partial_file.is_completed = true;
     }
 
     Ok(filename)
 }
 
+/// Saves the file_resource of a file already written to its local filepath.
+///
+/// The md5 of the file is calculated, and the file is removed if a file_resource
+/// with the same md5 has already been saved.
+#[instrument(skip_all, fields(file.id = %file_resource_id))]
+pub async fn save_local_file_resource(
+    repos: &Repositories,
+    file_resource_id: &str,
+    file_name: &str,
+    creator: &str,
+) -> Result<FileResource, SaveFileResourceError> {
+    // Step 1: Calculate md5 hash
+    let file_uri = get_local_filepath(file_resource_id, file_name);
+    let file_md5 = utils::md5::get_file_md5(&file_uri)
+        .await
+        .map_err(SaveFileResourceError::Io)?;
+
+    // Step 2: Check file md5 is repeated
+    match repos
+        .file_resource
+        .get_file_resource_by_md5(&file_md5)
+        .await
+    {
+        Ok(None) => {}
+        Ok(Some(file_resource)) => {
+            metrics::DUPLICATE_UPLOADS_TOTAL.inc();
+            delete_file(&file_uri).await;
+            info!(
+                "Delete duplicated file success, file_name: {:?}, md5: {:?}",
+                file_name, file_md5
+            );
+            return Err(SaveFileResourceError::Duplicated {
+                file_id: file_resource.id,
+            });
+        }
+        Err(err) => {
+            delete_file(&file_uri).await;
+            return Err(SaveFileResourceError::Repository(err));
+        }
+    };
+
+    // Step 3：Save file_resource
+    let file_size = fs::metadata(&file_uri)
+        .map(|metadata| metadata.len())
+        .unwrap_or_default();
+    let file_resource = FileResource {
+        id: file_resource_id.to_string(),
+        file_name: file_name.to_string(),
+        file_uri,
+        md5: file_md5,
+        creator: creator.to_string(),
+        created_on: chrono::Utc::now().timestamp(),
+        ..FileResource::default()
+    };
+    if let Err(err) = create_file_resource(repos, &file_resource).await {
+        delete_file(&file_resource.file_uri).await;
+        return Err(SaveFileResourceError::Repository(err));
+    }
+    metrics::UPLOADS_TOTAL.inc();
+    metrics::UPLOADED_BYTES_TOTAL.inc_by(file_size);
+
+    Ok(file_resource)
+}
+
+#[instrument(skip_all, fields(file.id = %file_resource.id))]
 pub async fn create_file_resource(
+    repos: &Repositories,
     file_resource: &FileResource,
-) -> mongodb::error::Result<InsertOneResult> {
-    file_resource_dao::add_one_file_resource(file_resource).await
+) -> RepositoryResult<()> {
+    repos.file_resource.add_file_resource(file_resource).await
+}
+
+#[instrument(skip_all, fields(file.id = %file_resource_id))]
+pub async fn get_file_resource_by_id(
+    repos: &Repositories,
+    file_resource_id: &str,
+) -> RepositoryResult<Option<FileResource>> {
+    repos
+        .file_resource
+        .get_file_resource_by_id(file_resource_id)
+        .await
+}
+
+#[instrument(skip_all, fields(file.id = %file_resource_id))]
+pub async fn soft_delete_file_resource(
+    repos: &Repositories,
+    file_resource_id: &str,
+    operator: &str,
+    now: i64,
+) -> RepositoryResult<bool> {
+    repos
+        .file_resource
+        .soft_delete_file_resource(file_resource_id, operator, now)
+        .await
 }
 
-pub async fn get_one_file_resource_by_doc_filter(
-    doc_filter: Document,
-) -> mongodb::error::Result<Option<FileResource>> {
-    file_resource_dao::get_one_file_resource_by_doc_filter(doc_filter).await
+#[instrument(skip_all, fields(file.id = %file_resource_id))]
+pub async fn restore_file_resource(
+    repos: &Repositories,
+    file_resource_id: &str,
+    operator: &str,
+    now: i64,
+) -> RepositoryResult<bool> {
+    repos
+        .file_resource
+        .restore_file_resource(file_resource_id, operator, now)
+        .await
 }
 
 pub async fn delete_file(filepath: &str) {
@@ -77,5 +261,10 @@ pub async fn delete_file(filepath: &str) {
 }
 
 pub fn get_local_filepath(face_info_id: &str, filename: &str) -> String {
-    format!("{SAVE_DIR}/{face_info_id}-{filename}")
+    format!(
+        "{}/{}-{}",
+        config::get().storage.save_dir,
+        face_info_id,
+        filename
+    )
 }
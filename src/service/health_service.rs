@@ -0,0 +1,95 @@
+//! The health of the dependencies, for the readiness probe.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::future::Future;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config;
+use crate::repository::Repositories;
+use crate::resource::id_generator;
+use crate::shutdown;
+
+/// A dependency taking longer is considered down
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ComponentHealth {
+    pub status: HealthStatus,
+    /// How long the check took, in milliseconds
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Readiness {
+    /// Up only if all the components are up
+    pub status: HealthStatus,
+    pub components: BTreeMap<String, ComponentHealth>,
+}
+
+/// Checks the database, the file storage and the id generator concurrently,
+/// the server is never ready once the shutdown starts.
+pub async fn check_readiness(repos: &Repositories) -> Readiness {
+    let (database, file_storage, id_generator) = futures_util::join!(
+        check_component(async { repos.database.ping().await.map_err(|err| err.to_string()) }),
+        check_component(async { check_file_storage(&config::get().storage.save_dir) }),
+        check_component(async { id_generator::try_get_id().map(|_| ()) }),
+    );
+
+    let components = BTreeMap::from([
+        ("database".to_string(), database),
+        ("file_storage".to_string(), file_storage),
+        ("id_generator".to_string(), id_generator),
+    ]);
+    let status = if !shutdown::is_stopping()
+        && components
+            .values()
+            .all(|component| component.status == HealthStatus::Up)
+    {
+        HealthStatus::Up
+    } else {
+        HealthStatus::Down
+    };
+    Readiness { status, components }
+}
+
+async fn check_component<F>(check: F) -> ComponentHealth
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let started_on = Instant::now();
+    let res = match actix_rt::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(res) => res,
+        Err(_) => Err(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+    };
+    ComponentHealth {
+        status: if res.is_ok() {
+            HealthStatus::Up
+        } else {
+            HealthStatus::Down
+        },
+        latency_ms: started_on.elapsed().as_millis() as u64,
+        error: res.err(),
+    }
+}
+
+/// Writes and removes a probe file in the directory of the uploaded files.
+fn check_file_storage(save_dir: &str) -> Result<(), String> {
+    let probe_path = Path::new(save_dir).join(format!(".readyz-{}", uuid::Uuid::new_v4()));
+    fs::write(&probe_path, b"ok")
+        .map_err(|err| format!("{} is not writable: {}", save_dir, err))?;
+    fs::remove_file(&probe_path).map_err(|err| err.to_string())
+}
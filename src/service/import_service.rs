@@ -0,0 +1,341 @@
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::dto::face_info_dto::NewFaceInfo;
+use crate::entity::face_info::FaceStatus;
+use crate::repository::Repositories;
+use crate::service::{face_info_service, file_resource_service};
+use crate::{config, resource};
+
+/// A row of the import manifest, the image_path is relative to the import directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestRow {
+    pub star_name: String,
+    pub image_path: String,
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    UnsupportedFormat(String),
+    Io(io::Error),
+    Csv(csv::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::UnsupportedFormat(path) => write!(
+                f,
+                "unsupported manifest format, expect .csv or .json: {}",
+                path
+            ),
+            ManifestError::Io(err) => write!(f, "failed to read manifest: {}", err),
+            ManifestError::Csv(err) => write!(f, "invalid csv manifest: {}", err),
+            ManifestError::Json(err) => write!(f, "invalid json manifest: {}", err),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportOptions {
+    pub creator: String,
+    /// Approves the imported face_infos instead of queueing them for moderation
+    pub approve: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImportRowResult {
+    /// 1-based row number of the manifest
+    pub row: usize,
+    pub star_name: String,
+    pub image_path: String,
+    pub face_info_id: Option<String>,
+    pub file_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ImportReport {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+/// Parses the manifest by its extension, either csv with a
+/// `star_name,image_path` header or a json array of rows.
+pub fn parse_manifest(manifest: &Path) -> Result<Vec<ManifestRow>, ManifestError> {
+    let extension = manifest
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_lowercase);
+    let file = File::open(manifest).map_err(ManifestError::Io)?;
+    match extension.as_deref() {
+        Some("csv") => parse_csv_manifest(file),
+        Some("json") => parse_json_manifest(file),
+        _ => Err(ManifestError::UnsupportedFormat(
+            manifest.display().to_string(),
+        )),
+    }
+}
+
+pub fn parse_csv_manifest<R: Read>(reader: R) -> Result<Vec<ManifestRow>, ManifestError> {
+    csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader)
+        .deserialize()
+        .collect::<Result<Vec<ManifestRow>, csv::Error>>()
+        .map_err(ManifestError::Csv)
+}
+
+pub fn parse_json_manifest<R: Read>(reader: R) -> Result<Vec<ManifestRow>, ManifestError> {
+    serde_json::from_reader(reader).map_err(ManifestError::Json)
+}
+
+/// Imports the face_infos listed in the manifest, the images are read from the directory.
+///
+/// Every row is validated and deduplicated like an upload, a failed row
+/// doesn't stop the import and is recorded in the report.
+#[instrument(skip_all, fields(manifest = ?manifest))]
+pub async fn import_face_infos(
+    repos: &Repositories,
+    dir: &Path,
+    manifest: &Path,
+    options: &ImportOptions,
+) -> Result<ImportReport, ManifestError> {
+    let rows = parse_manifest(manifest)?;
+    let dir = dir.canonicalize().map_err(ManifestError::Io)?;
+
+    let mut report = ImportReport {
+        total: rows.len(),
+        ..ImportReport::default()
+    };
+    for (idx, row) in rows.into_iter().enumerate() {
+        let mut row_result = ImportRowResult {
+            row: idx + 1,
+            star_name: row.star_name.clone(),
+            image_path: row.image_path.clone(),
+            face_info_id: None,
+            file_id: None,
+            error: None,
+        };
+
+        match import_face_info(repos, &dir, &row, options).await {
+            Ok((file_id, face_info_id)) => {
+                report.succeeded += 1;
+                row_result.file_id = Some(file_id);
+                row_result.face_info_id = Some(face_info_id);
+            }
+            Err((file_id, err)) => {
+                report.failed += 1;
+                warn!("Failed to import row {}, error: {}", row_result.row, err);
+                row_result.file_id = file_id;
+                row_result.error = Some(err);
+            }
+        }
+        report.rows.push(row_result);
+    }
+
+    info!(
+        "Import face_infos finished, total: {}, succeeded: {}, failed: {}",
+        report.total, report.succeeded, report.failed
+    );
+    Ok(report)
+}
+
+/// Imports a row, returns the file_id and face_info_id,
+/// or the file_id if saved and the error.
+async fn import_face_info(
+    repos: &Repositories,
+    dir: &Path,
+    row: &ManifestRow,
+    options: &ImportOptions,
+) -> Result<(String, String), (Option<String>, String)> {
+    // Step 1: Validate the row, by the rules of an upload
+    let file_id = resource::id_generator::get_id().await;
+    let mut new_face_info = NewFaceInfo {
+        star_name: row.star_name.clone(),
+        file_id: file_id.clone(),
+        categories: vec![],
+        creator: options.creator.clone(),
+    };
+    new_face_info
+        .validate()
+        .map_err(|err| (None, format!("invalid row: {}", err)))?;
+    let image_path = resolve_image_path(dir, &row.image_path).map_err(|err| (None, err))?;
+    let file_name = image_path
+        .file_name()
+        .and_then(OsStr::to_str)
+        .map(|f_name| sanitize_filename::sanitize(f_name).replace(' ', "_"))
+        .unwrap_or_default();
+    if file_name.is_empty() {
+        return Err((None, "image file name is empty".to_string()));
+    }
+
+    // Step 2: Copy the image & save file_resource
+    let filepath = file_resource_service::get_local_filepath(&file_id, &file_name);
+    match web::block(move || fs::copy(image_path, filepath)).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(err)) => return Err((None, format!("failed to copy image: {}", err))),
+        Err(err) => return Err((None, format!("failed to copy image: {}", err))),
+    }
+    let file_resource = file_resource_service::save_local_file_resource(
+        repos,
+        &file_id,
+        &file_name,
+        &options.creator,
+    )
+    .await
+    .map_err(|err| (None, err.to_string()))?;
+
+    // Step 3: Save face_info
+    let now = chrono::Utc::now().timestamp();
+    new_face_info.file_id = file_resource.id.clone();
+    let mut face_info = new_face_info.into_face_info(
+        resource::id_generator::get_id().await,
+        config::get().rating.initial_score,
+        now,
+    );
+    if options.approve {
+        face_info.status = FaceStatus::Approved;
+        face_info.moderator = options.creator.clone();
+        face_info.moderation_reason = "approved on import".to_string();
+        face_info.moderated_on = now;
+    }
+    face_info_service::add_face_info(repos, &face_info)
+        .await
+        .map_err(|err| (Some(file_resource.id.clone()), err.to_string()))?;
+
+    Ok((file_resource.id, face_info.id))
+}
+
+/// Resolves the image path inside the import directory.
+fn resolve_image_path(dir: &Path, image_path: &str) -> Result<PathBuf, String> {
+    if image_path.is_empty() {
+        return Err("image path is empty".to_string());
+    }
+
+    let path = dir
+        .join(image_path)
+        .canonicalize()
+        .map_err(|err| format!("image not found: {}", err))?;
+    if !path.starts_with(dir) {
+        return Err("image path is outside the import directory".to_string());
+    }
+    if !path.is_file() {
+        return Err("image path is not a file".to_string());
+    }
+    Ok(path)
+}
+
+/// Resolves the directory or the manifest of an import over http inside `root`,
+/// see `import.root` of the settings. Both are given by the caller, so neither may
+/// lead out of the root, which is what the caller may read.
+pub fn resolve_import_path(root: &Path, path: &Path) -> Result<PathBuf, String> {
+    let root = root
+        .canonicalize()
+        .map_err(|err| format!("import root not found: {}", err))?;
+    let resolved = root
+        .join(path)
+        .canonicalize()
+        .map_err(|err| format!("{:?} not found: {}", path, err))?;
+    if !resolved.starts_with(&root) {
+        return Err(format!("{:?} is outside the import root", path));
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use dotenv::dotenv;
+
+    use super::*;
+    use crate::dto::MAX_STAR_NAME_LEN;
+
+    #[test]
+    fn test_parse_manifest() {
+        let expected = vec![
+            ManifestRow {
+                star_name: "John Doe".to_string(),
+                image_path: "john.jpg".to_string(),
+            },
+            ManifestRow {
+                star_name: "Jane Doe".to_string(),
+                image_path: "jane/1.png".to_string(),
+            },
+        ];
+
+        let csv = "star_name,image_path\nJohn Doe, john.jpg\nJane Doe,jane/1.png\n";
+        assert_eq!(parse_csv_manifest(csv.as_bytes()).unwrap(), expected);
+
+        let json = r#"[
+            {"star_name": "John Doe", "image_path": "john.jpg"},
+            {"star_name": "Jane Doe", "image_path": "jane/1.png"}
+        ]"#;
+        assert_eq!(parse_json_manifest(json.as_bytes()).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_resolve_image_path() {
+        let dir = Path::new("./src").canonicalize().unwrap();
+
+        assert!(resolve_image_path(&dir, "main.rs").is_ok());
+        assert!(resolve_image_path(&dir, "../Cargo.toml").is_err());
+        assert!(resolve_image_path(&dir, "not_exists.jpg").is_err());
+        assert!(resolve_image_path(&dir, "service").is_err());
+    }
+
+    #[test]
+    fn test_resolve_import_path() {
+        let root = Path::new("./src");
+
+        assert!(resolve_import_path(root, Path::new("service")).is_ok());
+        assert!(resolve_import_path(root, Path::new("service/../main.rs")).is_ok());
+        assert!(resolve_import_path(root, Path::new("../Cargo.toml")).is_err());
+        assert!(resolve_import_path(root, Path::new("/etc")).is_err());
+        assert!(resolve_import_path(Path::new("./not_exists"), Path::new("service")).is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_import_face_info_with_long_star_name() {
+        dotenv().ok();
+        let repos = Repositories::in_memory();
+        let manifest = std::env::temp_dir().join(format!(
+            "manifest_{}.json",
+            resource::id_generator::get_id().await
+        ));
+        let star_name = "a".repeat(MAX_STAR_NAME_LEN as usize + 1);
+        let rows = vec![ManifestRow {
+            star_name: star_name.clone(),
+            image_path: "main.rs".to_string(),
+        }];
+        fs::write(&manifest, serde_json::to_vec(&rows).unwrap()).unwrap();
+        let options = ImportOptions {
+            creator: "admin".to_string(),
+            approve: true,
+        };
+
+        let report = import_face_infos(&repos, Path::new("./src"), &manifest, &options)
+            .await
+            .unwrap();
+        fs::remove_file(&manifest).unwrap();
+        assert_eq!(report.total, 1);
+        assert_eq!(report.failed, 1);
+        let row = &report.rows[0];
+        assert_eq!(row.row, 1);
+        assert_eq!(row.star_name, star_name);
+        assert!(row.file_id.is_none());
+        assert!(row.error.as_ref().unwrap().contains("star_name"));
+        assert_eq!(repos.face_info.count_all_face_infos().await.unwrap(), 0);
+    }
+}
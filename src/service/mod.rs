@@ -1,8 +1,18 @@
+use crate::resource;
 use crate::service::file_resource_service::init_local_directory;
 
+pub mod admin_service;
+pub mod archive_service;
+pub mod category_service;
 pub mod face_info_service;
 pub mod file_resource_service;
+pub mod health_service;
+pub mod import_service;
+pub mod moderation_service;
+pub mod report_service;
+pub mod season_service;
 
-pub async fn init_file_service() {
-    init_local_directory().await;
+/// Creates the directory of the uploaded files, retried with backoff until created.
+pub async fn init_file_service() -> std::io::Result<()> {
+    resource::retry("file storage", init_local_directory).await
 }
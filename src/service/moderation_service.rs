@@ -0,0 +1,81 @@
+use tracing::instrument;
+
+use crate::entity::face_info::{FaceInfo, FaceStatus};
+use crate::error::{AppError, AppResult};
+use crate::repository::{Repositories, RepositoryResult};
+use crate::service::face_info_service::FaceInfoChange;
+use crate::service::{face_info_service, report_service};
+
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+pub const MAX_PAGE_SIZE: i64 = 100;
+/// The last page of the listings, the deeper ones are refused
+pub const MAX_PAGE: u64 = 10_000;
+
+/// Checks the documents before the page can be counted, and skipped by the storage.
+pub fn check_page(page: u64, page_size: i64) -> AppResult<()> {
+    page.checked_mul(page_size.max(0) as u64)
+        .filter(|skip| i64::try_from(*skip).is_ok())
+        .map(|_| ())
+        .ok_or_else(|| AppError::BadRequest("page is out of range!".to_string()))
+}
+
+/// Lists the face_infos in the status, the oldest comes first.
+#[instrument(skip_all, fields(status = status.as_str(), page))]
+pub async fn get_face_infos_by_status(
+    repos: &Repositories,
+    status: FaceStatus,
+    page: u64,
+    page_size: i64,
+) -> RepositoryResult<Vec<FaceInfo>> {
+    repos
+        .face_info
+        .get_face_infos_by_status(status, page, page_size)
+        .await
+}
+
+/// Moves the face_info into the status and audits the transition,
+/// approving or rejecting it resolves its reports.
+///
+/// The update only matches if the face_info is still in its old status.
+#[instrument(skip_all, fields(face_info.id = %face_info.id, status = status.as_str()))]
+pub async fn moderate_face_info(
+    repos: &Repositories,
+    face_info: &FaceInfo,
+    status: FaceStatus,
+    moderator: &str,
+    reason: &str,
+    now: i64,
+) -> RepositoryResult<bool> {
+    let updated = repos
+        .face_info
+        .update_face_info_status(
+            &face_info.id,
+            face_info.status,
+            status,
+            moderator,
+            reason,
+            now,
+        )
+        .await?;
+
+    if updated {
+        face_info_service::add_face_info_audits(
+            repos,
+            &face_info.id,
+            &[FaceInfoChange {
+                field: "status",
+                old_value: face_info.status.as_str().to_string(),
+                new_value: status.as_str().to_string(),
+            }],
+            moderator,
+            now,
+        )
+        .await?;
+
+        if status != FaceStatus::Pending {
+            report_service::resolve_face_reports(repos, &face_info.id, moderator, now).await?;
+        }
+    }
+
+    Ok(updated)
+}
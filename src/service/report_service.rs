@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+use utoipa::ToSchema;
+
+use crate::entity::face_info::{FaceInfo, FaceStatus};
+use crate::entity::face_report::{FaceReport, FaceReportSummary, ReportReason};
+use crate::repository::{get_page_skip, Repositories, RepositoryResult};
+use crate::service::moderation_service;
+use crate::{config, resource};
+
+/// The operator recorded when a face_info is hidden by reports.
+pub const REPORT_MODERATOR: &str = "system";
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReportResult {
+    /// false if the reporter has already reported the face_info
+    pub created: bool,
+    pub report_cnt: u64,
+    pub hidden: bool,
+}
+
+/// Gets the number of distinct reporters which hides a face_info from the settings.
+pub fn get_report_hide_threshold() -> u64 {
+    config::get().moderation.report_hide_threshold
+}
+
+/// Reports the face_info, and moves it back to moderation once
+/// enough distinct reporters have reported it.
+///
+/// The reporter is derived by the server, e.g. the peer address, so it can't be
+/// forged by the request, the creator is the name given by the voter.
+#[instrument(skip_all, fields(face_info.id = %face_info.id))]
+pub async fn report_face_info(
+    repos: &Repositories,
+    face_info: &FaceInfo,
+    reporter: &str,
+    creator: &str,
+    reason: ReportReason,
+    comment: &str,
+    now: i64,
+) -> RepositoryResult<ReportResult> {
+    // Step 1: Add the report, once per reporter
+    let created = repos
+        .face_report
+        .add_face_report_if_absent(&FaceReport {
+            id: resource::id_generator::get_id().await,
+            face_info_id: face_info.id.clone(),
+            reporter: reporter.to_string(),
+            reason,
+            comment: comment.to_string(),
+            creator: creator.to_string(),
+            created_on: now,
+            ..FaceReport::default()
+        })
+        .await?;
+
+    // Step 2: Hide the face_info if the threshold is reached
+    let report_cnt = repos
+        .face_report
+        .count_unresolved_face_reports(&face_info.id)
+        .await?;
+    let threshold = get_report_hide_threshold();
+    let mut hidden = false;
+    if threshold > 0 && report_cnt >= threshold && face_info.status == FaceStatus::Approved {
+        hidden = moderation_service::moderate_face_info(
+            repos,
+            face_info,
+            FaceStatus::Pending,
+            REPORT_MODERATOR,
+            &format!("hidden after {} reports", report_cnt),
+            now,
+        )
+        .await?;
+        if hidden {
+            info!(
+                "FaceInfo hidden by reports, face_info_id: {:?}, report_cnt: {}",
+                face_info.id, report_cnt
+            );
+        }
+    }
+
+    Ok(ReportResult {
+        created,
+        report_cnt,
+        hidden,
+    })
+}
+
+/// Marks the unresolved reports of the face_info as resolved.
+#[instrument(skip_all, fields(face_info.id = %face_info_id))]
+pub async fn resolve_face_reports(
+    repos: &Repositories,
+    face_info_id: &str,
+    moderator: &str,
+    now: i64,
+) -> RepositoryResult<u64> {
+    repos
+        .face_report
+        .resolve_face_reports(face_info_id, moderator, now)
+        .await
+}
+
+#[instrument(skip_all, fields(page))]
+pub async fn get_reported_face_infos(
+    repos: &Repositories,
+    page: u64,
+    page_size: i64,
+) -> RepositoryResult<Vec<FaceReportSummary>> {
+    repos
+        .face_report
+        .get_unresolved_face_report_summaries(get_page_skip(page, page_size), page_size)
+        .await
+}
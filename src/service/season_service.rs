@@ -0,0 +1,429 @@
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+use utoipa::ToSchema;
+
+use crate::entity::face_info::FaceInfoOrder;
+use crate::entity::season::Season;
+use crate::entity::season_snapshot::SeasonSnapshot;
+use crate::repository::{Repositories, RepositoryResult};
+use crate::{config, resource};
+
+/// The number of face_infos snapshotted at a time.
+const SNAPSHOT_BATCH_SIZE: i64 = 1000;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SeasonStart {
+    pub season: Season,
+    /// The season which ended, none before the first season
+    pub ended_season_id: Option<String>,
+    pub snapshot_cnt: u64,
+    pub reset_cnt: u64,
+    /// True if it finished a start which failed midway instead of starting another season
+    pub resumed: bool,
+}
+
+/// Gets the id of the current season, which the votes are tagged with,
+/// empty before the first season.
+#[instrument(skip_all)]
+pub async fn get_current_season_id(repos: &Repositories) -> RepositoryResult<String> {
+    Ok(repos
+        .season
+        .get_current_season()
+        .await?
+        .map(|season| season.id)
+        .unwrap_or_default())
+}
+
+/// Starts a new season, returns none if the current season was ended concurrently.
+///
+/// The standings of the current season are archived as its snapshots before it ends, then the
+/// new season is added and the scores are moved toward `rating.initial_score` by `soft_reset`,
+/// 0.0 keeps them as they are. The votes cast while the season starts are tagged with no season.
+///
+/// Every step may run again, so a start which failed midway is resumed by the next one:
+/// the snapshots are replaced, a season ended without a successor gets the new season,
+/// and a pending soft reset of the current season is applied instead of starting another.
+/// The soft reset is applied at once, a start failing within it may reset some scores twice.
+#[instrument(skip_all, fields(name, soft_reset))]
+pub async fn start_season(
+    repos: &Repositories,
+    name: &str,
+    soft_reset: f64,
+    creator: &str,
+    now: i64,
+) -> RepositoryResult<Option<SeasonStart>> {
+    // Step 1: Snapshot and end the current season, once
+    let seasons = repos.season.get_seasons().await?;
+    let (ended_season_id, snapshot_cnt) = match seasons.iter().find(|season| season.ended_on == 0) {
+        Some(current_season) if current_season.reset_pending => {
+            return resume_season_reset(repos, &seasons, current_season).await;
+        }
+        Some(current_season) => {
+            let snapshot_cnt = snapshot_face_infos(repos, &current_season.id, now).await?;
+            if !repos.season.end_season(&current_season.id, now).await? {
+                return Ok(None);
+            }
+            (Some(current_season.id.clone()), snapshot_cnt)
+        }
+        // The last start ended the season but failed to add the next one,
+        // the ended season was snapshotted before it ended
+        None if !seasons.is_empty() => (Some(seasons[0].id.clone()), 0),
+        // The standings before the first season
+        None => (None, snapshot_face_infos(repos, "", now).await?),
+    };
+
+    // Step 2: Add the new season, with its soft reset pending
+    let mut season = Season {
+        id: resource::id_generator::get_id().await,
+        name: name.to_string(),
+        soft_reset,
+        creator: creator.to_string(),
+        started_on: now,
+        reset_pending: soft_reset > 0.0,
+        ..Season::default()
+    };
+    repos.season.add_season(&season).await?;
+
+    // Step 3: Soft reset the scores
+    let reset_cnt = reset_face_info_scores(repos, &mut season).await?;
+    info!(
+        "Season started, id: {}, ended: {:?}, snapshots: {}, reset: {}",
+        season.id, ended_season_id, snapshot_cnt, reset_cnt
+    );
+
+    Ok(Some(SeasonStart {
+        season,
+        ended_season_id,
+        snapshot_cnt,
+        reset_cnt,
+        resumed: false,
+    }))
+}
+
+/// Applies the soft reset a start of the current season failed to apply.
+async fn resume_season_reset(
+    repos: &Repositories,
+    seasons: &[Season],
+    current_season: &Season,
+) -> RepositoryResult<Option<SeasonStart>> {
+    let mut season = current_season.clone();
+    let reset_cnt = reset_face_info_scores(repos, &mut season).await?;
+    let ended_season_id = seasons
+        .iter()
+        .find(|season| season.ended_on > 0)
+        .map(|season| season.id.clone());
+    info!(
+        "Season start resumed, id: {}, ended: {:?}, reset: {}",
+        season.id, ended_season_id, reset_cnt
+    );
+
+    Ok(Some(SeasonStart {
+        season,
+        ended_season_id,
+        snapshot_cnt: 0,
+        reset_cnt,
+        resumed: true,
+    }))
+}
+
+/// Snapshots the approved face_infos as the standings of the season,
+/// returns the number of snapshots.
+async fn snapshot_face_infos(
+    repos: &Repositories,
+    season_id: &str,
+    now: i64,
+) -> RepositoryResult<u64> {
+    let mut snapshot_cnt = 0;
+    let mut after_id = String::new();
+    loop {
+        let face_infos = repos
+            .face_info
+            .get_approved_face_infos_after(&after_id, SNAPSHOT_BATCH_SIZE)
+            .await?;
+        let last_id = match face_infos.last() {
+            Some(face_info) => face_info.id.clone(),
+            None => break,
+        };
+        snapshot_cnt += face_infos.len() as u64;
+        let season_snapshots = face_infos
+            .iter()
+            .map(|face_info| SeasonSnapshot::of(season_id, face_info, now))
+            .collect();
+        repos
+            .season_snapshot
+            .save_season_snapshots(season_snapshots)
+            .await?;
+        after_id = last_id;
+    }
+    Ok(snapshot_cnt)
+}
+
+/// Applies the pending soft reset of the season, returns the number of face_infos reset.
+async fn reset_face_info_scores(
+    repos: &Repositories,
+    season: &mut Season,
+) -> RepositoryResult<u64> {
+    if !season.reset_pending {
+        return Ok(0);
+    }
+
+    let initial_score = config::get().rating.initial_score;
+    let reset_cnt = repos
+        .face_info
+        .soft_reset_face_info_scores(initial_score, season.soft_reset)
+        .await?;
+    repos.season.finish_season_reset(&season.id).await?;
+    season.reset_pending = false;
+    Ok(reset_cnt)
+}
+
+/// Gets a page of the standings of the season, the highest score first.
+///
+/// The ended seasons are read from their snapshots, the current one from the live scores.
+#[instrument(skip_all, fields(season.id = %season.id, page, page_size))]
+pub async fn get_season_leaderboard(
+    repos: &Repositories,
+    season: &Season,
+    page: u64,
+    page_size: i64,
+    now: i64,
+) -> RepositoryResult<Vec<SeasonSnapshot>> {
+    if season.ended_on > 0 {
+        return repos
+            .season_snapshot
+            .get_season_snapshots_page(&season.id, page, page_size)
+            .await;
+    }
+
+    let face_infos = repos
+        .face_info
+        .get_approved_face_infos(FaceInfoOrder::Score, page, page_size)
+        .await?;
+    Ok(face_infos
+        .iter()
+        .map(|face_info| SeasonSnapshot::of(&season.id, face_info, now))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use dotenv::dotenv;
+
+    use super::*;
+    use crate::entity::face_info::{FaceInfo, FaceStatus};
+    use crate::repository::{SeasonRepository, SeasonSnapshotRepository};
+
+    /// Fails adding the seasons while `failing` is set.
+    struct FlakySeasonRepository {
+        inner: Arc<dyn SeasonRepository>,
+        failing: AtomicBool,
+    }
+
+    #[async_trait]
+    impl SeasonRepository for FlakySeasonRepository {
+        async fn add_season(&self, season: &Season) -> RepositoryResult<()> {
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(sqlx::Error::PoolTimedOut.into());
+            }
+            self.inner.add_season(season).await
+        }
+
+        async fn get_season_by_id(&self, id: &str) -> RepositoryResult<Option<Season>> {
+            self.inner.get_season_by_id(id).await
+        }
+
+        async fn get_current_season(&self) -> RepositoryResult<Option<Season>> {
+            self.inner.get_current_season().await
+        }
+
+        async fn get_seasons(&self) -> RepositoryResult<Vec<Season>> {
+            self.inner.get_seasons().await
+        }
+
+        async fn end_season(&self, id: &str, now: i64) -> RepositoryResult<bool> {
+            self.inner.end_season(id, now).await
+        }
+
+        async fn finish_season_reset(&self, id: &str) -> RepositoryResult<bool> {
+            self.inner.finish_season_reset(id).await
+        }
+
+        async fn delete_seasons_by_ids(&self, ids: &[String]) -> RepositoryResult<u64> {
+            self.inner.delete_seasons_by_ids(ids).await
+        }
+    }
+
+    /// Fails saving the snapshots while `failing` is set.
+    struct FlakySeasonSnapshotRepository {
+        inner: Arc<dyn SeasonSnapshotRepository>,
+        failing: AtomicBool,
+    }
+
+    #[async_trait]
+    impl SeasonSnapshotRepository for FlakySeasonSnapshotRepository {
+        async fn save_season_snapshots(
+            &self,
+            season_snapshots: Vec<SeasonSnapshot>,
+        ) -> RepositoryResult<()> {
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(sqlx::Error::PoolTimedOut.into());
+            }
+            self.inner.save_season_snapshots(season_snapshots).await
+        }
+
+        async fn get_season_snapshots_page(
+            &self,
+            season_id: &str,
+            page: u64,
+            page_size: i64,
+        ) -> RepositoryResult<Vec<SeasonSnapshot>> {
+            self.inner
+                .get_season_snapshots_page(season_id, page, page_size)
+                .await
+        }
+
+        async fn get_all_season_snapshots(&self) -> RepositoryResult<Vec<SeasonSnapshot>> {
+            self.inner.get_all_season_snapshots().await
+        }
+
+        async fn count_all_season_snapshots(&self) -> RepositoryResult<u64> {
+            self.inner.count_all_season_snapshots().await
+        }
+
+        async fn delete_season_snapshots_by_season_ids(
+            &self,
+            season_ids: &[String],
+        ) -> RepositoryResult<u64> {
+            self.inner
+                .delete_season_snapshots_by_season_ids(season_ids)
+                .await
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_start_season_after_failure() {
+        dotenv().ok();
+        let mut repos = Repositories::in_memory();
+        let seasons = Arc::new(FlakySeasonRepository {
+            inner: repos.season.clone(),
+            failing: AtomicBool::new(false),
+        });
+        let season_snapshots = Arc::new(FlakySeasonSnapshotRepository {
+            inner: repos.season_snapshot.clone(),
+            failing: AtomicBool::new(true),
+        });
+        repos.season = seasons.clone();
+        repos.season_snapshot = season_snapshots.clone();
+        for id in ["1", "2"] {
+            repos
+                .face_info
+                .add_face_info(&FaceInfo {
+                    id: id.to_string(),
+                    score: 1600.0,
+                    status: FaceStatus::Approved,
+                    ..FaceInfo::default()
+                })
+                .await
+                .unwrap();
+        }
+        repos
+            .season
+            .add_season(&Season {
+                id: "s1".to_string(),
+                started_on: 1,
+                ..Season::default()
+            })
+            .await
+            .unwrap();
+
+        // Step 1: A start failing to snapshot keeps the current season
+        assert!(start_season(&repos, "s2", 0.0, "admin", 2).await.is_err());
+        let current_season = repos.season.get_current_season().await.unwrap().unwrap();
+        assert_eq!(current_season.id, "s1");
+
+        // Step 2: A start failing to add the new season leaves the current one ended
+        season_snapshots.failing.store(false, Ordering::SeqCst);
+        seasons.failing.store(true, Ordering::SeqCst);
+        assert!(start_season(&repos, "s2", 0.5, "admin", 3).await.is_err());
+        assert!(repos.season.get_current_season().await.unwrap().is_none());
+
+        // Step 3: The next start resumes it, without snapshotting the standings again
+        seasons.failing.store(false, Ordering::SeqCst);
+        let season_start = start_season(&repos, "s2", 0.5, "admin", 4)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(season_start.ended_season_id.as_deref(), Some("s1"));
+        assert_eq!(season_start.snapshot_cnt, 0);
+        assert_eq!(season_start.reset_cnt, 2);
+        assert!(!season_start.resumed);
+        assert!(!season_start.season.reset_pending);
+
+        let snapshots = repos
+            .season_snapshot
+            .get_season_snapshots_page("s1", 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert!(snapshots.iter().all(|snapshot| snapshot.score == 1600.0));
+        let snapshots = repos
+            .season_snapshot
+            .get_season_snapshots_page("", 0, 10)
+            .await
+            .unwrap();
+        assert!(snapshots.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_start_season_resumes_reset() {
+        dotenv().ok();
+        let repos = Repositories::in_memory();
+        repos
+            .face_info
+            .add_face_info(&FaceInfo {
+                id: "1".to_string(),
+                score: 1600.0,
+                status: FaceStatus::Approved,
+                ..FaceInfo::default()
+            })
+            .await
+            .unwrap();
+        for season in [
+            Season {
+                id: "s1".to_string(),
+                started_on: 1,
+                ended_on: 2,
+                ..Season::default()
+            },
+            // A start failed before the soft reset
+            Season {
+                id: "s2".to_string(),
+                soft_reset: 1.0,
+                started_on: 2,
+                reset_pending: true,
+                ..Season::default()
+            },
+        ] {
+            repos.season.add_season(&season).await.unwrap();
+        }
+
+        let season_start = start_season(&repos, "s3", 0.0, "admin", 3)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(season_start.resumed);
+        assert_eq!(season_start.season.id, "s2");
+        assert_eq!(season_start.ended_season_id.as_deref(), Some("s1"));
+        assert_eq!(season_start.reset_cnt, 1);
+
+        let season = repos.season.get_current_season().await.unwrap().unwrap();
+        assert_eq!(season.id, "s2");
+        assert!(!season.reset_pending);
+        let face_info = repos.face_info.get_face_info_by_id("1").await.unwrap();
+        assert_eq!(face_info.unwrap().score, config::get().rating.initial_score);
+    }
+}
@@ -0,0 +1,215 @@
+//! The coordinated shutdown on SIGTERM or SIGINT.
+//!
+//! The server stops accepting connections, the new votes and uploads are refused while
+//! the in-flight ones are drained within `server.shutdown_timeout_secs`, then the server
+//! stops, the background jobs are stopped, and the partially written uploads are removed.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use actix_web::dev::ServerHandle;
+use lazy_static::lazy_static;
+use tokio::sync::{watch, Notify};
+use tokio::task::JoinHandle;
+
+use crate::config;
+use crate::service::file_resource_service;
+
+lazy_static! {
+    static ref SHUTDOWN: Shutdown = Shutdown::default();
+    /// The background jobs, awaited once stopped
+    static ref BACKGROUND_JOBS: Mutex<Vec<(&'static str, JoinHandle<()>)>> = Mutex::new(vec![]);
+}
+
+/// The in-flight writes and whether the shutdown has started.
+struct Shutdown {
+    in_flight: AtomicUsize,
+    drained: Notify,
+    stopping: watch::Sender<bool>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Shutdown {
+            in_flight: AtomicUsize::new(0),
+            drained: Notify::new(),
+            stopping: watch::Sender::new(false),
+        }
+    }
+}
+
+impl Shutdown {
+    fn is_stopping(&self) -> bool {
+        *self.stopping.borrow()
+    }
+
+    fn track_in_flight(&self) -> Option<InFlight<'_>> {
+        // Counted before checked, so the drain never misses a write let in
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let in_flight = InFlight { shutdown: self };
+        if self.is_stopping() {
+            None
+        } else {
+            Some(in_flight)
+        }
+    }
+
+    /// Refuses the new writes and waits for the in-flight ones,
+    /// returns the count still in flight after the timeout.
+    async fn drain(&self, timeout: Duration) -> usize {
+        self.stopping.send_replace(true);
+        let drained = async {
+            loop {
+                // Registered before checked, so the last write's notification is never missed
+                let notified = self.drained.notified();
+                if self.in_flight.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        let _ = actix_rt::time::timeout(timeout, drained).await;
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    async fn stopped(&self) {
+        let _ = self
+            .stopping
+            .subscribe()
+            .wait_for(|stopping| *stopping)
+            .await;
+    }
+}
+
+/// A vote or an upload in flight, drained on shutdown until dropped.
+pub struct InFlight<'a> {
+    shutdown: &'a Shutdown,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if self.shutdown.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.shutdown.drained.notify_waiters();
+        }
+    }
+}
+
+/// Whether the shutdown has started, the server is not ready from then on.
+pub fn is_stopping() -> bool {
+    SHUTDOWN.is_stopping()
+}
+
+/// Tracks a vote or an upload until the guard is dropped,
+/// None if the shutdown has started and the write must be refused.
+pub fn track_in_flight() -> Option<InFlight<'static>> {
+    SHUTDOWN.track_in_flight()
+}
+
+/// Spawns a job running in the background until the shutdown starts.
+pub fn spawn_background_job<F>(name: &'static str, job: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    let handle = actix_rt::spawn(async move {
+        futures_util::future::select(Box::pin(job), Box::pin(SHUTDOWN.stopped())).await;
+    });
+    BACKGROUND_JOBS.lock().unwrap().push((name, handle));
+}
+
+async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminations) => {
+                let terminated = Box::pin(async move { terminations.recv().await });
+                futures_util::future::select(terminated, Box::pin(tokio::signal::ctrl_c())).await;
+                return;
+            }
+            Err(err) => error!("Failed to listen to SIGTERM, error: {:?}", err),
+        }
+    }
+    if let Err(err) = tokio::signal::ctrl_c().await {
+        error!("Failed to listen to SIGINT, error: {:?}", err);
+    }
+}
+
+/// Waits for SIGTERM or SIGINT, then shuts the server down step by step.
+///
+/// The server is expected to be run with `disable_signals`.
+pub async fn shutdown_on_signal(server: ServerHandle) {
+    wait_for_signal().await;
+    let timeout = Duration::from_secs(config::get().server.shutdown_timeout_secs);
+    info!(
+        "Shutting down, draining the in-flight votes and uploads for up to {}s.",
+        timeout.as_secs()
+    );
+
+    // Step 1: Stop accepting, the kept alive connections are still served
+    server.pause().await;
+
+    // Step 2: Drain the votes and uploads, the new ones are refused
+    match SHUTDOWN.drain(timeout).await {
+        0 => info!("All the in-flight votes and uploads are drained."),
+        cnt => warn!(
+            "{} votes or uploads are still in flight after {}s, they are cut.",
+            cnt,
+            timeout.as_secs()
+        ),
+    }
+
+    // Step 3: Stop the server, the other requests get another `shutdown_timeout_secs`
+    server.stop(true).await;
+
+    // Step 4: Wait for the background jobs, which are stopped by now
+    let jobs = std::mem::take(&mut *BACKGROUND_JOBS.lock().unwrap());
+    for (name, handle) in jobs {
+        if let Err(err) = handle.await {
+            error!("Background job {} failed, error: {:?}", name, err);
+        }
+    }
+
+    // Step 5: Remove the files of the uploads cut
+    match file_resource_service::remove_partial_files() {
+        Ok(0) => {}
+        Ok(cnt) => info!("Removed {} partially written uploads.", cnt),
+        Err(err) => error!("Failed to remove the partial uploads, error: {:?}", err),
+    }
+
+    info!("Shutdown completed.");
+    log::logger().flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_drain() {
+        let shutdown = Shutdown::default();
+
+        // Drained once the last write is done
+        let vote = shutdown.track_in_flight().unwrap();
+        let upload = shutdown.track_in_flight().unwrap();
+        let drain = shutdown.drain(Duration::from_secs(10));
+        let done = async {
+            actix_rt::time::sleep(Duration::from_millis(10)).await;
+            drop(vote);
+            actix_rt::time::sleep(Duration::from_millis(10)).await;
+            drop(upload);
+        };
+        let (left, _) = futures_util::join!(drain, done);
+        assert_eq!(left, 0);
+
+        // The new writes are refused, the stuck ones are left after the timeout
+        assert!(shutdown.track_in_flight().is_none());
+        assert_eq!(shutdown.in_flight.load(Ordering::SeqCst), 0);
+        let shutdown = Shutdown::default();
+        let _stuck = shutdown.track_in_flight().unwrap();
+        assert_eq!(shutdown.drain(Duration::from_millis(10)).await, 1);
+        shutdown.stopped().await;
+    }
+}
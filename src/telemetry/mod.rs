@@ -0,0 +1,360 @@
+//! The tracing of the requests, exported to an OTLP collector.
+//!
+//! A request is traced by a span per request from `tracing_middleware`, a span per
+//! service call from `#[instrument]`, and a span per repository operation from
+//! `dao::start_operation`. The log lines carry the trace id, see `logger`.
+//!
+//! The spans are not logged, only the events of the libraries traced by `tracing`,
+//! e.g. the queries of `sqlx`, are forwarded to `logger`.
+
+use std::fmt::{self, Write};
+use std::sync::OnceLock;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::HeaderMap;
+use actix_web::middleware::Next;
+use actix_web::Error;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::{TraceContextExt, TracerProvider};
+use opentelemetry::{global, Context};
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Instrument, Subscriber};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::{Context as LayerContext, Layer, SubscriberExt};
+use tracing_subscriber::Registry;
+
+use crate::config;
+use crate::config::settings::TracingSettings;
+use crate::logger::request_id;
+
+const TRACER_NAME: &str = "facemash-backend";
+
+static TRACER_PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// Installs the tracer, the spans are only exported if `tracing.otlp_endpoint` is set,
+/// but the trace ids are always attached to the logs.
+pub fn init() {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let provider = build_tracer_provider(&config::get().tracing);
+    tracing::subscriber::set_global_default(build_subscriber(&provider)).unwrap();
+    let _ = TRACER_PROVIDER.set(provider);
+}
+
+/// Exports the spans not exported yet, called before the process exits.
+pub fn shutdown() {
+    if let Some(provider) = TRACER_PROVIDER.get() {
+        if let Err(err) = provider.shutdown() {
+            error!("Failed to shutdown the tracer, error: {}", err);
+        }
+    }
+}
+
+fn build_tracer_provider(settings: &TracingSettings) -> SdkTracerProvider {
+    let mut builder = SdkTracerProvider::builder()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            settings.sample_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(settings.service_name.clone())
+                .build(),
+        );
+
+    if !settings.otlp_endpoint.is_empty() {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_protocol(Protocol::HttpJson)
+            .with_endpoint(format!(
+                "{}/v1/traces",
+                settings.otlp_endpoint.trim_end_matches('/')
+            ))
+            .build();
+        match exporter {
+            Ok(exporter) => builder = builder.with_batch_exporter(exporter),
+            Err(err) => error!("Failed to build the OTLP exporter, error: {}", err),
+        }
+    }
+    builder.build()
+}
+
+fn build_subscriber(provider: &SdkTracerProvider) -> impl Subscriber + Send + Sync {
+    Registry::default()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer(TRACER_NAME)))
+        .with(LogLayer)
+}
+
+/// Forwards the events to `log`, in the `message key=value` format of `tracing`.
+struct LogLayer;
+
+impl<S: Subscriber> Layer<S> for LogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        let metadata = event.metadata();
+        let level = match *metadata.level() {
+            tracing::Level::ERROR => log::Level::Error,
+            tracing::Level::WARN => log::Level::Warn,
+            tracing::Level::INFO => log::Level::Info,
+            tracing::Level::DEBUG => log::Level::Debug,
+            tracing::Level::TRACE => log::Level::Trace,
+        };
+        let log_metadata = log::Metadata::builder()
+            .level(level)
+            .target(metadata.target())
+            .build();
+        let logger = log::logger();
+        if level > log::max_level() || !logger.enabled(&log_metadata) {
+            return;
+        }
+
+        let mut message = LogMessage::default();
+        event.record(&mut message);
+        logger.log(
+            &log::Record::builder()
+                .metadata(log_metadata)
+                .module_path(metadata.module_path())
+                .file(metadata.file())
+                .line(metadata.line())
+                .args(format_args!("{}", message.0))
+                .build(),
+        );
+    }
+}
+
+#[derive(Default)]
+struct LogMessage(String);
+
+impl Visit for LogMessage {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let sep = if self.0.is_empty() { "" } else { " " };
+        let _ = if field.name() == "message" {
+            write!(self.0, "{}{:?}", sep, value)
+        } else {
+            write!(self.0, "{}{}={:?}", sep, field.name(), value)
+        };
+    }
+}
+
+/// Gets the trace id and the span id of the current span, None if not traced.
+pub fn current_trace_ids() -> Option<(String, String)> {
+    let context = tracing::Span::current().context();
+    let span = context.span();
+    let span_context = span.span_context();
+    if span_context.is_valid() {
+        Some((
+            span_context.trace_id().to_string(),
+            span_context.span_id().to_string(),
+        ))
+    } else {
+        None
+    }
+}
+
+/// Reads the `traceparent` header of the request.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+/// The middleware tracing the request by a span named after its route,
+/// continuing the trace of the caller if propagated,
+/// see `actix_web::middleware::from_fn`.
+pub async fn tracing_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let parent: Context = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(req.headers()))
+    });
+    let route = req
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let span = tracing::info_span!(
+        "http_request",
+        otel.name = %format!("{} {}", req.method(), route),
+        otel.kind = "server",
+        http.request.method = %req.method(),
+        http.route = %route,
+        request.id = request_id::current_request_id().unwrap_or_default(),
+    );
+    let _ = span.set_parent(parent);
+
+    let res = next.call(req).instrument(span.clone()).await;
+
+    let status = match &res {
+        Ok(res) => res.status(),
+        Err(err) => err.as_response_error().status_code(),
+    };
+    span.set_attribute("http.response.status_code", status.as_u16() as i64);
+    res
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use actix_web::middleware::from_fn;
+    use actix_web::{test, web, App, HttpResponse, HttpServer};
+    use dotenv::dotenv;
+
+    use super::*;
+    use crate::controller;
+    use crate::dao::sql::migration;
+    use crate::entity::face_info::{FaceInfo, FaceStatus};
+    use crate::repository::Repositories;
+    use crate::resource;
+
+    /// A stand-in OTLP/HTTP collector, sends the json of every export received.
+    fn start_collector() -> (String, mpsc::Receiver<serde_json::Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        // Its own thread, since the exporter blocks the thread flushing the spans
+        thread::spawn(move || {
+            actix_rt::System::new().block_on(async move {
+                HttpServer::new(move || {
+                    let sender = sender.clone();
+                    App::new().route(
+                        "/v1/traces",
+                        web::post().to(move |body: web::Bytes| {
+                            let sender = sender.clone();
+                            async move {
+                                sender.send(serde_json::from_slice(&body).unwrap()).unwrap();
+                                HttpResponse::Ok().json(serde_json::json!({}))
+                            }
+                        }),
+                    )
+                })
+                .workers(1)
+                .listen(listener)
+                .unwrap()
+                .run()
+                .await
+                .unwrap()
+            })
+        });
+        (endpoint, receiver)
+    }
+
+    #[actix_rt::test]
+    async fn test_trace_vote_face_info() {
+        dotenv().ok();
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let (endpoint, receiver) = start_collector();
+        let provider = build_tracer_provider(&TracingSettings {
+            otlp_endpoint: endpoint,
+            ..TracingSettings::default()
+        });
+        let _subscriber = tracing::subscriber::set_default(build_subscriber(&provider));
+
+        let pool = resource::sql::connect("sqlite::memory:").await.unwrap();
+        migration::migrate(&pool).await.unwrap();
+        let repos = Repositories::sql(pool);
+        for id in ["1", "2"] {
+            repos
+                .face_info
+                .add_face_info(&FaceInfo {
+                    id: id.to_string(),
+                    status: FaceStatus::Approved,
+                    ..FaceInfo::default()
+                })
+                .await
+                .unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(tracing_middleware))
+                .app_data(web::Data::new(repos))
+                .configure(controller::configure),
+        )
+        .await;
+
+        let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+        let req = test::TestRequest::post()
+            .uri("/vote_face_info")
+            .insert_header((
+                "traceparent",
+                format!("00-{}-00f067aa0ba902b7-01", trace_id),
+            ))
+            .set_json(serde_json::json!({
+                "win_face_info_id": "1",
+                "lose_face_info_id": "2",
+                "voter": "tester",
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        // The spans may be split into several exports, flushed until the server span ends
+        let mut spans = vec![];
+        for _ in 0..100 {
+            if spans
+                .iter()
+                .any(|span: &serde_json::Value| span["name"] == "POST /vote_face_info")
+            {
+                break;
+            }
+            provider.force_flush().unwrap();
+            if let Ok(export) = receiver.recv_timeout(Duration::from_millis(100)) {
+                spans.extend(
+                    export["resourceSpans"][0]["scopeSpans"][0]["spans"]
+                        .as_array()
+                        .unwrap()
+                        .clone(),
+                );
+            }
+        }
+        let span_names = spans
+            .iter()
+            .map(|span| span["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        for name in [
+            "POST /vote_face_info",
+            "get_approved_face_infos_by_ids",
+            "get_face_infos_by_ids face_info",
+            "update_face_info_rating",
+            "update_face_info_rating face_info",
+            "add_rating_logs rating_log",
+        ] {
+            assert!(
+                span_names.contains(&name),
+                "{} not in {:?}",
+                name,
+                span_names
+            );
+        }
+        // All the spans of the request continue the propagated trace
+        assert!(spans
+            .iter()
+            .filter(|span| span["name"] != "add_face_info face_info")
+            .all(|span| span["traceId"] == trace_id));
+
+        let ids_span = spans
+            .iter()
+            .find(|span| span["name"] == "get_face_infos_by_ids face_info")
+            .unwrap();
+        let attributes = ids_span["attributes"].to_string();
+        assert!(attributes.contains("face_info.ids"), "{}", attributes);
+        assert!(
+            attributes.contains("db.response.returned_rows"),
+            "{}",
+            attributes
+        );
+    }
+}
@@ -1 +1,2 @@
 pub mod md5;
+pub mod sha256;
@@ -0,0 +1,27 @@
+use std::fs::File;
+use std::io::{Error, Read};
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+
+pub fn get_bytes_sha256(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(bytes);
+
+    hasher.result_str()
+}
+
+pub async fn get_file_sha256(filepath: &str) -> Result<String, Error> {
+    let mut f = File::open(filepath)?;
+    let mut buffer = Vec::new();
+
+    // read the whole file
+    f.read_to_end(&mut buffer)?;
+
+    Ok(get_bytes_sha256(&buffer))
+}
+
+#[actix_rt::test]
+async fn test_get_file_sha256() {
+    println!("{}", get_file_sha256("./README.md").await.unwrap())
+}